@@ -0,0 +1,33 @@
+//! Chapter 11: reflection and refraction, rendered from the glass sphere and mirror room presets
+//! side by side.
+
+use std::path::PathBuf;
+
+use ray_tracer_challange::camera::Camera;
+use ray_tracer_challange::canvas::comparison::Comparison;
+use ray_tracer_challange::scenes::presets;
+use ray_tracer_challange::settings::OutputSettings;
+
+const SIZE: usize = 240;
+
+fn main() -> color_eyre::Result<()> {
+    let output = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("chapter11.png"), PathBuf::from);
+
+    let mut comparison = Comparison::new(2);
+    for (label, (world, preset)) in [
+        ("refraction", presets::glass_sphere_on_checkerboard()),
+        ("reflection", presets::mirror_room()),
+    ] {
+        let mut camera = Camera::new(SIZE, SIZE, preset.field_of_view);
+        camera.transform = preset.transform;
+        camera.samples_pre_pixel = 1;
+        comparison.render(label, &camera, &world)?;
+    }
+
+    comparison
+        .compose()
+        .save(output, &OutputSettings::default())?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Chapter 2: plots the arc of a projectile fired through gravity and wind onto a canvas.
+
+use std::path::PathBuf;
+
+use ray_tracer_challange::canvas::Canvas;
+use ray_tracer_challange::settings::OutputSettings;
+use ray_tracer_challange::tuple::{Color, Point, Vector};
+
+fn main() -> color_eyre::Result<()> {
+    let output = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("chapter2.png"), PathBuf::from);
+
+    let gravity = Vector::new(0., -0.1, 0.);
+    let wind = Vector::new(-0.01, 0., 0.);
+    let mut position = Point::new(0., 1., 0.);
+    let mut velocity = Vector::new(1., 1.8, 0.).normalize() * 11.25;
+
+    let mut canvas = Canvas::new(900, 550);
+    while position.y > 0. {
+        let (x, y) = (
+            position.x.round(),
+            canvas.height as f32 - position.y.round(),
+        );
+        if x >= 0. && y >= 0. {
+            // The arc may leave the canvas for a while; those points are simply not drawn.
+            let _ = canvas.write_pixel(x as usize, y as usize, Color::new(1., 0.3, 0.3));
+        }
+        position = position + velocity;
+        velocity = velocity + gravity + wind;
+    }
+
+    canvas.save(output, &OutputSettings::default())?;
+    Ok(())
+}
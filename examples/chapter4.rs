@@ -0,0 +1,29 @@
+//! Chapter 4: marks the twelve hours of a clock face by rotating a point around the centre.
+
+use std::f32::consts::PI;
+use std::path::PathBuf;
+
+use ray_tracer_challange::canvas::Canvas;
+use ray_tracer_challange::settings::OutputSettings;
+use ray_tracer_challange::tuple::{Color, Point, Vector};
+
+fn main() -> color_eyre::Result<()> {
+    let output = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("chapter4.png"), PathBuf::from);
+
+    let size = 200;
+    let radius = size as f32 * 3. / 8.;
+    let mut canvas = Canvas::new(size, size);
+    let twelve = Point::new(0., 0., 1.);
+    for hour in 0..12 {
+        let p = twelve
+            .rotate_y(hour as f32 * PI / 6.)
+            .scale(&Vector::new(radius, 0., radius))
+            .translate(&Vector::new(size as f32 / 2., 0., size as f32 / 2.));
+        canvas.write_pixel(p.x as usize, p.z as usize, Color::white())?;
+    }
+
+    canvas.save(output, &OutputSettings::default())?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Chapter 5: casts a ray through every pixel of a wall behind a sphere and paints the pixels
+//! whose rays hit it, leaving the sphere's silhouette.
+
+use std::path::PathBuf;
+
+use ray_tracer_challange::canvas::Canvas;
+use ray_tracer_challange::matrix::Matrix4;
+use ray_tracer_challange::ray::Ray;
+use ray_tracer_challange::settings::OutputSettings;
+use ray_tracer_challange::shape::{Intersection, Shape, Sphere};
+use ray_tracer_challange::tuple::{Color, Point, Vector};
+
+fn main() -> color_eyre::Result<()> {
+    let output = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("chapter5.png"), PathBuf::from);
+
+    let pixels = 200;
+    let wall_z = 10.;
+    let wall_size = 7.;
+    let pixel_size = wall_size / pixels as f32;
+    let eye = Point::new(0., 0., -5.);
+
+    // Squashed and skewed to show that transforms apply to the silhouette.
+    let sphere = Sphere::static_default().set_transform(
+        &Matrix4::identity()
+            .scale(&Vector::new(1., 0.5, 1.))
+            .shear(1., 0., 0., 0., 0., 0.),
+    );
+    let mut canvas = Canvas::new(pixels, pixels);
+    for y in 0..pixels {
+        let world_y = wall_size / 2. - pixel_size * y as f32;
+        for x in 0..pixels {
+            let world_x = -wall_size / 2. + pixel_size * x as f32;
+            let target = Point::new(world_x, world_y, wall_z);
+            let ray = Ray::new(eye, (target - eye).normalize());
+            let xs = sphere.intersect(&ray).unwrap_or_default();
+            if Intersection::get_hit(&xs).is_some() {
+                canvas.write_pixel(x, y, Color::new(1., 0., 0.))?;
+            }
+        }
+    }
+
+    canvas.save(output, &OutputSettings::default())?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Chapter 6: the silhouette from chapter 5 again, now shaded with the Phong model under a single
+//! point light.
+
+use std::path::PathBuf;
+
+use ray_tracer_challange::canvas::Canvas;
+use ray_tracer_challange::light::PointLight;
+use ray_tracer_challange::material::Material;
+use ray_tracer_challange::ray::Ray;
+use ray_tracer_challange::settings::OutputSettings;
+use ray_tracer_challange::shape::{Intersection, Shape, Sphere};
+use ray_tracer_challange::tuple::{Color, Point};
+
+fn main() -> color_eyre::Result<()> {
+    let output = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("chapter6.png"), PathBuf::from);
+
+    let pixels = 200;
+    let wall_z = 10.;
+    let wall_size = 7.;
+    let pixel_size = wall_size / pixels as f32;
+    let eye = Point::new(0., 0., -5.);
+
+    let sphere = Sphere::default_with_material(Material {
+        color: Color::new(1., 0.2, 1.),
+        ..Default::default()
+    });
+    let light = PointLight::new(Point::new(-10., 10., -10.), Color::white());
+    let mut canvas = Canvas::new(pixels, pixels);
+    for y in 0..pixels {
+        let world_y = wall_size / 2. - pixel_size * y as f32;
+        for x in 0..pixels {
+            let world_x = -wall_size / 2. + pixel_size * x as f32;
+            let target = Point::new(world_x, world_y, wall_z);
+            let ray = Ray::new(eye, (target - eye).normalize());
+            let xs = sphere.intersect(&ray).unwrap_or_default();
+            if let Some(hit) = Intersection::get_hit(&xs) {
+                let point = ray.position(hit.t);
+                let normal = hit.object.get_normal(&point);
+                let color = light.calculate_lighting(
+                    hit.object.get_material(),
+                    hit.object,
+                    &point,
+                    &-ray.direction,
+                    &normal,
+                    false,
+                );
+                canvas.write_pixel(x, y, color)?;
+            }
+        }
+    }
+
+    canvas.save(output, &OutputSettings::default())?;
+    Ok(())
+}
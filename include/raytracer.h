@@ -0,0 +1,79 @@
+/*
+ * C interface to the ray tracer, exported when the crate is built with the `capi` feature:
+ *
+ *     cargo rustc --lib --release --features capi --crate-type cdylib
+ *
+ * Worlds and cameras are opaque handles owned by the caller. Free every handle from a `_new`
+ * function with the matching `_free` function. Shapes added to a world are owned by it and freed
+ * by `rt_world_free`. Functions that can fail return RT_OK or one of
+ * the negative RT_* error codes.
+ *
+ * This header is maintained by hand alongside src/capi.rs rather than generated from it; keep
+ * the two in step when either changes.
+ */
+#ifndef RAYTRACER_H
+#define RAYTRACER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RT_OK 0
+#define RT_NULL_POINTER (-1)
+#define RT_SINGULAR_TRANSFORM (-2)
+#define RT_BUFFER_TOO_SMALL (-3)
+#define RT_RENDER_FAILED (-4)
+
+typedef struct RtWorld RtWorld;
+typedef struct RtCamera RtCamera;
+
+typedef struct RtMaterial {
+    float color[3];
+    float ambient;
+    float diffuse;
+    float specular;
+    float shininess;
+    float reflective;
+    float transparency;
+    float refractive_index;
+} RtMaterial;
+
+RtMaterial rt_material_default(void);
+
+/* An empty world with no objects and no lights. */
+RtWorld *rt_world_new(void);
+void rt_world_free(RtWorld *world);
+
+/*
+ * `transform` is 16 floats in row-major order, or NULL for the identity. `material` is NULL
+ * for the default material. The untransformed sphere has radius 1 at the origin and the
+ * untransformed plane is y = 0. A singular transform returns RT_SINGULAR_TRANSFORM and adds
+ * nothing.
+ */
+int rt_world_add_sphere(RtWorld *world, const float *transform, const RtMaterial *material);
+int rt_world_add_plane(RtWorld *world, const float *transform, const RtMaterial *material);
+/* `position` and `intensity` are 3 floats each. */
+int rt_world_add_light(RtWorld *world, const float *position, const float *intensity);
+
+RtCamera *rt_camera_new(size_t width, size_t height, float field_of_view);
+void rt_camera_free(RtCamera *camera);
+/* `from`, `to` and `up` are 3 floats each. */
+int rt_camera_look_at(RtCamera *camera, const float *from, const float *to, const float *up);
+int rt_camera_set_samples(RtCamera *camera, size_t samples);
+
+/*
+ * Renders into `rgba` as 8-bit sRGB RGBA rows, top to bottom. `len` must be at least
+ * width * height * 4 bytes, or RT_BUFFER_TOO_SMALL is returned, as it is when that size
+ * overflows size_t. Returns RT_RENDER_FAILED if the render threads cannot be started
+ * or rendering panics; the contents of `rgba` are then unspecified.
+ */
+int rt_render(RtWorld *world, const RtCamera *camera, uint8_t *rgba, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
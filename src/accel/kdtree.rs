@@ -0,0 +1,395 @@
+use std::cmp::Ordering;
+
+use itertools::Itertools;
+use smallvec::{smallvec, SmallVec};
+
+use crate::bounds::{axis_of, Bounds};
+use crate::ray::Ray;
+use crate::shape::Shape;
+
+const TRAVERSAL_COST: f32 = 1.;
+const INTERSECTION_COST: f32 = 80.;
+const EMPTY_BONUS: f32 = 0.5;
+const MAX_LEAF_OBJECTS: usize = 1;
+const MAX_BAD_REFINES: usize = 3;
+
+enum KdNode {
+    Leaf(Vec<usize>),
+    Interior {
+        axis: usize,
+        split: f32,
+        below: Box<KdNode>,
+        above: Box<KdNode>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum EdgeKind {
+    Start,
+    End,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Edge {
+    position: f32,
+    object: usize,
+    kind: EdgeKind,
+}
+
+pub struct KdTree {
+    objects: Vec<&'static dyn Shape>,
+    unbounded: Vec<usize>,
+    bounds: Bounds,
+    root: KdNode,
+}
+
+impl KdTree {
+    pub fn build(objects: &[&'static dyn Shape]) -> Self {
+        let object_bounds = objects.iter().map(|o| o.get_bounds()).collect_vec();
+        let (bounded, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..objects.len()).partition(|&i| object_bounds[i].is_finite());
+        let bounds = bounded
+            .iter()
+            .fold(Bounds::empty(), |acc, &i| acc.merge(&object_bounds[i]));
+        let max_depth = 1.3f32
+            .mul_add((bounded.len().max(1) as f32).log2(), 8.)
+            .round() as usize;
+        let root = build_node(bounded, &object_bounds, bounds, max_depth, 0);
+
+        Self {
+            objects: objects.to_vec(),
+            unbounded,
+            bounds,
+            root,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    // Whether the tree was built from exactly `objects`, in order.
+    pub fn is_built_from(&self, objects: &[&'static dyn Shape]) -> bool {
+        self.objects.len() == objects.len()
+            && self
+                .objects
+                .iter()
+                .zip(objects)
+                .all(|(a, b)| a.get_id() == b.get_id())
+    }
+
+    // Approximate heap and inline size of the tree in bytes, counting nodes and the object indices
+    // held by leaves but not the objects themselves.
+    pub fn memory_estimate(&self) -> usize {
+        fn node_size(node: &KdNode) -> usize {
+            size_of::<KdNode>()
+                + match node {
+                    KdNode::Leaf(objects) => objects.len() * size_of::<usize>(),
+                    KdNode::Interior { below, above, .. } => node_size(below) + node_size(above),
+                }
+        }
+        size_of::<Self>()
+            + self.objects.len() * size_of::<&dyn Shape>()
+            + self.unbounded.len() * size_of::<usize>()
+            + node_size(&self.root)
+    }
+
+    pub fn candidates(&self, ray: &Ray) -> SmallVec<[&'static dyn Shape; 16]> {
+        let mut indices: SmallVec<[usize; 16]> = self.unbounded.iter().copied().collect();
+
+        if let Some((tmin, tmax)) = self
+            .bounds
+            .intersect(ray)
+            .filter(|_| !self.bounds.is_empty())
+        {
+            let mut stack: SmallVec<[(&KdNode, f32, f32); 32]> =
+                smallvec![(&self.root, tmin, tmax)];
+            while let Some((node, tmin, tmax)) = stack.pop() {
+                match node {
+                    KdNode::Leaf(objects) => indices.extend_from_slice(objects),
+                    KdNode::Interior {
+                        axis,
+                        split,
+                        below,
+                        above,
+                    } => {
+                        let origin = axis_of(&ray.origin, *axis);
+                        let direction = match axis {
+                            0 => ray.direction.x,
+                            1 => ray.direction.y,
+                            _ => ray.direction.z,
+                        };
+
+                        if direction == 0. {
+                            if origin <= *split {
+                                stack.push((below, tmin, tmax));
+                            }
+                            if origin >= *split {
+                                stack.push((above, tmin, tmax));
+                            }
+                            continue;
+                        }
+
+                        let t_split = (split - origin) / direction;
+                        let (first, second) = if direction > 0. {
+                            (below, above)
+                        } else {
+                            (above, below)
+                        };
+                        if t_split >= tmax {
+                            stack.push((first, tmin, tmax));
+                        } else if t_split <= tmin {
+                            stack.push((second, tmin, tmax));
+                        } else {
+                            stack.push((first, tmin, t_split));
+                            stack.push((second, t_split, tmax));
+                        }
+                    }
+                }
+            }
+        }
+
+        indices.sort_unstable();
+        indices.dedup();
+        indices.into_iter().map(|i| self.objects[i]).collect()
+    }
+}
+
+fn build_node(
+    items: Vec<usize>,
+    object_bounds: &[Bounds],
+    node_bounds: Bounds,
+    depth: usize,
+    bad_refines: usize,
+) -> KdNode {
+    if items.len() <= MAX_LEAF_OBJECTS || depth == 0 {
+        return KdNode::Leaf(items);
+    }
+
+    let total_area = node_bounds.surface_area();
+    if total_area <= 0. {
+        return KdNode::Leaf(items);
+    }
+
+    let extent = node_bounds.max - node_bounds.min;
+    let extent = [extent.x, extent.y, extent.z];
+    let leaf_cost = INTERSECTION_COST * items.len() as f32;
+    let mut best: Option<(usize, usize, f32)> = None;
+    let mut edges_per_axis: [Vec<Edge>; 3] = Default::default();
+
+    for (axis, axis_edges) in edges_per_axis.iter_mut().enumerate() {
+        *axis_edges = items
+            .iter()
+            .flat_map(|&object| {
+                let b = object_bounds[object];
+                [
+                    Edge {
+                        position: b.min_at(axis),
+                        object,
+                        kind: EdgeKind::Start,
+                    },
+                    Edge {
+                        position: b.max_at(axis),
+                        object,
+                        kind: EdgeKind::End,
+                    },
+                ]
+            })
+            .sorted_by(|a, b| match a.position.total_cmp(&b.position) {
+                Ordering::Equal => a.kind.cmp(&b.kind),
+                ordering => ordering,
+            })
+            .collect();
+
+        let other0 = extent[(axis + 1) % 3];
+        let other1 = extent[(axis + 2) % 3];
+        let mut below = 0;
+        let mut above = items.len();
+        for (offset, edge) in axis_edges.iter().enumerate() {
+            if edge.kind == EdgeKind::End {
+                above -= 1;
+            }
+
+            let position = edge.position;
+            if position > node_bounds.min_at(axis) && position < node_bounds.max_at(axis) {
+                let below_area = 2.
+                    * (position - node_bounds.min_at(axis))
+                        .mul_add(other0 + other1, other0 * other1);
+                let above_area = 2.
+                    * (node_bounds.max_at(axis) - position)
+                        .mul_add(other0 + other1, other0 * other1);
+                let bonus = if below == 0 || above == 0 {
+                    EMPTY_BONUS
+                } else {
+                    0.
+                };
+                let cost = INTERSECTION_COST.mul_add(
+                    (1. - bonus)
+                        * (below_area / total_area)
+                            .mul_add(below as f32, above_area / total_area * above as f32),
+                    TRAVERSAL_COST,
+                );
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((axis, offset, cost));
+                }
+            }
+
+            if edge.kind == EdgeKind::Start {
+                below += 1;
+            }
+        }
+    }
+
+    let Some((axis, offset, cost)) = best else {
+        return KdNode::Leaf(items);
+    };
+    let bad_refines = if cost > leaf_cost {
+        bad_refines + 1
+    } else {
+        bad_refines
+    };
+    if (cost > 4. * leaf_cost && items.len() < 16) || bad_refines >= MAX_BAD_REFINES {
+        return KdNode::Leaf(items);
+    }
+
+    let edges = &edges_per_axis[axis];
+    let split = edges[offset].position;
+    let below_items = edges[..offset]
+        .iter()
+        .filter(|e| e.kind == EdgeKind::Start)
+        .map(|e| e.object)
+        .collect_vec();
+    let above_items = edges[offset + 1..]
+        .iter()
+        .filter(|e| e.kind == EdgeKind::End)
+        .map(|e| e.object)
+        .collect_vec();
+
+    let mut below_bounds = node_bounds;
+    below_bounds.set_max_at(axis, split);
+    let mut above_bounds = node_bounds;
+    above_bounds.set_min_at(axis, split);
+
+    KdNode::Interior {
+        axis,
+        split,
+        below: Box::new(build_node(
+            below_items,
+            object_bounds,
+            below_bounds,
+            depth - 1,
+            bad_refines,
+        )),
+        above: Box::new(build_node(
+            above_items,
+            object_bounds,
+            above_bounds,
+            depth - 1,
+            bad_refines,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::accel::KdTree;
+    use crate::matrix::Matrix4;
+    use crate::ray::Ray;
+    use crate::shape::{Cube, Plane, Shape, Sphere};
+    use crate::tuple::{Point, Vector};
+    use itertools::Itertools;
+    use pretty_assertions::assert_eq;
+    use rand::{Rng, SeedableRng};
+
+    fn sphere_grid() -> Vec<&'static dyn Shape> {
+        let mut objects: Vec<&'static dyn Shape> = vec![];
+        for x in -5..5 {
+            for z in -5..5 {
+                let s = Sphere::static_default().set_transform(
+                    &Matrix4::identity()
+                        .scale(&Vector::new(0.4, 0.4, 0.4))
+                        .translate(&Vector::new(x as f32, 0., z as f32)),
+                );
+                objects.push(s);
+            }
+        }
+        objects
+    }
+
+    fn intersections(objects: &[&'static dyn Shape], ray: &Ray) -> Vec<f32> {
+        objects
+            .iter()
+            .filter_map(|o| o.intersect(ray))
+            .flatten()
+            .map(|i| i.t)
+            .sorted_by(f32::total_cmp)
+            .collect()
+    }
+
+    #[test]
+    pub fn ray_between_objects_has_no_candidates() {
+        let objects = sphere_grid();
+        let tree = KdTree::build(&objects);
+        let r = Ray::new(Point::new(0.5, 5., 0.5), Vector::new(0., 1., 0.));
+        assert!(tree.candidates(&r).is_empty());
+    }
+
+    #[test]
+    pub fn ray_along_row_only_visits_that_row() {
+        let objects = sphere_grid();
+        let tree = KdTree::build(&objects);
+        let r = Ray::new(Point::new(-10., 0., 2.), Vector::new(1., 0., 0.));
+        let candidates = tree.candidates(&r);
+        assert!(candidates.len() < objects.len() / 2);
+        assert_eq!(intersections(&candidates, &r).len(), 20);
+    }
+
+    #[test]
+    pub fn unbounded_objects_are_always_candidates() {
+        let mut objects = sphere_grid();
+        let plane = Plane::static_default();
+        objects.push(plane);
+        let tree = KdTree::build(&objects);
+        let r = Ray::new(Point::new(0.5, 5., 0.5), Vector::new(0., 1., 0.));
+        let candidates = tree.candidates(&r);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].get_id(), plane.get_id());
+    }
+
+    #[test]
+    pub fn tree_finds_same_intersections_as_brute_force() {
+        let mut objects = sphere_grid();
+        let cube = Cube::static_default();
+        cube.set_transform(
+            Matrix4::identity()
+                .scale(&Vector::new(3., 0.1, 0.1))
+                .translate(&Vector::new(0., 1., 0.)),
+        );
+        objects.push(cube);
+        let tree = KdTree::build(&objects);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        for _ in 0..500 {
+            let origin = Point::new(
+                rng.gen_range(-8.0..8.0),
+                rng.gen_range(-3.0..3.0),
+                rng.gen_range(-8.0..8.0),
+            );
+            let direction = Vector::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize();
+            let r = Ray::new(origin, direction);
+            assert_eq!(
+                intersections(&tree.candidates(&r), &r),
+                intersections(&objects, &r)
+            );
+        }
+    }
+}
@@ -0,0 +1,10 @@
+mod kdtree;
+
+pub use kdtree::KdTree;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Accel {
+    #[default]
+    None,
+    KdTree,
+}
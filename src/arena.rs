@@ -0,0 +1,87 @@
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+use crate::shape::Intersection;
+
+thread_local! {
+    static INTERSECTIONS: RefCell<Vec<Vec<Intersection>>> = const { RefCell::new(Vec::new()) };
+}
+
+// A per-thread free list of intersection vectors. Buffers are handed out in stack order as the
+// tracer recurses and go back to the pool with their capacity intact when dropped, so after the
+// first few rays no heap allocation happens on the hot path.
+pub struct IntersectionBuffer(Vec<Intersection>);
+
+impl IntersectionBuffer {
+    pub fn take() -> Self {
+        let buffer = INTERSECTIONS
+            .try_with(|pool| pool.borrow_mut().pop())
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        Self(buffer)
+    }
+
+    pub fn pooled() -> usize {
+        INTERSECTIONS
+            .try_with(|pool| pool.borrow().len())
+            .unwrap_or(0)
+    }
+}
+
+impl Deref for IntersectionBuffer {
+    type Target = Vec<Intersection>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for IntersectionBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for IntersectionBuffer {
+    fn drop(&mut self) {
+        let mut buffer = std::mem::take(&mut self.0);
+        buffer.clear();
+        let _ = INTERSECTIONS.try_with(|pool| pool.borrow_mut().push(buffer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::IntersectionBuffer;
+    use crate::shape::{Intersection, Sphere};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn dropped_buffers_are_reused_with_their_capacity() {
+        let s = Sphere::static_default();
+        let mut xs = IntersectionBuffer::take();
+        xs.extend((0..32).map(|i| Intersection::new(i as f32, s)));
+        drop(xs);
+
+        let xs = IntersectionBuffer::take();
+        assert!(xs.is_empty());
+        assert!(xs.capacity() >= 32);
+    }
+
+    #[test]
+    pub fn nested_buffers_are_distinct() {
+        let s = Sphere::static_default();
+        let before = IntersectionBuffer::pooled();
+        let mut outer = IntersectionBuffer::take();
+        outer.push(Intersection::new(1., s));
+        {
+            let mut inner = IntersectionBuffer::take();
+            inner.push(Intersection::new(2., s));
+            assert_eq!(outer.len(), 1);
+            assert_eq!(inner[0].t, 2.);
+        }
+        drop(outer);
+        assert!(IntersectionBuffer::pooled() >= before.max(2));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::material::Material;
+use crate::shape::{Shape, Triangle};
+use crate::tuple::Point;
+
+// A bicubic Bézier patch, with `control[row][column]` running along v and u respectively.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BezierPatch {
+    pub control: [[Point; 4]; 4],
+}
+
+impl BezierPatch {
+    pub const fn new(control: [[Point; 4]; 4]) -> Self {
+        Self { control }
+    }
+
+    pub fn point_at(&self, u: f32, v: f32) -> Point {
+        let (bu, bv) = (bernstein(u), bernstein(v));
+        let mut sum = Point::zero() - Point::zero();
+        for (row, weight_v) in self.control.iter().zip(bv) {
+            for (p, weight_u) in row.iter().zip(bu) {
+                sum += (*p - Point::zero()) * (weight_u * weight_v);
+            }
+        }
+        Point::zero() + sum
+    }
+
+    // Splits the patch into a `resolution` by `resolution` grid of quads, two triangles each.
+    // Degenerate triangles, such as those at the pole of a patch that collapses to a point, are
+    // left out.
+    pub fn tessellate(
+        &self,
+        resolution: usize,
+        material: impl Fn() -> Material,
+    ) -> Vec<&'static dyn Shape> {
+        let resolution = resolution.max(1);
+        let step = 1. / resolution as f32;
+        let grid: Vec<Vec<Point>> = (0..=resolution)
+            .map(|j| {
+                (0..=resolution)
+                    .map(|i| self.point_at(i as f32 * step, j as f32 * step))
+                    .collect()
+            })
+            .collect();
+
+        let mut triangles: Vec<&'static dyn Shape> = vec![];
+        for j in 0..resolution {
+            for i in 0..resolution {
+                let (a, b) = (grid[j][i], grid[j][i + 1]);
+                let (c, d) = (grid[j + 1][i + 1], grid[j + 1][i]);
+                for [p1, p2, p3] in [[a, b, c], [a, c, d]] {
+                    if (p2 - p1).cross(&(p3 - p1)).magnitude() > f32::EPSILON {
+                        triangles.push(Triangle::with_material(p1, p2, p3, material()));
+                    }
+                }
+            }
+        }
+        triangles
+    }
+}
+
+fn bernstein(t: f32) -> [f32; 4] {
+    let s = 1. - t;
+    [s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bezier::BezierPatch;
+    use crate::tuple::Point;
+    use pretty_assertions::assert_eq;
+
+    fn flat_patch() -> BezierPatch {
+        let mut control = [[Point::zero(); 4]; 4];
+        for (j, row) in control.iter_mut().enumerate() {
+            for (i, p) in row.iter_mut().enumerate() {
+                *p = Point::new(i as f32, 0., j as f32);
+            }
+        }
+        BezierPatch::new(control)
+    }
+
+    #[test]
+    pub fn patch_interpolates_its_corners() {
+        let patch = flat_patch();
+        assert_eq!(patch.point_at(0., 0.), Point::new(0., 0., 0.));
+        assert_eq!(patch.point_at(1., 0.), Point::new(3., 0., 0.));
+        assert_eq!(patch.point_at(1., 1.), Point::new(3., 0., 3.));
+        assert_eq!(patch.point_at(0.5, 0.5), Point::new(1.5, 0., 1.5));
+    }
+
+    #[test]
+    pub fn tessellation_skips_degenerate_triangles() {
+        assert_eq!(flat_patch().tessellate(4, Default::default).len(), 32);
+
+        let mut cone = flat_patch();
+        cone.control[0] = [Point::new(0., 1., 0.); 4];
+        assert_eq!(cone.tessellate(4, Default::default).len(), 28);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::tuple::Point;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub const fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    pub const fn empty() -> Self {
+        Self::new(
+            Point::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            Point::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        )
+    }
+
+    pub const fn infinite() -> Self {
+        Self::new(
+            Point::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            Point::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        )
+    }
+
+    pub const fn unit_cube() -> Self {
+        Self::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| self.min_at(axis) > self.max_at(axis))
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [
+            self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z,
+        ]
+        .iter()
+        .all(|v| v.is_finite())
+    }
+
+    pub fn min_at(&self, axis: usize) -> f32 {
+        axis_of(&self.min, axis)
+    }
+
+    pub fn max_at(&self, axis: usize) -> f32 {
+        axis_of(&self.max, axis)
+    }
+
+    pub fn set_min_at(&mut self, axis: usize, value: f32) {
+        set_axis(&mut self.min, axis, value);
+    }
+
+    pub fn set_max_at(&mut self, axis: usize, value: f32) {
+        set_axis(&mut self.max, axis, value);
+    }
+
+    pub fn add_point(&mut self, p: &Point) {
+        self.min = Point::new(
+            self.min.x.min(p.x),
+            self.min.y.min(p.y),
+            self.min.z.min(p.z),
+        );
+        self.max = Point::new(
+            self.max.x.max(p.x),
+            self.max.y.max(p.y),
+            self.max.z.max(p.z),
+        );
+    }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut merged = *self;
+        merged.add_point(&other.min);
+        merged.add_point(&other.max);
+        merged
+    }
+
+    pub fn contains_point(&self, p: &Point) -> bool {
+        (0..3).all(|axis| {
+            let v = axis_of(p, axis);
+            self.min_at(axis) <= v && v <= self.max_at(axis)
+        })
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        [
+            Point::new(self.min.x, self.min.y, self.min.z),
+            Point::new(self.min.x, self.min.y, self.max.z),
+            Point::new(self.min.x, self.max.y, self.min.z),
+            Point::new(self.min.x, self.max.y, self.max.z),
+            Point::new(self.max.x, self.min.y, self.min.z),
+            Point::new(self.max.x, self.min.y, self.max.z),
+            Point::new(self.max.x, self.max.y, self.min.z),
+            Point::new(self.max.x, self.max.y, self.max.z),
+        ]
+    }
+
+    pub fn transform(&self, transform: &Matrix4) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_finite() {
+            return Self::infinite();
+        }
+
+        let mut transformed = Self::empty();
+        for corner in self.corners() {
+            transformed.add_point(&(transform * corner));
+        }
+        transformed
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let dx = self.max.x - self.min.x;
+        let dy = self.max.y - self.min.y;
+        let dz = self.max.z - self.min.z;
+        2. * dz.mul_add(dx, dx.mul_add(dy, dy * dz))
+    }
+
+    pub fn maximum_extent(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let mut tmin = f32::NEG_INFINITY;
+        let mut tmax = f32::INFINITY;
+
+        for axis in 0..3 {
+            let origin = axis_of(&ray.origin, axis);
+            let direction = match axis {
+                0 => ray.direction.x,
+                1 => ray.direction.y,
+                _ => ray.direction.z,
+            };
+            let (lo, hi) = (self.min_at(axis), self.max_at(axis));
+
+            if direction == 0. {
+                if origin < lo || origin > hi {
+                    return None;
+                }
+                continue;
+            }
+
+            let t0 = (lo - origin) / direction;
+            let t1 = (hi - origin) / direction;
+            tmin = tmin.max(t0.min(t1));
+            tmax = tmax.min(t0.max(t1));
+            if tmin > tmax {
+                return None;
+            }
+        }
+
+        Some((tmin, tmax))
+    }
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+pub(crate) fn axis_of(p: &Point, axis: usize) -> f32 {
+    match axis {
+        0 => p.x,
+        1 => p.y,
+        _ => p.z,
+    }
+}
+
+fn set_axis(p: &mut Point, axis: usize, value: f32) {
+    match axis {
+        0 => p.x = value,
+        1 => p.y = value,
+        _ => p.z = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::Bounds;
+    use crate::matrix::Matrix4;
+    use crate::ray::Ray;
+    use crate::tuple::{Point, Vector};
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test]
+    pub fn adding_points_to_empty_bounds() {
+        let mut b = Bounds::empty();
+        assert!(b.is_empty());
+        b.add_point(&Point::new(-5., 2., 0.));
+        b.add_point(&Point::new(7., 0., -3.));
+        assert_eq!(b.min, Point::new(-5., 0., -3.));
+        assert_eq!(b.max, Point::new(7., 2., 0.));
+    }
+
+    #[test]
+    pub fn merging_bounds() {
+        let a = Bounds::new(Point::new(-5., -2., 0.), Point::new(7., 4., 4.));
+        let b = Bounds::new(Point::new(8., -7., -2.), Point::new(14., 2., 8.));
+        let merged = a.merge(&b);
+        assert_eq!(merged.min, Point::new(-5., -7., -2.));
+        assert_eq!(merged.max, Point::new(14., 4., 8.));
+    }
+
+    #[test]
+    pub fn transforming_bounds() {
+        let b = Bounds::unit_cube();
+        let t = Matrix4::identity()
+            .rotate_y(std::f32::consts::PI / 4.)
+            .rotate_x(std::f32::consts::PI / 4.);
+        let transformed = b.transform(&t);
+        let (sqrt2, half_sqrt2) = (std::f32::consts::SQRT_2, std::f32::consts::FRAC_1_SQRT_2);
+        assert_eq!(
+            transformed.min,
+            Point::new(-sqrt2, -1. - half_sqrt2, -1. - half_sqrt2)
+        );
+        assert_eq!(
+            transformed.max,
+            Point::new(sqrt2, 1. + half_sqrt2, 1. + half_sqrt2)
+        );
+    }
+
+    #[test]
+    pub fn transforming_infinite_bounds_stays_infinite() {
+        let b = Bounds::infinite();
+        let transformed = b.transform(&Matrix4::identity().translate(&Vector::new(1., 2., 3.)));
+        assert!(!transformed.is_finite());
+    }
+
+    #[test_case(Point::new(5., 0.5, 0.), Vector::new(-1., 0., 0.), Some((4., 6.)) ; "positive x")]
+    #[test_case(Point::new(0., 0., 0.), Vector::new(0., 0., 1.), Some((-1., 1.)) ; "inside")]
+    #[test_case(Point::new(0., 2., 2.), Vector::new(0., -1., 0.), None ; "parallel miss")]
+    #[test_case(Point::new(-2., 0., 0.), Vector::new(0.2673, 0.5345, 0.8018), None ; "oblique miss")]
+    pub fn intersecting_ray_with_bounds(
+        origin: Point,
+        direction: Vector,
+        expected: Option<(f32, f32)>,
+    ) {
+        let b = Bounds::unit_cube();
+        let r = Ray::new(origin, direction);
+        assert_eq!(b.intersect(&r), expected);
+    }
+}
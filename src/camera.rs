@@ -1,14 +1,25 @@
 use crate::canvas::Canvas;
+use crate::error::{Error, Result};
+use crate::exposure::Exposure;
+use crate::film::{Film, OrderedSplats};
 use crate::matrix::Matrix4;
-use crate::ray::Ray;
+use crate::postprocess;
+use crate::ray::{Ray, RayDifferential};
+use crate::sampler::{self, Dimension};
+use crate::sampling::concentric_disk;
+use crate::shadow_cache;
+use crate::shape::Intersection;
+use crate::stats::{self, HeatmapMetric};
+use crate::term_preview::TermPreview;
+use crate::tile::{self, Tile};
 use crate::tuple::{Color, Point, Vector};
 use crate::world::World;
+use itertools::Itertools;
 use rand::Rng;
+use smallvec::SmallVec;
 use std::f32::consts::PI;
-use std::sync::atomic::{AtomicI64, Ordering};
-use std::sync::mpsc;
-
-use rayon::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Debug)]
 pub struct Camera {
@@ -20,10 +31,34 @@ pub struct Camera {
     pub half_width: f32,
     pub half_height: f32,
     pub samples_pre_pixel: usize,
+    pub aperture: f32,
+    pub focal_distance: f32,
+    // Lens shift as a fraction of the view width and height. Shifting the window instead of
+    // tilting the camera keeps verticals parallel when framing tall buildings.
+    pub shift_x: f32,
+    pub shift_y: f32,
+    // Scales radiance as a camera with these settings would expose it. `None` leaves light
+    // intensities in the renderer's own unitless scale.
+    pub exposure: Option<Exposure>,
+    // Radial lens distortion: negative values bow straight lines outwards (barrel), positive
+    // values pinch them in (pincushion).
+    pub distortion: f32,
+    // Lateral chromatic aberration: how much larger the red image is, and the blue image
+    // smaller, than the green one towards the corners of the frame. Each channel is traced with
+    // its own ray, so any non-zero value triples the primary rays.
+    pub chromatic_aberration: f32,
 }
 
 const SAMPLES_PER_PIXEL: usize = 10;
-const MAX_REFLECTION_RECURSION_DEPTH: i32 = 5;
+pub const MAX_REFLECTION_RECURSION_DEPTH: i32 = 5;
+const TILE_SIZE: usize = 16;
+const OUTLIER_FACTOR: f32 = 4.;
+// Fills tiles whose rendering panicked, loud enough to spot in the finished frame.
+pub const PANIC_COLOR: Color = Color {
+    r: 1.,
+    g: 0.,
+    b: 1.,
+};
 
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, fov: f32) -> Self {
@@ -36,6 +71,13 @@ impl Camera {
             half_width: 0.,
             half_height: 0.,
             samples_pre_pixel: SAMPLES_PER_PIXEL,
+            aperture: 0.,
+            focal_distance: 1.,
+            shift_x: 0.,
+            shift_y: 0.,
+            exposure: None,
+            distortion: 0.,
+            chromatic_aberration: 0.,
         };
 
         let half_view = (fov / 2.).tan();
@@ -57,68 +99,341 @@ impl Camera {
         self.transform = Matrix4::view_transform(from, to, up);
     }
 
+    #[cfg(test)]
     fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let (xoffset, yoffset) = if self.samples_pre_pixel == 1 {
-            (
-                (px as f32 + 0.5) * self.pixel_size,
-                (py as f32 + 0.5) * self.pixel_size,
-            )
+        let (film_x, film_y) = self.sample_position(px, py);
+        self.ray_through(film_x, film_y)
+    }
+
+    // Position of the next sample for a pixel, in pixels from the top-left corner of the image.
+    fn sample_position(&self, px: usize, py: usize) -> (f32, f32) {
+        if let Some((u, v)) = sampler::stratified_2d(Dimension::Pixel) {
+            return (px as f32 + u, py as f32 + v);
+        }
+        if self.samples_pre_pixel == 1 {
+            (px as f32 + 0.5, py as f32 + 0.5)
+        } else {
+            sampler::with_rng(|rng| (px as f32 + rng.gen::<f32>(), py as f32 + rng.gen::<f32>()))
+        }
+    }
+
+    fn ray_through(&self, film_x: f32, film_y: f32) -> Ray {
+        self.ray_through_lens(film_x, film_y, 1.)
+    }
+
+    // A ray through the film as seen through the lens, with the image scaled by `magnification`
+    // about its centre on top of the lens distortion.
+    fn ray_through_lens(&self, film_x: f32, film_y: f32, magnification: f32) -> Ray {
+        let (world_x, world_y) = self.distort(
+            self.window_point(film_x * self.pixel_size, film_y * self.pixel_size),
+            magnification,
+        );
+
+        let inv = self.transform.inverse();
+        // Thin lens: every ray through the pixel converges on the plane of focus. A pinhole
+        // camera is the same with the lens collapsed to a point and the focus plane at z = -1.
+        let (lens_x, lens_y, focal_distance) = if self.aperture <= 0. {
+            (0., 0., 1.)
         } else {
-            (
-                (px as f32 + rand::thread_rng().gen_range(0.0..=0.5)) * self.pixel_size,
-                (py as f32 + rand::thread_rng().gen_range(0.0..=0.5)) * self.pixel_size,
-            )
+            let (lens_x, lens_y) = sample_disk(self.aperture / 2.);
+            (lens_x, lens_y, self.focal_distance)
+        };
+        let origin = inv * Point::new(lens_x, lens_y, 0.);
+        let direction_through = |x: f32, y: f32| {
+            let focus = Point::new(x * focal_distance, y * focal_distance, -focal_distance);
+            (inv * focus - origin).normalize()
         };
 
-        let world_x = self.half_width - xoffset;
-        let world_y = self.half_height - yoffset;
+        // The auxiliary rays are one sample apart, so the footprint shrinks as the pixel is
+        // supersampled.
+        let spacing = self.pixel_size / (self.samples_pre_pixel as f32).sqrt();
+        Ray::new(origin, direction_through(world_x, world_y)).with_differential(RayDifferential {
+            rx_origin: origin,
+            rx_direction: direction_through(world_x - spacing, world_y),
+            ry_origin: origin,
+            ry_direction: direction_through(world_x, world_y - spacing),
+        })
+    }
 
+    // Camera-space x grows to the left of the image, so a shift to the right lowers it.
+    fn window_point(&self, xoffset: f32, yoffset: f32) -> (f32, f32) {
+        (
+            self.half_width - xoffset - self.shift_x * 2. * self.half_width,
+            self.half_height - yoffset + self.shift_y * 2. * self.half_height,
+        )
+    }
+
+    fn distort(&self, (x, y): (f32, f32), magnification: f32) -> (f32, f32) {
+        if self.distortion == 0. && magnification == 1. {
+            return (x, y);
+        }
+        let (cx, cy) = (
+            -self.shift_x * 2. * self.half_width,
+            self.shift_y * 2. * self.half_height,
+        );
+        let (dx, dy) = (x - cx, y - cy);
+        // Radius relative to the corner of the frame, so the strength doesn't depend on the
+        // field of view.
+        let r2 = (dx * dx + dy * dy)
+            / self
+                .half_width
+                .mul_add(self.half_width, self.half_height * self.half_height);
+        let scale = magnification * self.distortion.mul_add(r2, 1.);
+        (cx + dx * scale, cy + dy * scale)
+    }
+
+    // Traces each channel through its own ray, with red magnified and blue shrunk by the
+    // chromatic aberration.
+    fn dispersed_color(&self, world: &World, film_x: f32, film_y: f32, depth: i32) -> Color {
+        let channel = |magnification| {
+            world.color_at(&self.ray_through_lens(film_x, film_y, magnification), depth)
+        };
+        Color::new(
+            channel(1. + self.chromatic_aberration).r,
+            channel(1.).g,
+            channel(1. - self.chromatic_aberration).b,
+        )
+    }
+
+    pub fn pixel_center_ray(&self, px: usize, py: usize) -> Ray {
+        self.pixel_to_ray(px as f32 + 0.5, py as f32 + 0.5)
+    }
+
+    // A pinhole ray through a position on the image, in pixels from its top-left corner. Unlike
+    // the rays used for rendering it ignores the aperture and involves no randomness.
+    pub fn pixel_to_ray(&self, px: f32, py: f32) -> Ray {
         let inv = self.transform.inverse();
-        let pixel = inv * Point::new(world_x, world_y, -1.);
+        let (world_x, world_y) = self.window_point(px * self.pixel_size, py * self.pixel_size);
+        let target = Point::new(world_x, world_y, -1.);
         let origin = inv * Point::new(0., 0., 0.);
-        let direction = (pixel - origin).normalize();
+        Ray::new(origin, (inv * target - origin).normalize())
+    }
+
+    // The inverse of `pixel_to_ray`: where a world point appears on the image, in pixels from its
+    // top-left corner. Points beside the frame get coordinates outside the image; points behind
+    // the camera have none.
+    pub fn project(&self, point: Point) -> Option<(f32, f32)> {
+        let p = self.transform * point;
+        if p.z >= 0. {
+            return None;
+        }
+        let (world_x, world_y) = (p.x / -p.z, p.y / -p.z);
+        let xoffset = self.half_width - self.shift_x * 2. * self.half_width - world_x;
+        let yoffset = self.half_height + self.shift_y * 2. * self.half_height - world_y;
+        Some((xoffset / self.pixel_size, yoffset / self.pixel_size))
+    }
 
-        Ray::new(origin, direction)
+    pub fn autofocus(&mut self, world: &World, px: usize, py: usize) -> Option<f32> {
+        let ray = self.pixel_center_ray(px, py);
+        let xs = world.intersect_world(&ray);
+        let hit = Intersection::get_hit(&xs)?;
+        let in_camera_space = self.transform * ray.position(hit.t);
+        self.focal_distance = -in_camera_space.z;
+        Some(self.focal_distance)
     }
 
-    pub fn render(&self, world: &World) -> Canvas {
-        let mut canvas = Canvas::new(self.hsize, self.vsize);
-        let (rx, tx) = mpsc::channel();
-        let progress = AtomicI64::new(0);
+    pub fn render(&self, world: &World) -> Result<Canvas> {
+        let _span = tracing::info_span!(
+            "render",
+            width = self.hsize,
+            height = self.vsize,
+            samples = self.samples_pre_pixel
+        )
+        .entered();
+        let tiles = Tile::centre_out(self.hsize, self.vsize, TILE_SIZE);
+        let remaining = AtomicUsize::new(tiles.len());
+        if !world.acceleration_is_current() {
+            return Err(Error::StaleAcceleration);
+        }
+        let pool = world.thread_pool()?;
+        let preview = world
+            .settings
+            .term_preview
+            .map(|columns| TermPreview::new(self.hsize, self.vsize, columns));
 
-        (0..self.vsize - 1).into_par_iter().for_each(|y| {
-            progress.fetch_add(1, Ordering::AcqRel);
-            eprint!(
-                "\rScanlines remaining: {}  ",
-                self.vsize - progress.load(Ordering::Relaxed) as usize
-            );
-            (0..self.hsize - 1).for_each(|x| {
-                let mut color = Color::black();
-                for _ in 0..self.samples_pre_pixel {
-                    let ray = self.ray_for_pixel(x, y);
-                    color += world.color_at(&ray, MAX_REFLECTION_RECURSION_DEPTH);
+        let canvas = pool.install(|| {
+            let splats =
+                OrderedSplats::new(Film::new(self.hsize, self.vsize, world.settings.filter));
+            tile::for_each_in_order(&tiles, |index, tile| {
+                let samples = self.render_tile(world, tile);
+                let left = remaining.fetch_sub(1, Ordering::AcqRel) - 1;
+                tracing::debug!(x = tile.x, y = tile.y, left, "tile finished");
+                if let Some(preview) = &preview {
+                    preview.add_samples(&samples);
                 }
-                rx.send(((x, y), self.rescale_color_range(color))).unwrap();
+                splats.add(index, samples);
+            });
+            if let Some(preview) = &preview {
+                preview.finish();
+            }
+
+            let film = splats.into_film();
+            let mut canvas = film.develop();
+            postprocess::apply_all(&mut canvas, &world.settings.post_process);
+            canvas.par_map_pixels(|_, _, c| {
+                Color::new(c.r.clamp(0., 1.), c.g.clamp(0., 1.), c.b.clamp(0., 1.))
             });
+            canvas
         });
 
-        for _ in 0..((self.hsize - 1) * (self.vsize - 1)) {
-            let ((x, y), color) = tx.recv().unwrap();
-            canvas.write_pixel(x, y, color).unwrap();
+        Ok(canvas)
+    }
+
+    // Colours each pixel by how much work it took instead of by what it shows, scaled so the
+    // most expensive pixel is white.
+    pub fn render_heatmap(&self, world: &World, metric: HeatmapMetric) -> Result<Canvas> {
+        let mut costs = Canvas::new(self.hsize, self.vsize);
+        let mut heatmap = Canvas::new(self.hsize, self.vsize);
+        if !world.acceleration_is_current() {
+            return Err(Error::StaleAcceleration);
         }
+        let pool = world.thread_pool()?;
+        pool.install(|| {
+            costs.par_map_pixels(|x, y, _| {
+                stats::take();
+                self.render_pixel(world, x, y);
+                let cost = stats::take().get(metric) as f32 / self.samples_pre_pixel as f32;
+                Color::new(cost, cost, cost)
+            });
 
-        canvas
+            let max = costs.iter_pixels().map(|(_, _, c)| c.r).fold(0., f32::max);
+            heatmap.par_map_pixels(|x, y, _| {
+                let cost = costs.pixel_at(x, y).unwrap().r;
+                stats::heat_color(if max > 0. { cost / max } else { 0. })
+            });
+        });
+        Ok(heatmap)
     }
 
-    fn rescale_color_range(&self, color: Color) -> Color {
-        let scale = 1.0 / self.samples_pre_pixel as f32;
-        let scaled = color * scale;
-        Color::new(
-            scaled.r.clamp(0., 1.),
-            scaled.g.clamp(0., 1.),
-            scaled.b.clamp(0., 1.),
-        )
+    pub fn render_light_groups(&self, world: &mut World) -> Result<Vec<(String, Canvas)>> {
+        let enabled = world.light_groups.iter().map(|g| g.enabled).collect_vec();
+
+        let mut passes = vec![];
+        for i in 0..world.light_groups.len() {
+            for (j, group) in world.light_groups.iter_mut().enumerate() {
+                group.enabled = i == j;
+            }
+            let pass = self.render(world);
+            passes.push((world.light_groups[i].name.clone(), pass));
+        }
+
+        for (group, enabled) in world.light_groups.iter_mut().zip(enabled) {
+            group.enabled = enabled;
+        }
+        passes
+            .into_iter()
+            .map(|(name, pass)| Ok((name, pass?)))
+            .collect()
+    }
+
+    // Samples of every pixel in a tile. A panic while shading one of them is logged with the
+    // offending pixel and fills the tile with `PANIC_COLOR`, so the rest of the frame still
+    // renders.
+    fn render_tile(&self, world: &World, tile: &Tile) -> Vec<(f32, f32, Color)> {
+        let mut current = (tile.x, tile.y);
+        let rendered = panic::catch_unwind(AssertUnwindSafe(|| {
+            tile.pixels()
+                .flat_map(|(x, y)| {
+                    current = (x, y);
+                    self.render_pixel(world, x, y)
+                })
+                .collect_vec()
+        }));
+        rendered.unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            let (x, y) = current;
+            tracing::error!(
+                tile.x,
+                tile.y,
+                x,
+                y,
+                ray = ?self.pixel_center_ray(x, y),
+                "rendering panicked, filling the tile: {message}"
+            );
+            tile.pixels()
+                .map(|(x, y)| (x as f32 + 0.5, y as f32 + 0.5, PANIC_COLOR))
+                .collect()
+        })
+    }
+
+    // Samples of one pixel as (film x, film y, radiance), ready to be splatted.
+    fn render_pixel(&self, world: &World, x: usize, y: usize) -> SmallVec<[(f32, f32, Color); 16]> {
+        sampler::seed_pixel(world.settings.frame_seed(), x, y);
+        shadow_cache::clear();
+        let mut samples = (0..self.samples_pre_pixel)
+            .map(|i| {
+                sampler::start_sample(world.settings.sample_pattern, i, self.samples_pre_pixel);
+                let (film_x, film_y) = self.sample_position(x, y);
+                let depth = world
+                    .settings
+                    .max_depth
+                    .unwrap_or(MAX_REFLECTION_RECURSION_DEPTH);
+                let color = if self.chromatic_aberration == 0. {
+                    world.color_at(&self.ray_through(film_x, film_y), depth)
+                } else {
+                    self.dispersed_color(world, film_x, film_y, depth)
+                };
+                let color = self.exposure.map_or(color, |e| color * e.scale());
+                let color = world
+                    .settings
+                    .max_radiance
+                    .map_or(color, |max| clamp_radiance(color, max));
+                (film_x, film_y, color)
+            })
+            .collect::<SmallVec<[_; 16]>>();
+        if world.settings.reject_outliers {
+            reject_outliers(&mut samples);
+        }
+        samples
+    }
+}
+
+// Renders the same world from every camera in turn. The world's acceleration structure is built
+// once by the caller and shared by all of the views.
+pub fn render_all_cameras(
+    world: &World,
+    cameras: &[(String, Camera)],
+) -> Result<Vec<(String, Canvas)>> {
+    cameras
+        .iter()
+        .map(|(name, camera)| Ok((name.clone(), camera.render(world)?)))
+        .collect()
+}
+
+fn clamp_radiance(color: Color, max: f32) -> Color {
+    let peak = color.r.max(color.g).max(color.b);
+    if peak > max {
+        color * (max / peak)
+    } else {
+        color
+    }
+}
+
+// Drops samples far brighter than the pixel's median. Only samples that would saturate the
+// output anyway are considered, so legitimately bright pixels keep all of theirs.
+fn reject_outliers(samples: &mut SmallVec<[(f32, f32, Color); 16]>) {
+    if samples.len() < 3 {
+        return;
+    }
+    let mut luminances = samples.iter().map(|(_, _, c)| c.luminance()).collect_vec();
+    luminances.sort_by(f32::total_cmp);
+    let median = luminances[luminances.len() / 2];
+    let threshold = (median * OUTLIER_FACTOR).max(1.);
+    samples.retain(|(_, _, c)| c.luminance() <= threshold);
+}
+
+fn sample_disk(radius: f32) -> (f32, f32) {
+    if let Some((u, v)) = sampler::stratified_2d(Dimension::Lens) {
+        let (x, y) = concentric_disk(u, v);
+        return (x * radius, y * radius);
     }
+    let (u, theta) = sampler::with_rng(|rng| (rng.gen::<f32>(), rng.gen_range(0.0..2. * PI)));
+    let r = radius * u.sqrt();
+    (r * theta.cos(), r * theta.sin())
 }
 
 impl Default for Camera {
@@ -129,11 +444,20 @@ impl Default for Camera {
 
 #[cfg(test)]
 mod tests {
-    use crate::camera::Camera;
+    use crate::accel::Accel;
+    use crate::camera::{clamp_radiance, reject_outliers, render_all_cameras, Camera, PANIC_COLOR};
+    use crate::exposure::Exposure;
+    use crate::light::{PointLight, DEFAULT_LIGHT_GROUP};
+    use crate::material::Material;
     use crate::matrix::Matrix4;
+    use crate::postprocess::Effect;
+    use crate::sampler;
+    use crate::shape::{Plane, Sphere};
+    use crate::stats::{heat_color, HeatmapMetric};
     use crate::tuple::{Color, Point, Vector};
     use crate::world::World;
     use pretty_assertions::assert_eq;
+    use smallvec::SmallVec;
     use std::f32::consts::PI;
 
     #[test]
@@ -169,6 +493,149 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn lens_shift_moves_the_window_without_turning_the_camera() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.samples_pre_pixel = 1;
+        c.shift_y = 0.5;
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin, crate::tuple::Point::new(0., 0., 0.));
+        assert_eq!(
+            r.direction,
+            crate::tuple::Vector::new(0., c.half_height, -1.).normalize()
+        );
+
+        c.shift_y = 0.;
+        c.shift_x = 0.5;
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(
+            r.direction,
+            crate::tuple::Vector::new(-1., 0., -1.).normalize()
+        );
+    }
+
+    #[test]
+    pub fn barrel_distortion_pulls_the_corners_in() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.samples_pre_pixel = 1;
+        let straight = c.ray_for_pixel(0, 0).direction;
+        c.distortion = -0.2;
+        assert_eq!(c.ray_for_pixel(100, 50).direction, Vector::new(0., 0., -1.));
+        let bent = c.ray_for_pixel(0, 0).direction;
+        assert!(bent.x.abs() < straight.x.abs() && bent.y.abs() < straight.y.abs());
+        // The corner is at the frame's full radius, so it moves in by the whole 20%.
+        assert!((bent.x / -bent.z - 0.8 * straight.x / -straight.z).abs() < 1e-2);
+    }
+
+    #[test]
+    pub fn chromatic_aberration_fringes_edges() {
+        // A white wall beside the centre of the view. Red rays are spread wider than blue ones,
+        // so towards its outer edge there are pixels where only the blue ray still hits it.
+        let wall = crate::shape::Cube::default_with_material(crate::material::Material {
+            ambient: 1.,
+            ..Default::default()
+        });
+        wall.set_transform(
+            Matrix4::identity()
+                .scale(&Vector::new(1., 10., 1.))
+                .translate(&Vector::new(1., 0., -5.)),
+        );
+        let w = World::new(
+            PointLight::new(Point::new(0., 0., 10.), Color::white()),
+            vec![wall],
+        );
+        let mut c = Camera::new(20, 20, PI / 2.);
+        c.samples_pre_pixel = 1;
+        c.chromatic_aberration = 0.2;
+        let fringed = |canvas: &crate::canvas::Canvas| {
+            (0..20).any(|x| {
+                let p = canvas.pixel_at(x, 9).unwrap();
+                p.b > p.r
+            })
+        };
+        assert!(fringed(&c.render(&w).unwrap()));
+        c.chromatic_aberration = 0.;
+        assert!(!fringed(&c.render(&w).unwrap()));
+    }
+
+    #[test]
+    pub fn heatmap_is_brightest_where_rays_hit_objects() {
+        let mut w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.samples_pre_pixel = 1;
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+        w.settings.threads = 1;
+        let heatmap = c.render_heatmap(&w, HeatmapMetric::Rays).unwrap();
+        let centre = heatmap.pixel_at(5, 5).unwrap();
+        let corner = heatmap.pixel_at(0, 0).unwrap();
+        // A miss costs one camera ray; a hit adds the shadow ray.
+        assert_eq!(corner, heat_color(0.5));
+        assert_eq!(centre, Color::white());
+    }
+
+    #[test]
+    pub fn output_does_not_depend_on_thread_count() {
+        let mut w = World::default();
+        w.settings.russian_roulette = Some(0);
+        let mut c = Camera::new(24, 24, PI / 2.);
+        c.samples_pre_pixel = 4;
+        c.aperture = 0.2;
+        c.focal_distance = 4.;
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+        let bits = |canvas: &crate::canvas::Canvas| {
+            canvas
+                .iter_pixels()
+                .flat_map(|(_, _, c)| [c.r.to_bits(), c.g.to_bits(), c.b.to_bits()])
+                .collect::<Vec<_>>()
+        };
+
+        w.settings.threads = 1;
+        let single = bits(&c.render(&w).unwrap());
+        w.settings.threads = 4;
+        assert_eq!(bits(&c.render(&w).unwrap()), single);
+    }
+
+    #[test]
+    pub fn bright_samples_are_clamped_to_max_radiance() {
+        assert_eq!(
+            clamp_radiance(Color::new(8., 4., 0.), 2.),
+            Color::new(2., 1., 0.)
+        );
+        assert_eq!(
+            clamp_radiance(Color::new(0.5, 0.2, 0.1), 2.),
+            Color::new(0.5, 0.2, 0.1)
+        );
+    }
+
+    #[test]
+    pub fn single_fireflies_are_rejected() {
+        let grey = Color::new(0.2, 0.2, 0.2);
+        let mut samples: SmallVec<[(f32, f32, Color); 16]> =
+            SmallVec::from_elem((0.5, 0.5, grey), 7);
+        samples.push((0.5, 0.5, Color::new(50., 40., 30.)));
+        reject_outliers(&mut samples);
+        assert_eq!(samples.len(), 7);
+        assert!(samples.iter().all(|&(_, _, c)| c == grey));
+    }
+
+    #[test]
+    pub fn ray_differentials_point_at_neighbouring_pixels() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.samples_pre_pixel = 1;
+        let d = c.ray_for_pixel(10, 20).differential.unwrap();
+        assert_eq!(d.rx_direction, c.pixel_center_ray(11, 20).direction);
+        assert_eq!(d.ry_direction, c.pixel_center_ray(10, 21).direction);
+        assert_eq!(d.rx_origin, c.pixel_center_ray(11, 20).origin);
+    }
+
     #[test]
     pub fn ray_when_camera_is_transformed() {
         let mut c = Camera::new(201, 101, PI / 2.);
@@ -195,10 +662,280 @@ mod tests {
             Point::zero(),
             Vector::new(0., 1., 0.),
         );
-        let image = c.render(&w);
+        let image = c.render(&w).unwrap();
         assert_eq!(
             image.pixel_at(5, 5).unwrap(),
             Color::new(0.38066, 0.47582, 0.28549)
         );
     }
+
+    #[test]
+    pub fn render_world_on_single_thread() {
+        let mut w = World::default();
+        w.settings.threads = 1;
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.samples_pre_pixel = 1;
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+        let image = c.render(&w).unwrap();
+        assert_eq!(
+            image.pixel_at(5, 5).unwrap(),
+            Color::new(0.38066, 0.47582, 0.28549)
+        );
+    }
+
+    #[test]
+    pub fn lens_rays_converge_on_the_focal_plane() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.samples_pre_pixel = 1;
+        c.aperture = 0.5;
+        c.focal_distance = 3.;
+        for _ in 0..16 {
+            let r = c.ray_for_pixel(100, 50);
+            assert!(r.origin.x.hypot(r.origin.y) <= 0.25);
+            let t = -3. / r.direction.z;
+            assert_eq!(r.position(t), Point::new(0., 0., -3.));
+        }
+    }
+
+    #[test]
+    pub fn autofocus_on_object_under_pixel() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+        assert_eq!(c.autofocus(&w, 5, 5), Some(4.));
+        assert_eq!(c.focal_distance, 4.);
+    }
+
+    #[test]
+    pub fn autofocus_on_empty_pixel_keeps_focal_distance() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+        c.focal_distance = 2.;
+        assert_eq!(c.autofocus(&w, 0, 0), None);
+        assert_eq!(c.focal_distance, 2.);
+    }
+
+    #[test]
+    pub fn render_one_pass_per_light_group() {
+        let mut w = World::default();
+        w.add_light(
+            "rim",
+            PointLight::new(Point::new(10., 10., -10.), Color::new(0.5, 0.5, 0.5)),
+        );
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.samples_pre_pixel = 1;
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+
+        let passes = c.render_light_groups(&mut w).unwrap();
+        assert_eq!(
+            passes
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec![DEFAULT_LIGHT_GROUP, "rim"]
+        );
+        assert_eq!(
+            passes[0].1.pixel_at(5, 5).unwrap(),
+            Color::new(0.38066, 0.47582, 0.28549)
+        );
+        assert!(w.light_groups.iter().all(|g| g.enabled));
+    }
+
+    #[test]
+    pub fn projecting_a_point_inverts_pixel_to_ray() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.set_transform(
+            Point::new(1., 2., -5.),
+            Point::new(0., 0.5, 0.),
+            Vector::new(0., 1., 0.),
+        );
+        c.shift_x = 0.1;
+        let ray = c.pixel_to_ray(30.25, 70.5);
+        let (x, y) = c.project(ray.position(7.)).unwrap();
+        assert!((x - 30.25).abs() < 1e-3);
+        assert!((y - 70.5).abs() < 1e-3);
+
+        let behind = c.pixel_to_ray(100., 50.).position(-1.);
+        assert!(c.project(behind).is_none());
+    }
+
+    #[test]
+    pub fn every_camera_renders_its_own_view() {
+        let mut w = World::default();
+        w.settings.accel = Accel::KdTree;
+        w.build_acceleration();
+        let camera = |from: Point, size: usize| {
+            let mut c = Camera::new(size, size, PI / 2.);
+            c.samples_pre_pixel = 1;
+            c.set_transform(from, Point::zero(), Vector::new(0., 1., 0.));
+            c
+        };
+        let cameras = vec![
+            ("front".to_string(), camera(Point::new(0., 0., -5.), 5)),
+            ("top".to_string(), camera(Point::new(0., 5., 0.01), 3)),
+        ];
+
+        let views = render_all_cameras(&w, &cameras).unwrap();
+        assert_eq!(
+            views
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["front", "top"]
+        );
+        assert_eq!(views[1].1.width, 3);
+        assert_eq!(
+            views[0].1.pixel_at(2, 2).unwrap(),
+            cameras[0].1.render(&w).unwrap().pixel_at(2, 2).unwrap()
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct Faulty(crate::shape::ShapeCommon);
+
+    impl crate::shape::Shape for Faulty {
+        fn local_intersect(
+            &'static self,
+            ray: &crate::ray::Ray,
+        ) -> Option<SmallVec<[crate::shape::Intersection; 8]>> {
+            assert!(ray.direction.x < 0., "faulty shape");
+            None
+        }
+
+        fn local_normal(&self, _: &Point) -> Vector {
+            Vector::new(0., 1., 0.)
+        }
+
+        fn local_bounds(&self) -> crate::bounds::Bounds {
+            crate::bounds::Bounds::infinite()
+        }
+
+        fn common(&self) -> &crate::shape::ShapeCommon {
+            &self.0
+        }
+
+        fn common_mut(&mut self) -> &mut crate::shape::ShapeCommon {
+            &mut self.0
+        }
+
+        fn shape_type(&self) -> crate::shape::ShapeType {
+            crate::shape::ShapeType::Sphere
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    unsafe impl Send for Faulty {}
+    unsafe impl Sync for Faulty {}
+
+    #[test]
+    pub fn panicking_tile_is_filled_and_the_rest_still_renders() {
+        let faulty: &'static Faulty = Box::leak(Box::default());
+        let light = PointLight::new(Point::new(0., 10., 0.), Color::white());
+        let w = World::new(light, vec![faulty]);
+        let mut c = Camera::new(32, 16, PI / 2.);
+        c.samples_pre_pixel = 1;
+        let canvas = c.render(&w).unwrap();
+        let tiles = [
+            canvas.pixel_at(0, 0).unwrap(),
+            canvas.pixel_at(31, 15).unwrap(),
+        ];
+        assert!(tiles.contains(&PANIC_COLOR));
+        assert!(tiles.contains(&Color::black()));
+    }
+
+    #[test]
+    pub fn independent_samples_cover_the_whole_pixel() {
+        let mut c = Camera::new(10, 10, PI / 2.);
+        c.samples_pre_pixel = 64;
+        sampler::seed_pixel(7, 3, 4);
+        let positions = (0..c.samples_pre_pixel)
+            .map(|_| c.sample_position(3, 4))
+            .collect::<Vec<_>>();
+        assert!(positions
+            .iter()
+            .all(|&(x, y)| (3. ..4.).contains(&x) && (4. ..5.).contains(&y)));
+        assert!(positions.iter().any(|&(x, _)| x > 3.5));
+        assert!(positions.iter().any(|&(_, y)| y > 4.5));
+    }
+
+    #[test]
+    pub fn bloom_sees_highlights_brighter_than_white() {
+        let glowing = Sphere::new(Point::zero(), 0.5);
+        glowing.common.material = Material {
+            color: Color::new(3., 3., 3.),
+            ambient: 1.,
+            diffuse: 0.,
+            specular: 0.,
+            ..Default::default()
+        };
+        let light = PointLight::new(Point::new(-10., 10., -10.), Color::white());
+        let mut w = World::new(light, vec![glowing]);
+        let mut c = Camera::new(21, 21, PI / 3.);
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+        let plain = c.render(&w).unwrap();
+        w.settings.post_process = vec![Effect::Bloom {
+            threshold: 1.,
+            radius: 8,
+            intensity: 1.,
+        }];
+        let bloomed = c.render(&w).unwrap();
+
+        assert_eq!(plain.pixel_at(10, 10).unwrap(), Color::white());
+        assert_eq!(plain.pixel_at(10, 2).unwrap(), Color::black());
+        assert!(bloomed.pixel_at(10, 2).unwrap().r > 0.);
+        assert_eq!(bloomed.pixel_at(10, 10).unwrap(), Color::white());
+    }
+
+    #[test]
+    pub fn exposed_photometric_light_gives_the_luminance_of_a_lambertian_surface() {
+        // 4 cd at 1 m is 4 lux; a surface of albedo 0.5 under it has a luminance of 2/π cd/m².
+        let floor = Plane::default_with_material(Material {
+            color: Color::white(),
+            ambient: 0.,
+            diffuse: 0.5,
+            specular: 0.,
+            ..Default::default()
+        });
+        let light = PointLight::with_candela(Point::new(0., 1., 0.), Color::white(), 4.);
+        let w = World::new(light, vec![floor]);
+        let mut c = Camera::new(3, 3, 0.01);
+        c.set_transform(
+            Point::new(0., 2., 0.),
+            Point::zero(),
+            Vector::new(0., 0., 1.),
+        );
+        let exposure = Exposure::new(100., 1., 1.);
+        c.exposure = Some(exposure);
+
+        let luminance = 0.5 * 4. / PI;
+        let expected = luminance * exposure.scale();
+        assert_eq!(
+            c.render(&w).unwrap().pixel_at(1, 1).unwrap(),
+            Color::new(expected, expected, expected)
+        );
+    }
 }
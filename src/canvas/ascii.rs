@@ -0,0 +1,123 @@
+use std::fmt::Write;
+
+use crate::canvas::Canvas;
+use crate::tuple::Color;
+
+// Characters from darkest to brightest.
+const RAMP: &[u8] = b" .:-=+*#%@";
+// Terminal cells are about twice as tall as they are wide.
+const CELL_ASPECT: f32 = 2.;
+
+impl Canvas {
+    // A rough preview of the image `width` characters across, one character per cell picked by
+    // brightness.
+    pub fn to_ascii(&self, width: usize) -> String {
+        self.to_terminal(width, false)
+    }
+
+    // Like `to_ascii`, with every character also coloured through 24-bit ANSI escapes.
+    pub fn to_ansi(&self, width: usize) -> String {
+        self.to_terminal(width, true)
+    }
+
+    fn to_terminal(&self, width: usize, color: bool) -> String {
+        let cells = self.downsample(width);
+        let mut text = String::new();
+        for row in cells {
+            for c in row {
+                let luminance = c.luminance().clamp(0., 1.);
+                let index = ((luminance * RAMP.len() as f32) as usize).min(RAMP.len() - 1);
+                let ch = char::from(RAMP[index]);
+                if color {
+                    let [r, g, b] = [c.r, c.g, c.b].map(|v| (v.clamp(0., 1.) * 255.).round() as u8);
+                    let _ = write!(text, "\x1b[38;2;{r};{g};{b}m{ch}");
+                } else {
+                    text.push(ch);
+                }
+            }
+            if color {
+                text.push_str("\x1b[0m");
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    // Averages the pixels under each terminal cell, for a grid `width` cells across.
+    pub(crate) fn downsample(&self, width: usize) -> Vec<Vec<Color>> {
+        if self.width == 0 || self.height == 0 || width == 0 {
+            return vec![];
+        }
+        let columns = width.min(self.width);
+        let cell_width = self.width as f32 / columns as f32;
+        let rows = ((self.height as f32 / (cell_width * CELL_ASPECT)).round() as usize).max(1);
+        let cell_height = self.height as f32 / rows as f32;
+
+        (0..rows)
+            .map(|row| {
+                let (y0, y1) = span(row, cell_height, self.height);
+                (0..columns)
+                    .map(|column| {
+                        let (x0, x1) = span(column, cell_width, self.width);
+                        let mut sum = Color::black();
+                        for y in y0..y1 {
+                            for x in x0..x1 {
+                                sum += self.pixels[y * self.width + x];
+                            }
+                        }
+                        sum * (1. / ((x1 - x0) * (y1 - y0)) as f32)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// The pixels covered by cell `index` when cells are `size` pixels wide, never empty.
+fn span(index: usize, size: f32, limit: usize) -> (usize, usize) {
+    let start = ((index as f32 * size) as usize).min(limit - 1);
+    let end = (((index + 1) as f32 * size) as usize).clamp(start + 1, limit);
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+
+    fn halves() -> Canvas {
+        let mut c = Canvas::new(8, 4);
+        c.par_map_pixels(|x, _, _| {
+            if x < 4 {
+                Color::black()
+            } else {
+                Color::white()
+            }
+        });
+        c
+    }
+
+    #[test]
+    pub fn ascii_preview_follows_brightness() {
+        assert_eq!(halves().to_ascii(4), "  @@\n");
+        assert_eq!(halves().to_ascii(8), "    @@@@\n    @@@@\n");
+    }
+
+    #[test]
+    pub fn ansi_preview_colours_every_cell() {
+        let text = halves().to_ansi(2);
+        assert_eq!(text, "\x1b[38;2;0;0;0m \x1b[38;2;255;255;255m@\x1b[0m\n");
+    }
+
+    #[test]
+    pub fn cells_average_the_pixels_they_cover() {
+        let mut c = Canvas::new(2, 8);
+        c.write_pixel(0, 0, Color::new(0.8, 0.4, 0.)).unwrap();
+        let cells = c.downsample(1);
+        assert_eq!(
+            cells,
+            vec![vec![Color::new(0.1, 0.05, 0.)], vec![Color::black()]]
+        );
+    }
+}
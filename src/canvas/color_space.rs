@@ -0,0 +1,93 @@
+use std::borrow::Cow;
+
+use crate::canvas::Canvas;
+use crate::tuple::Color;
+
+// How channel values are encoded in an image file. Shading works in linear light; photos and
+// hand-painted textures are almost always sRGB, so they look washed out unless decoded first.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    // Values are written as they are, as the book's PPM output does.
+    Linear,
+    // What 8- and 16-bit image viewers expect, and so what renders are saved as by default.
+    #[default]
+    Srgb,
+}
+
+impl ColorSpace {
+    pub fn decode(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Srgb if value <= 0.04045 => value / 12.92,
+            Self::Srgb => ((value + 0.055) / 1.055).powf(2.4),
+        }
+    }
+
+    pub fn encode(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Srgb if value <= 0.003_130_8 => value * 12.92,
+            Self::Srgb => 1.055f32.mul_add(value.powf(1. / 2.4), -0.055),
+        }
+    }
+
+    pub fn decode_color(self, c: Color) -> Color {
+        Color::new(self.decode(c.r), self.decode(c.g), self.decode(c.b))
+    }
+
+    pub fn encode_color(self, c: Color) -> Color {
+        Color::new(self.encode(c.r), self.encode(c.g), self.encode(c.b))
+    }
+}
+
+impl Canvas {
+    // The canvas ready to be written in `space`. Linear output borrows the canvas unchanged.
+    pub fn encoded(&self, space: ColorSpace) -> Cow<'_, Self> {
+        if space == ColorSpace::Linear {
+            return Cow::Borrowed(self);
+        }
+        let mut encoded = self.clone();
+        for p in &mut encoded.pixels {
+            *p = space.encode_color(Color::new(
+                p.r.clamp(0., 1.),
+                p.g.clamp(0., 1.),
+                p.b.clamp(0., 1.),
+            ));
+        }
+        Cow::Owned(encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::color_space::ColorSpace;
+    use crate::canvas::Canvas;
+    use crate::settings::OutputSettings;
+    use crate::tuple::Color;
+    use test_case::test_case;
+
+    #[test_case(0. ; "black")]
+    #[test_case(0.002 ; "linear toe")]
+    #[test_case(0.214 ; "mid grey")]
+    #[test_case(1. ; "white")]
+    pub fn srgb_round_trips(value: f32) {
+        let srgb = ColorSpace::Srgb;
+        assert!((srgb.decode(srgb.encode(value)) - value).abs() < 1e-5);
+    }
+
+    #[test]
+    pub fn srgb_mid_grey_is_darker_in_linear_light() {
+        assert!((ColorSpace::Srgb.decode(0.5) - 0.214).abs() < 1e-3);
+        assert_eq!(ColorSpace::Linear.decode(0.5), 0.5);
+    }
+
+    #[test]
+    pub fn output_is_srgb_unless_linear_is_asked_for() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(0.214, 0.214, 0.214))
+            .unwrap();
+        let ppm = c.convert_to_ppm_with(&OutputSettings::default());
+        assert_eq!(ppm.lines().nth(3), Some("127 127 127 "));
+        assert_eq!(c.convert_to_ppm().lines().nth(3), Some("54 54 54 "));
+    }
+}
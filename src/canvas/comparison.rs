@@ -0,0 +1,147 @@
+use std::time::Instant;
+
+use crate::camera::Camera;
+use crate::canvas::font::{text_width, GLYPH_HEIGHT};
+use crate::canvas::Canvas;
+use crate::error::Result;
+use crate::tuple::Color;
+use crate::world::World;
+
+const GAP: usize = 4;
+const LABEL_SCALE: usize = 2;
+const LABEL_PADDING: usize = 4;
+const LABEL_HEIGHT: usize = GLYPH_HEIGHT * LABEL_SCALE + 2 * LABEL_PADDING;
+
+pub struct Comparison {
+    columns: usize,
+    panels: Vec<(String, Canvas)>,
+}
+
+impl Comparison {
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns: columns.max(1),
+            panels: vec![],
+        }
+    }
+
+    pub fn add(&mut self, label: impl Into<String>, canvas: Canvas) -> &mut Self {
+        self.panels.push((label.into(), canvas));
+        self
+    }
+
+    pub fn render(
+        &mut self,
+        label: impl Into<String>,
+        camera: &Camera,
+        world: &World,
+    ) -> Result<&mut Self> {
+        let start = Instant::now();
+        let canvas = camera.render(world)?;
+        let label = format!("{} ({:.2}s)", label.into(), start.elapsed().as_secs_f32());
+        Ok(self.add(label, canvas))
+    }
+
+    pub fn len(&self) -> usize {
+        self.panels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.panels.is_empty()
+    }
+
+    pub fn compose(&self) -> Canvas {
+        let cell_width = self.panels.iter().map(|(_, c)| c.width).max().unwrap_or(0);
+        let cell_height =
+            self.panels.iter().map(|(_, c)| c.height).max().unwrap_or(0) + LABEL_HEIGHT;
+        let columns = self.columns.min(self.panels.len()).max(1);
+        let rows = self.panels.len().div_ceil(columns);
+
+        let mut grid = Canvas::new(
+            columns * (cell_width + GAP) + GAP,
+            rows * (cell_height + GAP) + GAP,
+        );
+        grid.par_map_pixels(|_, _, _| Color::new(0.15, 0.15, 0.15));
+
+        for (i, (label, panel)) in self.panels.iter().enumerate() {
+            let x = GAP + (i % columns) * (cell_width + GAP);
+            let y = GAP + (i / columns) * (cell_height + GAP);
+            blit(&mut grid, panel, x, y);
+
+            let label_x = x + cell_width.saturating_sub(text_width(label, LABEL_SCALE)) / 2;
+            grid.draw_text(
+                label_x,
+                y + panel.height + LABEL_PADDING,
+                label,
+                Color::white(),
+                LABEL_SCALE,
+            );
+        }
+
+        grid
+    }
+}
+
+fn blit(target: &mut Canvas, source: &Canvas, x: usize, y: usize) {
+    for (sx, sy, &color) in source.iter_pixels() {
+        let _ = target.write_pixel(x + sx, y + sy, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::Camera;
+    use crate::canvas::comparison::{Comparison, GAP, LABEL_HEIGHT};
+    use crate::canvas::Canvas;
+    use crate::tuple::Color;
+    use crate::world::World;
+    use pretty_assertions::assert_eq;
+
+    fn solid(width: usize, height: usize, color: Color) -> Canvas {
+        let mut c = Canvas::new(width, height);
+        c.par_map_pixels(|_, _, _| color);
+        c
+    }
+
+    #[test]
+    pub fn panels_are_laid_out_in_a_grid() {
+        let red = Color::new(1., 0., 0.);
+        let blue = Color::new(0., 0., 1.);
+        let mut comparison = Comparison::new(2);
+        comparison
+            .add("RED", solid(10, 8, red))
+            .add("BLUE", solid(10, 8, blue))
+            .add("RED AGAIN", solid(6, 6, red));
+        let grid = comparison.compose();
+
+        assert_eq!(grid.width, 2 * (10 + GAP) + GAP);
+        assert_eq!(grid.height, 2 * (8 + LABEL_HEIGHT + GAP) + GAP);
+        assert_eq!(grid.pixel_at(GAP, GAP).unwrap(), red);
+        assert_eq!(grid.pixel_at(2 * GAP + 10, GAP).unwrap(), blue);
+        assert_eq!(grid.pixel_at(GAP, 2 * GAP + 8 + LABEL_HEIGHT).unwrap(), red);
+    }
+
+    #[test]
+    pub fn labels_are_drawn_below_panels() {
+        let mut comparison = Comparison::new(1);
+        comparison.add("SPP 1", solid(80, 4, Color::black()));
+        let grid = comparison.compose();
+        let label_band = grid
+            .iter_pixels()
+            .filter(|&(_, y, &c)| y >= GAP + 4 && c == Color::white())
+            .count();
+        assert!(label_band > 0);
+    }
+
+    #[test]
+    pub fn rendering_a_variant_records_its_time() {
+        let mut camera = Camera::new(4, 4, std::f32::consts::PI / 2.);
+        camera.samples_pre_pixel = 1;
+        let mut comparison = Comparison::new(3);
+        comparison
+            .render("SPP 1", &camera, &World::default())
+            .unwrap();
+        assert_eq!(comparison.len(), 1);
+        assert!(comparison.panels[0].0.starts_with("SPP 1 ("));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::canvas::Canvas;
+use crate::error::{Error, Result};
+use crate::stats::heat_color;
+
+// How far apart two images are. Channel values are clamped to 0..1 before comparing, so
+// differences in overexposed areas that would display identically are ignored.
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    pub rmse: f32,
+    // Peak signal-to-noise ratio in decibels; infinite for identical images.
+    pub psnr: f32,
+    // Pixels where some channel differs by more than the tolerance.
+    pub differing: usize,
+    // The largest channel difference per pixel on a heat ramp, scaled so the worst pixel is white.
+    pub heatmap: Canvas,
+}
+
+impl Canvas {
+    pub fn diff(&self, other: &Self, tolerance: f32) -> Result<ImageDiff> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(Error::SizeMismatch {
+                expected: (self.width, self.height),
+                found: (other.width, other.height),
+            });
+        }
+
+        let mut squared = 0.;
+        let mut worst = vec![0f32; self.pixels.len()];
+        for ((a, b), worst) in self.pixels.iter().zip(&other.pixels).zip(&mut worst) {
+            for (a, b) in [(a.r, b.r), (a.g, b.g), (a.b, b.b)] {
+                let d = a.clamp(0., 1.) - b.clamp(0., 1.);
+                squared += f64::from(d * d);
+                *worst = worst.max(d.abs());
+            }
+        }
+
+        let samples = (self.pixels.len() * 3).max(1) as f64;
+        let rmse = (squared / samples).sqrt() as f32;
+        let peak = worst.iter().copied().fold(0., f32::max);
+        let mut heatmap = Self::new(self.width, self.height);
+        for (pixel, d) in heatmap.pixels.iter_mut().zip(&worst) {
+            *pixel = heat_color(if peak > 0. { d / peak } else { 0. });
+        }
+
+        Ok(ImageDiff {
+            rmse,
+            psnr: -20. * rmse.log10(),
+            differing: worst.iter().filter(|&&d| d > tolerance).count(),
+            heatmap,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::error::Error;
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn identical_images_have_no_error() {
+        let c = Canvas::new(4, 4);
+        let diff = c.diff(&c.clone(), 0.).unwrap();
+        assert_eq!(diff.rmse, 0.);
+        assert_eq!(diff.psnr, f32::INFINITY);
+        assert_eq!(diff.differing, 0);
+        assert!(diff.heatmap.pixels.iter().all(|&p| p == Color::black()));
+    }
+
+    #[test]
+    pub fn differing_pixels_are_counted_above_tolerance() {
+        let mut a = Canvas::new(2, 2);
+        // Beyond white still compares as white.
+        a.write_pixel(1, 1, Color::new(2., 2., 2.)).unwrap();
+        let mut b = a.clone();
+        b.write_pixel(0, 0, Color::new(1., 1., 1.)).unwrap();
+        b.write_pixel(1, 0, Color::new(0.01, 0., 0.)).unwrap();
+        b.write_pixel(1, 1, Color::new(1., 1., 1.)).unwrap();
+
+        let diff = a.diff(&b, 0.05).unwrap();
+        assert_eq!(diff.differing, 1);
+        assert!((diff.rmse - (3.0001f32 / 12.).sqrt()).abs() < 1e-6);
+        assert_eq!(diff.heatmap.pixel_at(0, 0).unwrap(), Color::white());
+    }
+
+    #[test]
+    pub fn images_must_match_in_size() {
+        assert!(matches!(
+            Canvas::new(2, 2).diff(&Canvas::new(2, 3), 0.),
+            Err(Error::SizeMismatch {
+                expected: (2, 2),
+                found: (2, 3)
+            })
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Dither {
+    #[default]
+    None,
+    Ordered,
+    BlueNoise,
+}
+
+impl Dither {
+    pub fn threshold(&self, x: usize, y: usize) -> f32 {
+        match self {
+            Self::None => 0.,
+            Self::Ordered => (bayer8(x, y) as f32 + 0.5) / 64.,
+            // Interleaved gradient noise: cheap, tileable and close enough to blue noise that
+            // the dither pattern is not visible at 8 bits.
+            Self::BlueNoise => {
+                let v = 0.067_110_56f32.mul_add(x as f32, 0.005_837_15 * y as f32);
+                (52.982_918 * v.fract()).fract()
+            }
+        }
+    }
+
+    pub fn quantize(&self, value: f32, x: usize, y: usize) -> u8 {
+        value.mul_add(255., self.threshold(x, y)) as u8
+    }
+
+    // The 16-bit counterpart of `quantize`. Without dithering values are rounded rather than
+    // truncated, as 16-bit output always has been.
+    pub fn quantize16(&self, value: f32, x: usize, y: usize) -> u16 {
+        let offset = match self {
+            Self::None => 0.5,
+            _ => self.threshold(x, y),
+        };
+        value.clamp(0., 1.).mul_add(f32::from(u16::MAX), offset) as u16
+    }
+}
+
+fn bayer8(x: usize, y: usize) -> u32 {
+    let xy = x ^ y;
+    (0..3).fold(0, |v, i| {
+        v | (((xy >> i) & 1) << (5 - 2 * i)) as u32 | (((y >> i) & 1) << (4 - 2 * i)) as u32
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::dither::{bayer8, Dither};
+    use itertools::Itertools;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn bayer_matrix_is_a_permutation() {
+        let values = (0..8)
+            .cartesian_product(0..8)
+            .map(|(x, y)| bayer8(x, y))
+            .sorted()
+            .collect_vec();
+        assert_eq!(values, (0..64).collect_vec());
+        assert_eq!(
+            [bayer8(0, 0), bayer8(1, 0), bayer8(0, 1), bayer8(1, 1)],
+            [0, 32, 48, 16]
+        );
+    }
+
+    #[test]
+    pub fn no_dither_truncates() {
+        assert_eq!(Dither::None.quantize(0.999, 3, 4), 254);
+        assert_eq!(Dither::None.quantize(1.5, 0, 0), 255);
+    }
+
+    #[test]
+    pub fn sixteen_bit_dithering_spreads_between_neighbouring_levels() {
+        assert_eq!(Dither::None.quantize16(0.5, 0, 0), 32768);
+        assert_eq!(Dither::BlueNoise.quantize16(1.5, 5, 7), u16::MAX);
+        let value = 1000.5 / 65535.;
+        let levels = (0..8)
+            .cartesian_product(0..8)
+            .map(|(x, y)| Dither::Ordered.quantize16(value, x, y))
+            .collect_vec();
+        assert!(levels.contains(&1000) && levels.contains(&1001));
+    }
+
+    #[test]
+    pub fn dithering_preserves_average_level() {
+        let value = 100.3 / 255.;
+        for dither in [Dither::Ordered, Dither::BlueNoise] {
+            let levels = (0..64)
+                .cartesian_product(0..64)
+                .map(|(x, y)| dither.quantize(value, x, y))
+                .collect_vec();
+            assert!(levels.iter().all(|&l| l == 100 || l == 101));
+            let mean = levels.iter().map(|&l| f32::from(l)).sum::<f32>() / levels.len() as f32;
+            assert!((mean - 100.3).abs() < 0.05, "{dither:?}: {mean}");
+        }
+    }
+}
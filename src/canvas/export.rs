@@ -0,0 +1,293 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+use crate::canvas::dither::Dither;
+use crate::canvas::metadata::RenderMetadata;
+use crate::canvas::Canvas;
+use crate::error::{Error, Result};
+use crate::settings::OutputSettings;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,
+    Png16,
+    Tiff16,
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(Self::Ppm),
+            "png" => Some(Self::Png16),
+            "tif" | "tiff" => Some(Self::Tiff16),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+}
+
+impl Canvas {
+    pub fn save(&self, path: impl AsRef<Path>, output: &OutputSettings) -> Result<()> {
+        self.save_with_metadata(path, output, None)
+    }
+
+    // Metadata is embedded as tEXt chunks in PNG and as description/software tags in TIFF. PPM
+    // and JPEG output carry the pixels only.
+    pub fn save_with_metadata(
+        &self,
+        path: impl AsRef<Path>,
+        output: &OutputSettings,
+        metadata: Option<&RenderMetadata>,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let _span = tracing::info_span!("export", path = %path.display()).entered();
+        let format = ImageFormat::from_path(path).ok_or_else(|| {
+            Error::Encode(format!("unsupported output format: {}", path.display()))
+        })?;
+
+        // PPM conversion encodes by itself since it is also used for writing to stdout.
+        let encoded = self.encoded(output.color_space);
+        match format {
+            ImageFormat::Ppm => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                writer.write_all(self.convert_to_ppm_with(output).as_bytes())?;
+                writer.flush()?;
+                Ok(())
+            }
+            ImageFormat::Png16 => {
+                encoded.write_png16(BufWriter::new(File::create(path)?), output.dither, metadata)
+            }
+            ImageFormat::Tiff16 => encoded.write_tiff16(
+                &mut BufWriter::new(File::create(path)?),
+                output.dither,
+                metadata,
+            ),
+            ImageFormat::Jpeg => encoded.write_jpeg(
+                BufWriter::new(File::create(path)?),
+                output.jpeg_quality,
+                output.dither,
+            ),
+        }
+    }
+
+    pub fn to_rgb8(&self, dither: Dither) -> Vec<u8> {
+        self.iter_pixels()
+            .flat_map(|(x, y, p)| {
+                [
+                    dither.quantize(p.r, x, y),
+                    dither.quantize(p.g, x, y),
+                    dither.quantize(p.b, x, y),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn to_rgb16(&self, dither: Dither) -> Vec<u16> {
+        self.iter_pixels()
+            .flat_map(|(x, y, p)| {
+                [
+                    dither.quantize16(p.r, x, y),
+                    dither.quantize16(p.g, x, y),
+                    dither.quantize16(p.b, x, y),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn write_png16<W: Write>(
+        &self,
+        writer: W,
+        dither: Dither,
+        metadata: Option<&RenderMetadata>,
+    ) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        for (key, value) in metadata.map(RenderMetadata::entries).unwrap_or_default() {
+            encoder.add_text_chunk(key.to_string(), value)?;
+        }
+
+        let data = self
+            .to_rgb16(dither)
+            .into_iter()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
+    }
+
+    pub fn save_as_png16(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_png16(BufWriter::new(File::create(path)?), Dither::None, None)
+    }
+
+    pub fn write_tiff16<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        dither: Dither,
+        metadata: Option<&RenderMetadata>,
+    ) -> Result<()> {
+        let mut encoder = TiffEncoder::new(writer)?;
+        let mut image =
+            encoder.new_image::<colortype::RGB16>(self.width as u32, self.height as u32)?;
+        if let Some(metadata) = metadata {
+            image
+                .encoder()
+                .write_tag(Tag::ImageDescription, metadata.caption().as_str())?;
+            image.encoder().write_tag(
+                Tag::Software,
+                format!("ray-tracer-challange {}", metadata.version).as_str(),
+            )?;
+        }
+        image.write_data(&self.to_rgb16(dither))?;
+        Ok(())
+    }
+
+    pub fn save_as_tiff16(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_tiff16(&mut BufWriter::new(File::create(path)?), Dither::None, None)
+    }
+
+    pub fn write_jpeg<W: Write>(&self, writer: W, quality: u8, dither: Dither) -> Result<()> {
+        let (Ok(width), Ok(height)) = (u16::try_from(self.width), u16::try_from(self.height))
+        else {
+            return Err(Error::Encode(format!(
+                "{}x{} is too large for JPEG",
+                self.width, self.height
+            )));
+        };
+        jpeg_encoder::Encoder::new(writer, quality.clamp(1, 100)).encode(
+            &self.to_rgb8(dither),
+            width,
+            height,
+            jpeg_encoder::ColorType::Rgb,
+        )?;
+        Ok(())
+    }
+
+    pub fn save_as_jpeg(&self, path: impl AsRef<Path>, quality: u8) -> Result<()> {
+        self.write_jpeg(BufWriter::new(File::create(path)?), quality, Dither::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::path::Path;
+
+    use crate::canvas::dither::Dither;
+    use crate::canvas::export::ImageFormat;
+    use crate::canvas::metadata::RenderMetadata;
+    use crate::canvas::Canvas;
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    fn gradient() -> Canvas {
+        let mut c = Canvas::new(4, 2);
+        c.par_map_pixels(|x, y, _| Color::new(x as f32 / 3., y as f32, 2.));
+        c
+    }
+
+    #[test]
+    pub fn sixteen_bit_values_are_clamped_and_scaled() {
+        let c = gradient();
+        let data = c.to_rgb16(Dither::None);
+        assert_eq!(&data[..6], &[0, 0, 65535, 21845, 0, 65535]);
+    }
+
+    #[test]
+    pub fn png_round_trips_at_sixteen_bits() {
+        let c = gradient();
+        let mut bytes = vec![];
+        c.write_png16(&mut bytes, Dither::None, None).unwrap();
+
+        let decoder = png::Decoder::new(Cursor::new(bytes));
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+        assert_eq!((info.width, info.height), (4, 2));
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+
+        let decoded = buffer[..info.buffer_size()]
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, c.to_rgb16(Dither::None));
+    }
+
+    #[test]
+    pub fn tiff_round_trips_at_sixteen_bits() {
+        let c = gradient();
+        let mut cursor = Cursor::new(vec![]);
+        c.write_tiff16(&mut cursor, Dither::None, None).unwrap();
+        cursor.set_position(0);
+
+        let mut decoder = tiff::decoder::Decoder::new(cursor).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (4, 2));
+        match decoder.read_image().unwrap() {
+            tiff::decoder::DecodingResult::U16(data) => assert_eq!(data, c.to_rgb16(Dither::None)),
+            _ => panic!("expected 16-bit samples"),
+        }
+    }
+
+    #[test_case("out.ppm", Some(ImageFormat::Ppm))]
+    #[test_case("out.PNG", Some(ImageFormat::Png16))]
+    #[test_case("renders/out.tiff", Some(ImageFormat::Tiff16))]
+    #[test_case("out.jpeg", Some(ImageFormat::Jpeg))]
+    #[test_case("out.exr", None)]
+    #[test_case("out", None)]
+    pub fn format_is_chosen_by_extension(path: &str, expected: Option<ImageFormat>) {
+        assert_eq!(ImageFormat::from_path(Path::new(path)), expected);
+    }
+
+    #[test]
+    pub fn jpeg_output_is_a_jfif_stream() {
+        let c = gradient();
+        let mut bytes = vec![];
+        c.write_jpeg(&mut bytes, 80, Dither::None).unwrap();
+        assert_eq!(&bytes[..2], &[0xFF, 0xD8]);
+        assert_eq!(&bytes[bytes.len() - 2..], &[0xFF, 0xD9]);
+    }
+
+    #[test]
+    pub fn png_carries_render_metadata() {
+        let c = gradient();
+        let metadata = RenderMetadata {
+            samples_per_pixel: 4,
+            ..RenderMetadata::new("gradient")
+        };
+        let mut bytes = vec![];
+        c.write_png16(&mut bytes, Dither::None, Some(&metadata))
+            .unwrap();
+
+        let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+        let text = &reader.info().uncompressed_latin1_text;
+        assert!(text
+            .iter()
+            .any(|t| t.keyword == "Title" && t.text == "gradient"));
+        assert!(text
+            .iter()
+            .any(|t| t.keyword == "Samples per pixel" && t.text == "4"));
+    }
+
+    #[test]
+    pub fn tiff_carries_render_caption() {
+        let c = gradient();
+        let metadata = RenderMetadata::new("gradient");
+        let mut cursor = Cursor::new(vec![]);
+        c.write_tiff16(&mut cursor, Dither::None, Some(&metadata))
+            .unwrap();
+        cursor.set_position(0);
+
+        let mut decoder = tiff::decoder::Decoder::new(cursor).unwrap();
+        let description = decoder
+            .get_tag_ascii_string(tiff::tags::Tag::ImageDescription)
+            .unwrap();
+        assert_eq!(description, metadata.caption());
+    }
+}
@@ -0,0 +1,243 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek};
+use std::path::Path;
+
+use crate::canvas::color_space::ColorSpace;
+use crate::canvas::export::ImageFormat;
+use crate::canvas::Canvas;
+use crate::error::{Error, Result};
+use crate::tuple::Color;
+
+impl Canvas {
+    // Reads back an image in any of the formats the renderer writes. Channel values are scaled to
+    // 0..1 without any colour-space conversion.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let _span = tracing::info_span!("load_image", path = %path.display()).entered();
+        let reader = BufReader::new(File::open(path)?);
+        match ImageFormat::from_path(path) {
+            Some(ImageFormat::Ppm) => Self::read_ppm(reader),
+            Some(ImageFormat::Png16) => Self::read_png(reader),
+            Some(ImageFormat::Tiff16) => Self::read_tiff(reader),
+            Some(ImageFormat::Jpeg) => Self::read_jpeg(reader),
+            None => Err(Error::Decode(format!(
+                "unsupported input format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    // Loads an image for use as a texture, decoding it from `space` into the linear values
+    // shading works with. Pass `ColorSpace::Srgb` for photos and painted colour maps.
+    pub fn load_texture(path: impl AsRef<Path>, space: ColorSpace) -> Result<Self> {
+        let mut texture = Self::load(path)?;
+        texture.decode(space);
+        Ok(texture)
+    }
+
+    pub fn decode(&mut self, space: ColorSpace) {
+        for p in &mut self.pixels {
+            *p = space.decode_color(*p);
+        }
+    }
+
+    pub fn read_jpeg(reader: impl Read) -> Result<Self> {
+        let mut decoder = jpeg_decoder::Decoder::new(reader);
+        let pixels = decoder.decode().map_err(|e| Error::Decode(e.to_string()))?;
+        let info = decoder
+            .info()
+            .ok_or_else(|| Error::Decode("JPEG has no frame".to_string()))?;
+        let channels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::L8 => 1,
+            jpeg_decoder::PixelFormat::RGB24 => 3,
+            other => {
+                return Err(Error::Decode(format!(
+                    "unsupported JPEG pixel format {other:?}"
+                )))
+            }
+        };
+        let samples = pixels
+            .iter()
+            .map(|&v| f32::from(v) / f32::from(u8::MAX))
+            .collect::<Vec<_>>();
+        from_samples(
+            usize::from(info.width),
+            usize::from(info.height),
+            &samples,
+            channels,
+        )
+    }
+
+    pub fn read_png(reader: impl BufRead + Seek) -> Result<Self> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let samples = match info.bit_depth {
+            png::BitDepth::Sixteen => buffer[..info.buffer_size()]
+                .chunks_exact(2)
+                .map(|b| f32::from(u16::from_be_bytes([b[0], b[1]])) / f32::from(u16::MAX))
+                .collect(),
+            _ => buffer[..info.buffer_size()]
+                .iter()
+                .map(|&b| f32::from(b) / f32::from(u8::MAX))
+                .collect::<Vec<_>>(),
+        };
+        let channels = info.color_type.samples();
+        from_samples(
+            info.width as usize,
+            info.height as usize,
+            &samples,
+            channels,
+        )
+    }
+
+    pub fn read_tiff(reader: impl Read + Seek) -> Result<Self> {
+        let decode_error = |e: tiff::TiffError| Error::Decode(e.to_string());
+        let mut decoder = tiff::decoder::Decoder::new(reader).map_err(decode_error)?;
+        let (width, height) = decoder.dimensions().map_err(decode_error)?;
+        let channels = match decoder.colortype().map_err(decode_error)? {
+            tiff::ColorType::Gray(_) => 1,
+            tiff::ColorType::GrayA(_) => 2,
+            tiff::ColorType::RGB(_) => 3,
+            tiff::ColorType::RGBA(_) => 4,
+            other => {
+                return Err(Error::Decode(format!(
+                    "unsupported TIFF colour type {other:?}"
+                )))
+            }
+        };
+        let samples = match decoder.read_image().map_err(decode_error)? {
+            tiff::decoder::DecodingResult::U8(data) => data
+                .iter()
+                .map(|&v| f32::from(v) / f32::from(u8::MAX))
+                .collect::<Vec<_>>(),
+            tiff::decoder::DecodingResult::U16(data) => data
+                .iter()
+                .map(|&v| f32::from(v) / f32::from(u16::MAX))
+                .collect(),
+            tiff::decoder::DecodingResult::F32(data) => data,
+            _ => return Err(Error::Decode("unsupported TIFF sample format".to_string())),
+        };
+        from_samples(width as usize, height as usize, &samples, channels)
+    }
+
+    // Plain (P3) PPM, as written by `convert_to_ppm`.
+    pub fn read_ppm(mut reader: impl BufRead) -> Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut tokens = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace);
+        if tokens.next() != Some("P3") {
+            return Err(Error::Decode("only plain P3 PPM is supported".to_string()));
+        }
+        let mut number = || -> Result<usize> {
+            tokens
+                .next()
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(|| Error::Decode("truncated or malformed PPM".to_string()))
+        };
+        let (width, height, max) = (number()?, number()?, number()?.max(1));
+        let samples = (0..width * height * 3)
+            .map(|_| number().map(|v| v as f32 / max as f32))
+            .collect::<Result<Vec<_>>>()?;
+        from_samples(width, height, &samples, 3)
+    }
+}
+
+// Builds a canvas from interleaved samples with one (grey), two (grey and alpha), three (RGB) or
+// four (RGBA) channels per pixel. Alpha is dropped.
+fn from_samples(width: usize, height: usize, samples: &[f32], channels: usize) -> Result<Canvas> {
+    if !(1..=4).contains(&channels) || samples.len() < width * height * channels {
+        return Err(Error::Decode(format!(
+            "expected {width}x{height} pixels with {channels} channels"
+        )));
+    }
+    let mut canvas = Canvas::new(width, height);
+    for (pixel, s) in canvas.pixels.iter_mut().zip(samples.chunks_exact(channels)) {
+        *pixel = match s.len() {
+            1 | 2 => Color::new(s[0], s[0], s[0]),
+            _ => Color::new(s[0], s[1], s[2]),
+        };
+    }
+    Ok(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::canvas::color_space::ColorSpace;
+    use crate::canvas::dither::Dither;
+    use crate::canvas::Canvas;
+    use crate::error::Error;
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+
+    fn gradient() -> Canvas {
+        let mut c = Canvas::new(3, 2);
+        c.par_map_pixels(|x, y, _| Color::new(x as f32 / 2., y as f32, 0.2));
+        c
+    }
+
+    #[test]
+    pub fn png_and_tiff_read_back_what_was_written() {
+        let c = gradient();
+        let mut png = vec![];
+        c.write_png16(&mut png, Dither::None, None).unwrap();
+        let mut tiff = Cursor::new(vec![]);
+        c.write_tiff16(&mut tiff, Dither::None, None).unwrap();
+        tiff.set_position(0);
+
+        for decoded in [
+            Canvas::read_png(Cursor::new(png)).unwrap(),
+            Canvas::read_tiff(tiff).unwrap(),
+        ] {
+            assert_eq!(decoded.to_rgb16(Dither::None), c.to_rgb16(Dither::None));
+        }
+    }
+
+    #[test]
+    pub fn ppm_reads_back_at_eight_bits() {
+        let c = gradient();
+        let decoded = Canvas::read_ppm(c.convert_to_ppm().as_bytes()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(
+            decoded.pixel_at(2, 1).unwrap(),
+            Color::new(1., 1., 51. / 255.)
+        );
+    }
+
+    #[test]
+    pub fn truncated_ppm_is_an_error() {
+        assert!(matches!(
+            Canvas::read_ppm("P3\n2 2\n255\n0 0 0\n".as_bytes()),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    pub fn jpeg_reads_back_close_to_what_was_written() {
+        let mut c = Canvas::new(16, 16);
+        c.par_map_pixels(|_, _, _| Color::new(0.5, 0.5, 0.5));
+        let mut jpeg = vec![];
+        c.write_jpeg(&mut jpeg, 95, Dither::None).unwrap();
+
+        let decoded = Canvas::read_jpeg(jpeg.as_slice()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (16, 16));
+        assert!((decoded.pixel_at(8, 8).unwrap().g - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    pub fn srgb_textures_are_decoded_to_linear() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(1., 0.5, 0.)).unwrap();
+        c.decode(ColorSpace::Srgb);
+        let p = c.pixel_at(0, 0).unwrap();
+        assert_eq!((p.r, p.b), (1., 0.));
+        assert!((p.g - 0.214).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use crate::canvas::font::GLYPH_HEIGHT;
+use crate::canvas::Canvas;
+use crate::tuple::Color;
+
+const CAPTION_PADDING: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderMetadata {
+    pub scene: String,
+    pub samples_per_pixel: usize,
+    pub max_depth: i32,
+    pub seed: Option<u64>,
+    pub render_time: Duration,
+    pub version: String,
+}
+
+impl RenderMetadata {
+    pub fn new(scene: impl Into<String>) -> Self {
+        Self {
+            scene: scene.into(),
+            samples_per_pixel: 1,
+            max_depth: 0,
+            seed: None,
+            render_time: Duration::ZERO,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![
+            ("Title", self.scene.clone()),
+            ("Software", format!("ray-tracer-challange {}", self.version)),
+            ("Samples per pixel", self.samples_per_pixel.to_string()),
+            ("Max depth", self.max_depth.to_string()),
+            (
+                "Render time",
+                format!("{:.2}s", self.render_time.as_secs_f32()),
+            ),
+        ];
+        if let Some(seed) = self.seed {
+            entries.push(("Seed", seed.to_string()));
+        }
+        entries
+    }
+
+    pub fn caption(&self) -> String {
+        let mut caption = format!(
+            "{} - spp {} - depth {} - {:.2}s",
+            self.scene,
+            self.samples_per_pixel,
+            self.max_depth,
+            self.render_time.as_secs_f32()
+        );
+        if let Some(seed) = self.seed {
+            caption.push_str(&format!(" - seed {seed}"));
+        }
+        caption.push_str(&format!(" - v{}", self.version));
+        caption
+    }
+}
+
+impl Canvas {
+    pub fn with_caption(&self, caption: &str) -> Self {
+        let strip = GLYPH_HEIGHT + 2 * CAPTION_PADDING;
+        let mut captioned = Self::new(self.width, self.height + strip);
+        captioned.par_map_pixels(|x, y, _| {
+            if y < self.height {
+                self.pixels[y * self.width + x]
+            } else {
+                Color::new(0.1, 0.1, 0.1)
+            }
+        });
+        captioned.draw_text(
+            CAPTION_PADDING,
+            self.height + CAPTION_PADDING,
+            caption,
+            Color::white(),
+            1,
+        );
+        captioned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::canvas::metadata::RenderMetadata;
+    use crate::canvas::Canvas;
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+
+    fn metadata() -> RenderMetadata {
+        RenderMetadata {
+            samples_per_pixel: 16,
+            max_depth: 5,
+            seed: Some(42),
+            render_time: Duration::from_millis(1500),
+            ..RenderMetadata::new("cubes")
+        }
+    }
+
+    #[test]
+    pub fn caption_lists_render_parameters() {
+        assert_eq!(
+            metadata().caption(),
+            format!(
+                "cubes - spp 16 - depth 5 - 1.50s - seed 42 - v{}",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    pub fn seed_is_only_listed_when_known() {
+        let mut m = metadata();
+        assert!(m.entries().iter().any(|(k, v)| *k == "Seed" && v == "42"));
+        m.seed = None;
+        assert!(m.entries().iter().all(|(k, _)| *k != "Seed"));
+    }
+
+    #[test]
+    pub fn caption_strip_is_added_below_the_image() {
+        let mut c = Canvas::new(200, 10);
+        c.write_pixel(3, 9, Color::new(1., 0., 0.)).unwrap();
+        let captioned = c.with_caption("SPP 1");
+        assert_eq!(captioned.width, 200);
+        assert!(captioned.height > 10);
+        assert_eq!(captioned.pixel_at(3, 9).unwrap(), Color::new(1., 0., 0.));
+        assert!(captioned
+            .iter_pixels()
+            .any(|(_, y, &c)| y >= 10 && c == Color::white()));
+    }
+}
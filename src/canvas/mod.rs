@@ -1,7 +1,20 @@
+pub mod ascii;
+pub mod color_space;
+pub mod comparison;
+pub mod diff;
+pub mod dither;
+pub mod export;
+pub mod font;
+pub mod import;
+pub mod metadata;
+
+use crate::canvas::color_space::ColorSpace;
+use crate::error::{Error, Result};
+use crate::settings::OutputSettings;
 use crate::tuple::{Color, Point};
-use color_eyre::eyre::eyre;
-use color_eyre::Result;
+use rayon::prelude::*;
 
+#[derive(Debug, Clone)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -22,11 +35,12 @@ impl Canvas {
 
     fn index_at(&self, x: usize, y: usize) -> Result<usize> {
         if x >= self.width || y >= self.height {
-            return Err(eyre!(
-                "Index out of bounds: ({x}, {y}). Canvas size: ({}, {})",
-                self.width,
-                self.height
-            ));
+            return Err(Error::OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            });
         }
 
         Ok(y * self.width + x)
@@ -38,11 +52,52 @@ impl Canvas {
         Ok(())
     }
 
+    // Infallible write for the renderer's own loops, such as film development, whose
+    // coordinates come from a grid of the canvas's own size. Bounds are only checked in debug
+    // builds; past the last row the write still panics, but an `x` beyond the width lands in the
+    // next row.
+    pub(crate) fn set(&mut self, x: usize, y: usize, color: Color) {
+        debug_assert!(
+            x < self.width && y < self.height,
+            "pixel ({x}, {y}) is outside the {}x{} canvas",
+            self.width,
+            self.height
+        );
+        self.pixels[y * self.width + x] = color;
+    }
+
     pub fn pixel_at(&self, x: usize, y: usize) -> Result<Color> {
         let index = self.index_at(x, y)?;
         Ok(self.pixels[index])
     }
 
+    pub fn iter_pixels(&self) -> impl Iterator<Item = (usize, usize, &Color)> {
+        let width = self.width;
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(move |(i, c)| (i % width, i / width, c))
+    }
+
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut Color)> {
+        let width = self.width;
+        self.pixels
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, c)| (i % width, i / width, c))
+    }
+
+    pub fn par_map_pixels<F>(&mut self, f: F)
+    where
+        F: Fn(usize, usize, Color) -> Color + Sync,
+    {
+        let width = self.width;
+        self.pixels
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(i, c)| *c = f(i % width, i / width, *c));
+    }
+
     pub fn draw_circle(&mut self, x: usize, y: usize, radius: u32) -> Result<()> {
         for i in x.saturating_sub(radius as usize)..x + radius as usize {
             for j in y.saturating_sub(radius as usize)..y + radius as usize {
@@ -55,17 +110,25 @@ impl Canvas {
         Ok(())
     }
 
+    // The book's PPM output, with linear values.
     pub fn convert_to_ppm(&self) -> String {
+        self.convert_to_ppm_with(&OutputSettings {
+            color_space: ColorSpace::Linear,
+            ..Default::default()
+        })
+    }
+
+    pub fn convert_to_ppm_with(&self, output: &OutputSettings) -> String {
         let mut ppm = String::new();
         ppm.push_str("P3\n");
         ppm.push_str(format!("{} {}\n", self.width, self.height).as_str());
         ppm.push_str("255\n");
 
         let mut char_count = 0;
-        for pixel in &self.pixels {
-            let r = (pixel.r * 255.) as u8;
-            let g = (pixel.g * 255.) as u8;
-            let b = (pixel.b * 255.) as u8;
+        for (x, y, pixel) in self.encoded(output.color_space).iter_pixels() {
+            let r = output.dither.quantize(pixel.r, x, y);
+            let g = output.dither.quantize(pixel.g, x, y);
+            let b = output.dither.quantize(pixel.b, x, y);
 
             ppm.push_str(format!("{r} {g} {b} ").as_str());
             if char_count > 70 - 12 {
@@ -84,6 +147,8 @@ impl Canvas {
 #[cfg(test)]
 mod tests {
     use crate::canvas::Canvas;
+    use crate::error::Error;
+    use crate::tuple::Color;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -106,4 +171,61 @@ mod tests {
             crate::tuple::Color::new(1., 0., 0.)
         );
     }
+
+    #[test]
+    pub fn set_matches_write_pixel() {
+        let mut checked = Canvas::new(4, 3);
+        let mut unchecked = Canvas::new(4, 3);
+        checked.write_pixel(3, 2, Color::white()).unwrap();
+        unchecked.set(3, 2, Color::white());
+        assert_eq!(unchecked.pixels, checked.pixels);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "outside the 4x3 canvas")]
+    pub fn set_outside_canvas_panics_in_debug_builds() {
+        Canvas::new(4, 3).set(4, 0, Color::white());
+    }
+
+    #[test]
+    pub fn reading_outside_canvas_is_an_error() {
+        let c = Canvas::new(10, 20);
+        assert!(matches!(
+            c.pixel_at(10, 0),
+            Err(Error::OutOfBounds {
+                x: 10,
+                y: 0,
+                width: 10,
+                height: 20
+            })
+        ));
+    }
+
+    #[test]
+    pub fn iterating_pixels_yields_coordinates_in_row_order() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(2, 1, Color::white()).unwrap();
+        let coords = c.iter_pixels().map(|(x, y, _)| (x, y)).collect::<Vec<_>>();
+        assert_eq!(coords, vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+        assert_eq!(c.iter_pixels().last().unwrap().2, &Color::white());
+    }
+
+    #[test]
+    pub fn mutating_pixels_through_iterator() {
+        let mut c = Canvas::new(4, 3);
+        for (x, y, color) in c.pixels_mut() {
+            *color = Color::new(x as f32, y as f32, 0.);
+        }
+        assert_eq!(c.pixel_at(3, 2).unwrap(), Color::new(3., 2., 0.));
+    }
+
+    #[test]
+    pub fn parallel_map_over_pixels() {
+        let mut c = Canvas::new(5, 4);
+        c.write_pixel(1, 1, Color::new(0.5, 0.5, 0.5)).unwrap();
+        c.par_map_pixels(|x, y, color| color * 2. + Color::new(x as f32, y as f32, 0.));
+        assert_eq!(c.pixel_at(1, 1).unwrap(), Color::new(2., 2., 1.));
+        assert_eq!(c.pixel_at(4, 3).unwrap(), Color::new(4., 3., 0.));
+    }
 }
@@ -0,0 +1,361 @@
+// C interface for embedding the renderer in other languages, declared in `include/raytracer.h`.
+// That header is maintained by hand, not generated: any change to an exported function, constant
+// or `#[repr(C)]` struct here has to be mirrored there.
+// Worlds and cameras are opaque handles owned by the caller, and every `_new` has a matching
+// `_free`. Shapes added to a world belong to it and are freed with it. Build the library with
+// `cargo rustc --lib --release --features capi --crate-type cdylib` (or `staticlib`).
+
+use std::ffi::c_int;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use crate::camera::Camera;
+use crate::canvas::color_space::ColorSpace;
+use crate::canvas::dither::Dither;
+use crate::error::Error;
+use crate::light::{PointLight, DEFAULT_LIGHT_GROUP};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::shape::{Plane, Shape, Sphere};
+use crate::tuple::{Color, Point, Vector};
+use crate::world::World;
+
+pub const RT_OK: c_int = 0;
+pub const RT_NULL_POINTER: c_int = -1;
+pub const RT_SINGULAR_TRANSFORM: c_int = -2;
+pub const RT_BUFFER_TOO_SMALL: c_int = -3;
+pub const RT_RENDER_FAILED: c_int = -4;
+
+// A world together with the shapes the C side has added to it. The rest of the crate leaks
+// shapes for the lifetime of the program; here they are reclaimed once the world referring to
+// them is gone.
+pub struct RtWorld {
+    world: World,
+    shapes: Vec<*mut dyn Shape>,
+}
+
+impl Drop for RtWorld {
+    fn drop(&mut self) {
+        drop(std::mem::replace(&mut self.world, World::empty()));
+        for shape in self.shapes.drain(..) {
+            // SAFETY: every shape was leaked from a box by `add_shape`, and the world that
+            // borrowed it has just been dropped.
+            drop(unsafe { Box::from_raw(shape) });
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RtMaterial {
+    pub color: [f32; 3],
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    pub reflective: f32,
+    pub transparency: f32,
+    pub refractive_index: f32,
+}
+
+impl From<&RtMaterial> for Material {
+    fn from(m: &RtMaterial) -> Self {
+        let [r, g, b] = m.color;
+        Self {
+            color: Color::new(r, g, b),
+            ambient: m.ambient,
+            diffuse: m.diffuse,
+            specular: m.specular,
+            shininess: m.shininess,
+            reflective: m.reflective,
+            transparency: m.transparency,
+            refractive_index: m.refractive_index,
+            ..Default::default()
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rt_material_default() -> RtMaterial {
+    let m = Material::default();
+    RtMaterial {
+        color: [m.color.r, m.color.g, m.color.b],
+        ambient: m.ambient,
+        diffuse: m.diffuse,
+        specular: m.specular,
+        shininess: m.shininess,
+        reflective: m.reflective,
+        transparency: m.transparency,
+        refractive_index: m.refractive_index,
+    }
+}
+
+// An empty world with no objects and no lights.
+#[no_mangle]
+pub extern "C" fn rt_world_new() -> *mut RtWorld {
+    Box::into_raw(Box::new(RtWorld {
+        world: World::empty(),
+        shapes: vec![],
+    }))
+}
+
+/// # Safety
+/// `world` must come from `rt_world_new` and not have been freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_free(world: *mut RtWorld) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// # Safety
+/// `world` must be a live handle. `transform` is null for the identity or points to 16 floats
+/// in row-major order; `material` is null for the default material or points to one material.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_sphere(
+    world: *mut RtWorld,
+    transform: *const f32,
+    material: *const RtMaterial,
+) -> c_int {
+    add_shape(world, transform, material, |m| {
+        Sphere::default_with_material(m)
+    })
+}
+
+/// # Safety
+/// As for `rt_world_add_sphere`. The untransformed plane is y = 0.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_plane(
+    world: *mut RtWorld,
+    transform: *const f32,
+    material: *const RtMaterial,
+) -> c_int {
+    add_shape(world, transform, material, |m| {
+        Plane::default_with_material(m)
+    })
+}
+
+/// # Safety
+/// `world` must be a live handle; `position` and `intensity` must each point to 3 floats.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_light(
+    world: *mut RtWorld,
+    position: *const f32,
+    intensity: *const f32,
+) -> c_int {
+    let (Some(world), Some([x, y, z]), Some([r, g, b])) =
+        (world.as_mut(), read3(position), read3(intensity))
+    else {
+        return RT_NULL_POINTER;
+    };
+    world.world.add_light(
+        DEFAULT_LIGHT_GROUP,
+        PointLight::new(Point::new(x, y, z), Color::new(r, g, b)),
+    );
+    RT_OK
+}
+
+#[no_mangle]
+pub extern "C" fn rt_camera_new(width: usize, height: usize, field_of_view: f32) -> *mut Camera {
+    Box::into_raw(Box::new(Camera::new(width, height, field_of_view)))
+}
+
+/// # Safety
+/// `camera` must come from `rt_camera_new` and not have been freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_camera_free(camera: *mut Camera) {
+    if !camera.is_null() {
+        drop(Box::from_raw(camera));
+    }
+}
+
+/// # Safety
+/// `camera` must be a live handle; `from`, `to` and `up` must each point to 3 floats.
+#[no_mangle]
+pub unsafe extern "C" fn rt_camera_look_at(
+    camera: *mut Camera,
+    from: *const f32,
+    to: *const f32,
+    up: *const f32,
+) -> c_int {
+    let (Some(camera), Some(from), Some(to), Some(up)) =
+        (camera.as_mut(), read3(from), read3(to), read3(up))
+    else {
+        return RT_NULL_POINTER;
+    };
+    camera.set_transform(
+        Point::new(from[0], from[1], from[2]),
+        Point::new(to[0], to[1], to[2]),
+        Vector::new(up[0], up[1], up[2]),
+    );
+    RT_OK
+}
+
+/// # Safety
+/// `camera` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rt_camera_set_samples(camera: *mut Camera, samples: usize) -> c_int {
+    let Some(camera) = camera.as_mut() else {
+        return RT_NULL_POINTER;
+    };
+    camera.samples_pre_pixel = samples.max(1);
+    RT_OK
+}
+
+/// # Safety
+/// `world` and `camera` must be live handles and `rgba` must point to `len` writable bytes.
+/// The image is written as 8-bit sRGB RGBA rows, top to bottom, and needs
+/// `width * height * 4` bytes. A render that fails, or panics, returns `RT_RENDER_FAILED` and
+/// leaves the buffer in an unspecified state.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render(
+    world: *mut RtWorld,
+    camera: *const Camera,
+    rgba: *mut u8,
+    len: usize,
+) -> c_int {
+    let (Some(world), Some(camera)) = (world.as_mut(), camera.as_ref()) else {
+        return RT_NULL_POINTER;
+    };
+    if rgba.is_null() {
+        return RT_NULL_POINTER;
+    }
+    // A size that overflows can't fit in any buffer.
+    let needed = camera
+        .hsize
+        .checked_mul(camera.vsize)
+        .and_then(|pixels| pixels.checked_mul(4));
+    if needed.is_none_or(|needed| len < needed) {
+        return RT_BUFFER_TOO_SMALL;
+    }
+
+    let out = slice::from_raw_parts_mut(rgba, len);
+
+    // Unwinding across the C boundary is undefined, so panics end here.
+    let rendered = panic::catch_unwind(AssertUnwindSafe(|| {
+        let world = &mut world.world;
+        world.build_acceleration();
+        let rgb = camera
+            .render(world)?
+            .encoded(ColorSpace::Srgb)
+            .to_rgb8(Dither::None);
+        for (pixel, rgb) in out.chunks_exact_mut(4).zip(rgb.chunks_exact(3)) {
+            pixel[..3].copy_from_slice(rgb);
+            pixel[3] = u8::MAX;
+        }
+        Ok::<_, Error>(())
+    }));
+    match rendered {
+        Ok(Ok(())) => RT_OK,
+        Ok(Err(_)) | Err(_) => RT_RENDER_FAILED,
+    }
+}
+
+unsafe fn add_shape(
+    world: *mut RtWorld,
+    transform: *const f32,
+    material: *const RtMaterial,
+    create: impl FnOnce(Material) -> &'static mut dyn Shape,
+) -> c_int {
+    let Some(world) = world.as_mut() else {
+        return RT_NULL_POINTER;
+    };
+    let material = material.as_ref().map(Material::from).unwrap_or_default();
+    let shape: *mut dyn Shape = create(material);
+    if !transform.is_null() {
+        let rows = slice::from_raw_parts(transform, 16);
+        let transform = Matrix4::from(nalgebra::Matrix4::from_row_slice(rows));
+        if (*shape).replace_transform(transform).is_err() {
+            drop(Box::from_raw(shape));
+            return RT_SINGULAR_TRANSFORM;
+        }
+    }
+    world.shapes.push(shape);
+    world.world.objects.push(&*shape);
+    RT_OK
+}
+
+unsafe fn read3(values: *const f32) -> Option<[f32; 3]> {
+    (!values.is_null()).then(|| [*values, *values.add(1), *values.add(2)])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::capi::*;
+    use pretty_assertions::assert_eq;
+    use std::ptr;
+
+    #[test]
+    pub fn renders_a_lit_sphere_into_an_rgba_buffer() {
+        unsafe {
+            let world = rt_world_new();
+            let material = RtMaterial {
+                color: [1., 0., 0.],
+                ..rt_material_default()
+            };
+            assert_eq!(rt_world_add_sphere(world, ptr::null(), &material), RT_OK);
+            assert_eq!(
+                rt_world_add_light(world, [-10., 10., -10.].as_ptr(), [1., 1., 1.].as_ptr()),
+                RT_OK
+            );
+
+            let camera = rt_camera_new(5, 5, std::f32::consts::FRAC_PI_2);
+            let (from, to, up) = ([0., 0., -5.], [0., 0., 0.], [0., 1., 0.]);
+            assert_eq!(
+                rt_camera_look_at(camera, from.as_ptr(), to.as_ptr(), up.as_ptr()),
+                RT_OK
+            );
+
+            let mut rgba = vec![0; 5 * 5 * 4];
+            assert_eq!(
+                rt_render(world, camera, rgba.as_mut_ptr(), rgba.len() - 1),
+                RT_BUFFER_TOO_SMALL
+            );
+            assert_eq!(
+                rt_render(world, camera, rgba.as_mut_ptr(), rgba.len()),
+                RT_OK
+            );
+            let center = &rgba[(2 * 5 + 2) * 4..][..4];
+            assert!(center[0] > 0 && center[1] == 0 && center[2] == 0);
+            assert_eq!(center[3], 255);
+            assert_eq!(&rgba[..4], &[0, 0, 0, 255]);
+
+            rt_camera_free(camera);
+            rt_world_free(world);
+        }
+    }
+
+    #[test]
+    pub fn singular_transforms_and_null_handles_are_reported() {
+        unsafe {
+            let world = rt_world_new();
+            let flat = [0.; 16];
+            assert_eq!(
+                rt_world_add_plane(world, flat.as_ptr(), ptr::null()),
+                RT_SINGULAR_TRANSFORM
+            );
+            assert_eq!((*world).world.objects.len(), 0);
+            assert!((*world).shapes.is_empty());
+            assert_eq!(
+                rt_world_add_sphere(ptr::null_mut(), ptr::null(), ptr::null()),
+                RT_NULL_POINTER
+            );
+            rt_world_free(world);
+        }
+    }
+
+    #[test]
+    pub fn overflowing_image_sizes_are_too_small_for_any_buffer() {
+        unsafe {
+            let world = rt_world_new();
+            let camera = rt_camera_new(usize::MAX / 2, 3, std::f32::consts::FRAC_PI_2);
+            let mut rgba = vec![0; 16];
+            assert_eq!(
+                rt_render(world, camera, rgba.as_mut_ptr(), rgba.len()),
+                RT_BUFFER_TOO_SMALL
+            );
+            rt_camera_free(camera);
+            rt_world_free(world);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+pub const CONFIG_FILE: &str = "raytracer.toml";
+// Names a config file to use instead of `raytracer.toml` in the working directory.
+pub const CONFIG_PATH_VAR: &str = "RAYTRACER_CONFIG";
+const ENV_PREFIX: &str = "RAYTRACER_";
+const KEYS: [&str; 5] = ["width", "height", "samples", "threads", "output_dir"];
+
+// Defaults for the command line, read from `raytracer.toml` and from `RAYTRACER_*` environment
+// variables such as `RAYTRACER_SAMPLES=4`. Unset entries fall through to the next source:
+// flags, then the environment, then the file, then the built-in defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub samples: Option<usize>,
+    pub threads: Option<usize>,
+    pub output_dir: Option<PathBuf>,
+}
+
+impl Config {
+    // The file named by `RAYTRACER_CONFIG`, or `raytracer.toml` if there is one, overridden by
+    // the environment.
+    pub fn discover() -> Result<Self> {
+        let file = match std::env::var_os(CONFIG_PATH_VAR) {
+            Some(path) => Self::load(Path::new(&path))?,
+            None if Path::new(CONFIG_FILE).exists() => Self::load(Path::new(CONFIG_FILE))?,
+            None => Self::default(),
+        };
+        Ok(file.merge(Self::from_env(std::env::vars())?))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?, path)
+    }
+
+    // The flat `key = value` subset of TOML: one setting per line, `#` comments, bare integers
+    // and quoted strings. Tables aren't needed for these settings and are rejected. `path` is
+    // only used to name the file in errors.
+    pub fn parse(text: &str, path: &Path) -> Result<Self> {
+        let mut config = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| config_error(path, number + 1, "expected `key = value`"))?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            config
+                .set(key.trim(), value)
+                .map_err(|msg| config_error(path, number + 1, &msg))?;
+        }
+        Ok(config)
+    }
+
+    // Settings from variables named after the keys, upper-cased behind `RAYTRACER_`. Other
+    // variables are ignored, and `RAYTRACER_*` ones that match no setting only get a warning,
+    // since the environment may hold them for other tools or versions.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut config = Self::default();
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if name == CONFIG_PATH_VAR {
+                continue;
+            }
+            let key = key.to_ascii_lowercase();
+            if !KEYS.contains(&key.as_str()) {
+                tracing::warn!(variable = name, "ignoring unknown setting");
+                continue;
+            }
+            config
+                .set(&key, &value)
+                .map_err(|msg| Error::Config(format!("{name}: {msg}")))?;
+        }
+        Ok(config)
+    }
+
+    // Entries set in `overrides` replace those in `self`.
+    pub fn merge(self, overrides: Self) -> Self {
+        Self {
+            width: overrides.width.or(self.width),
+            height: overrides.height.or(self.height),
+            samples: overrides.samples.or(self.samples),
+            threads: overrides.threads.or(self.threads),
+            output_dir: overrides.output_dir.or(self.output_dir),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("`{key}` must be a non-negative integer, not `{value}`"))
+        };
+        match key {
+            "width" => self.width = Some(number()?),
+            "height" => self.height = Some(number()?),
+            "samples" => self.samples = Some(number()?),
+            "threads" => self.threads = Some(number()?),
+            "output_dir" => self.output_dir = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown setting `{key}`")),
+        }
+        Ok(())
+    }
+}
+
+// Drops a trailing comment, leaving `#` inside quoted strings alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn config_error(path: &Path, line: usize, msg: &str) -> Error {
+    Error::Config(format!("{} line {line}: {msg}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::config::{Config, CONFIG_FILE};
+    use crate::error::Error;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn file_settings_are_parsed() {
+        let config = Config::parse(
+            "# defaults for quick previews
+width = 640
+height = 480 # 4:3
+samples = 4
+output_dir = \"renders/#drafts\"
+",
+            Path::new(CONFIG_FILE),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                width: Some(640),
+                height: Some(480),
+                samples: Some(4),
+                threads: None,
+                output_dir: Some(PathBuf::from("renders/#drafts")),
+            }
+        );
+    }
+
+    #[test]
+    pub fn environment_overrides_the_file() {
+        let file = Config::parse("width = 640\nsamples = 4\n", Path::new(CONFIG_FILE)).unwrap();
+        let env = Config::from_env([
+            ("RAYTRACER_SAMPLES".to_string(), "16".to_string()),
+            ("RAYTRACER_CONFIG".to_string(), "elsewhere.toml".to_string()),
+            (
+                "RAYTRACER_FROM_A_NEWER_VERSION".to_string(),
+                "1".to_string(),
+            ),
+            ("HOME".to_string(), "/root".to_string()),
+        ])
+        .unwrap();
+        let merged = file.merge(env);
+        assert_eq!(merged.width, Some(640));
+        assert_eq!(merged.samples, Some(16));
+    }
+
+    #[test]
+    pub fn bad_lines_are_reported() {
+        let path = Path::new("scenes/preview.toml");
+        assert!(matches!(
+            Config::parse("width = 640\nsamples = lots\n", path),
+            Err(Error::Config(msg)) if msg.starts_with("scenes/preview.toml line 2")
+        ));
+        assert!(matches!(
+            Config::parse("[render]\n", path),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    pub fn bad_values_in_the_environment_are_reported() {
+        assert!(matches!(
+            Config::from_env([("RAYTRACER_SAMPLES".to_string(), "lots".to_string())]),
+            Err(Error::Config(msg)) if msg.starts_with("RAYTRACER_SAMPLES")
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::bounds::Bounds;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::shape::{Cube, Shape};
+use crate::tuple::{Color, Point, Vector};
+
+// Helpers that build thin boxes for checking orientation and extents while composing a scene.
+// The boxes are fully ambient so they read the same regardless of lighting.
+
+const LINE_THICKNESS: f32 = 0.02;
+
+pub fn axes_gizmo(length: f32) -> Vec<&'static dyn Shape> {
+    let thickness = length * LINE_THICKNESS;
+    [
+        (Point::new(length, 0., 0.), Color::new(1., 0., 0.)),
+        (Point::new(0., length, 0.), Color::new(0., 1., 0.)),
+        (Point::new(0., 0., length), Color::new(0., 0., 1.)),
+    ]
+    .into_iter()
+    .map(|(end, color)| strut(Point::zero(), end, thickness, color))
+    .collect()
+}
+
+// Lines every `spacing` units in x and z on the y = 0 plane, covering -extent..=extent.
+pub fn ground_grid(spacing: f32, extent: f32) -> Vec<&'static dyn Shape> {
+    let thickness = spacing * LINE_THICKNESS;
+    let color = Color::new(0.5, 0.5, 0.5);
+    let lines = (extent / spacing).floor() as i32;
+    (-lines..=lines)
+        .flat_map(|i| {
+            let offset = i as f32 * spacing;
+            [
+                strut(
+                    Point::new(-extent, 0., offset),
+                    Point::new(extent, 0., offset),
+                    thickness,
+                    color,
+                ),
+                strut(
+                    Point::new(offset, 0., -extent),
+                    Point::new(offset, 0., extent),
+                    thickness,
+                    color,
+                ),
+            ]
+        })
+        .collect()
+}
+
+// The twelve edges of the shape's world-space bounds. Unbounded shapes get no wireframe.
+pub fn bounding_box_wireframe(shape: &dyn Shape) -> Vec<&'static dyn Shape> {
+    let bounds = shape.get_bounds();
+    if !bounds.is_finite() || bounds.is_empty() {
+        return vec![];
+    }
+
+    let largest = (0..3)
+        .map(|axis| bounds.max_at(axis) - bounds.min_at(axis))
+        .fold(0., f32::max);
+    let thickness = (largest * LINE_THICKNESS / 2.).max(f32::EPSILON);
+    let color = Color::new(1., 1., 0.);
+    let corner = |x: bool, y: bool, z: bool| {
+        let pick = |axis: usize, max: bool| {
+            if max {
+                bounds.max_at(axis)
+            } else {
+                bounds.min_at(axis)
+            }
+        };
+        Point::new(pick(0, x), pick(1, y), pick(2, z))
+    };
+
+    let mut edges = vec![];
+    for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+        edges.push(strut(
+            corner(false, a, b),
+            corner(true, a, b),
+            thickness,
+            color,
+        ));
+        edges.push(strut(
+            corner(a, false, b),
+            corner(a, true, b),
+            thickness,
+            color,
+        ));
+        edges.push(strut(
+            corner(a, b, false),
+            corner(a, b, true),
+            thickness,
+            color,
+        ));
+    }
+    edges
+}
+
+// An axis-aligned box around the segment from `from` to `to`.
+fn strut(from: Point, to: Point, thickness: f32, color: Color) -> &'static dyn Shape {
+    let mut bounds = Bounds::empty();
+    bounds.add_point(&from);
+    bounds.add_point(&to);
+    for axis in 0..3 {
+        bounds.set_min_at(axis, bounds.min_at(axis) - thickness / 2.);
+        bounds.set_max_at(axis, bounds.max_at(axis) + thickness / 2.);
+    }
+
+    let half_size = (bounds.max - bounds.min) * 0.5;
+    let center = bounds.min + half_size;
+    let cube = Cube::default_with_material(Material {
+        color,
+        ambient: 1.,
+        diffuse: 0.,
+        specular: 0.,
+        ..Default::default()
+    });
+    cube.set_transform(
+        Matrix4::identity()
+            .scale(&Vector::new(half_size.x, half_size.y, half_size.z))
+            .translate(&(center - Point::zero())),
+    );
+    cube
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::Bounds;
+    use crate::debug_scene::{axes_gizmo, bounding_box_wireframe, ground_grid};
+    use crate::shape::{Plane, Sphere};
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn gizmo_axes_are_colored_by_direction() {
+        let axes = axes_gizmo(2.);
+        assert_eq!(axes.len(), 3);
+        assert_eq!(axes[1].get_material().color, Color::new(0., 1., 0.));
+        let x = axes[0].get_bounds();
+        assert_eq!(x.min, Point::new(-0.02, -0.02, -0.02));
+        assert_eq!(x.max, Point::new(2.02, 0.02, 0.02));
+    }
+
+    #[test]
+    pub fn grid_has_lines_in_both_directions() {
+        assert_eq!(ground_grid(1., 2.).len(), 10);
+    }
+
+    #[test]
+    pub fn wireframe_encloses_the_shape() {
+        let s = Sphere::new(Point::new(1., 0., 0.), 2.);
+        let edges = bounding_box_wireframe(s);
+        assert_eq!(edges.len(), 12);
+        let enclosing = edges
+            .iter()
+            .fold(Bounds::empty(), |b, e| b.merge(&e.get_bounds()));
+        assert_eq!(enclosing.min, Point::new(-1.02, -2.02, -2.02));
+        assert_eq!(enclosing.max, Point::new(3.02, 2.02, 2.02));
+
+        assert!(bounding_box_wireframe(Plane::static_default()).is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::{Display, Formatter};
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub enum Error {
+    OutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    SingularMatrix,
+    SingularTransform(Uuid),
+    Io(std::io::Error),
+    Encode(String),
+    Decode(String),
+    SizeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    SceneParse(String),
+    Import(String),
+    Config(String),
+    ThreadPool(rayon::ThreadPoolBuildError),
+    StaleAcceleration,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "Index out of bounds: ({x}, {y}). Canvas size: ({width}, {height})"
+            ),
+            Self::SingularMatrix => write!(f, "Matrix is not invertible"),
+            Self::SingularTransform(id) => {
+                write!(f, "Transform of object {id} is not invertible")
+            }
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::Encode(msg) => write!(f, "Failed to encode image: {msg}"),
+            Self::Decode(msg) => write!(f, "Failed to decode image: {msg}"),
+            Self::SizeMismatch {
+                expected: (w1, h1),
+                found: (w2, h2),
+            } => write!(f, "Image sizes differ: {w1}x{h1} and {w2}x{h2}"),
+            Self::SceneParse(msg) => write!(f, "Failed to parse scene: {msg}"),
+            Self::Import(msg) => write!(f, "Failed to import geometry: {msg}"),
+            Self::Config(msg) => write!(f, "Invalid configuration: {msg}"),
+            Self::ThreadPool(e) => write!(f, "Failed to start render threads: {e}"),
+            Self::StaleAcceleration => write!(
+                f,
+                "Acceleration structure does not match the world's objects; rebuild it before rendering"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::ThreadPool(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<rayon::ThreadPoolBuildError> for Error {
+    fn from(value: rayon::ThreadPoolBuildError) -> Self {
+        Self::ThreadPool(value)
+    }
+}
+
+impl From<png::EncodingError> for Error {
+    fn from(value: png::EncodingError) -> Self {
+        match value {
+            png::EncodingError::IoError(e) => Self::Io(e),
+            other => Self::Encode(other.to_string()),
+        }
+    }
+}
+
+impl From<png::DecodingError> for Error {
+    fn from(value: png::DecodingError) -> Self {
+        match value {
+            png::DecodingError::IoError(e) => Self::Io(e),
+            other => Self::Decode(other.to_string()),
+        }
+    }
+}
+
+impl From<jpeg_encoder::EncodingError> for Error {
+    fn from(value: jpeg_encoder::EncodingError) -> Self {
+        match value {
+            jpeg_encoder::EncodingError::IoError(e) => Self::Io(e),
+            other => Self::Encode(other.to_string()),
+        }
+    }
+}
+
+impl From<tiff::TiffError> for Error {
+    fn from(value: tiff::TiffError) -> Self {
+        match value {
+            tiff::TiffError::IoError(e) => Self::Io(e),
+            other => Self::Encode(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use pretty_assertions::assert_eq;
+    use std::error::Error as _;
+
+    #[test]
+    pub fn out_of_bounds_message_names_position_and_size() {
+        let e = Error::OutOfBounds {
+            x: 10,
+            y: 2,
+            width: 10,
+            height: 20,
+        };
+        assert_eq!(
+            e.to_string(),
+            "Index out of bounds: (10, 2). Canvas size: (10, 20)"
+        );
+    }
+
+    #[test]
+    pub fn io_errors_keep_their_source() {
+        let e: Error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing").into();
+        assert!(matches!(e, Error::Io(_)));
+        assert!(e.source().is_some());
+    }
+}
@@ -0,0 +1,73 @@
+use std::f32::consts::PI;
+
+// Photographic exposure settings. With lights given in candela, they turn the scene's radiance
+// into the 0..1 range the canvas stores, the way a real camera with the same settings would
+// expose it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Exposure {
+    pub iso: f32,
+    // Seconds.
+    pub shutter: f32,
+    pub f_number: f32,
+}
+
+impl Default for Exposure {
+    // "Sunny 16": f/16 at 1/100 s and ISO 100.
+    fn default() -> Self {
+        Self {
+            iso: 100.,
+            shutter: 0.01,
+            f_number: 16.,
+        }
+    }
+}
+
+impl Exposure {
+    pub const fn new(iso: f32, shutter: f32, f_number: f32) -> Self {
+        Self {
+            iso,
+            shutter,
+            f_number,
+        }
+    }
+
+    // Exposure value normalised to ISO 100.
+    pub fn ev100(&self) -> f32 {
+        (self.f_number * self.f_number / self.shutter * 100. / self.iso).log2()
+    }
+
+    // Factor from luminance in cd/m² to canvas values, which saturate at the luminance a sensor
+    // with these settings clips at (the standard output sensitivity model with its 1.2 headroom
+    // for a 78% saturation point).
+    pub fn scale(&self) -> f32 {
+        1. / (1.2 * 2f32.powf(self.ev100()))
+    }
+}
+
+// Luminous intensity of an isotropic point source emitting `lumens` in total.
+pub fn candela_from_lumens(lumens: f32) -> f32 {
+    lumens / (4. * PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exposure::{candela_from_lumens, Exposure};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn sunny_sixteen_is_about_ev_fifteen() {
+        let exposure = Exposure::default();
+        assert!(
+            (exposure.ev100() - 14.64).abs() < 0.01,
+            "{}",
+            exposure.ev100()
+        );
+        let doubled_iso = Exposure::new(200., 0.01, 16.);
+        assert!((doubled_iso.scale() / exposure.scale() - 2.).abs() < 1e-4);
+    }
+
+    #[test]
+    pub fn lumens_spread_over_the_whole_sphere() {
+        assert_eq!(candela_from_lumens(4. * std::f32::consts::PI), 1.);
+    }
+}
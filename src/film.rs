@@ -0,0 +1,249 @@
+use crate::canvas::Canvas;
+use crate::filter::Filter;
+use crate::tuple::Color;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+struct FilmPixel {
+    sum: Color,
+    weight: f32,
+    // Welford's running mean and squared deviation of the luminance of the samples taken for
+    // this pixel, independent of how they were filtered.
+    count: usize,
+    mean: f32,
+    m2: f32,
+}
+
+// Accumulates samples into per-pixel running sums. Keeping sums rather than finished colours
+// lets renders be refined progressively and variance drive where more samples are spent.
+#[derive(Debug, Clone)]
+pub struct Film {
+    pub width: usize,
+    pub height: usize,
+    pub filter: Filter,
+    pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize, filter: Filter) -> Self {
+        Self {
+            width,
+            height,
+            filter,
+            pixels: vec![FilmPixel::default(); width * height],
+        }
+    }
+
+    pub fn add_sample(&mut self, x: usize, y: usize, color: Color) {
+        self.add_filtered_sample(x as f32 + 0.5, y as f32 + 0.5, color);
+    }
+
+    // `film_x` and `film_y` are in pixels from the top-left corner of the image.
+    pub fn add_filtered_sample(&mut self, film_x: f32, film_y: f32, color: Color) {
+        if film_x < 0. || film_y < 0. {
+            return;
+        }
+        let (x, y) = (film_x as usize, film_y as usize);
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let owner = &mut self.pixels[y * self.width + x];
+        owner.count += 1;
+        let luminance = color.luminance();
+        let delta = luminance - owner.mean;
+        owner.mean += delta / owner.count as f32;
+        owner.m2 += delta * (luminance - owner.mean);
+
+        for (index, weight) in self.footprint(film_x, film_y) {
+            let pixel = &mut self.pixels[index];
+            pixel.sum += color * weight;
+            pixel.weight += weight;
+        }
+    }
+
+    pub fn sample_count(&self, x: usize, y: usize) -> usize {
+        self.pixels[y * self.width + x].count
+    }
+
+    // Unbiased sample variance of the pixel's luminance; zero until it has two samples.
+    pub fn variance(&self, x: usize, y: usize) -> f32 {
+        let pixel = &self.pixels[y * self.width + x];
+        if pixel.count < 2 {
+            0.
+        } else {
+            pixel.m2 / (pixel.count - 1) as f32
+        }
+    }
+
+    // The filtered radiance of every pixel. Values aren't clamped, so post-processing still sees
+    // highlights brighter than white.
+    pub fn develop(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for (i, pixel) in self.pixels.iter().enumerate() {
+            // Negative filter lobes can cancel a pixel's weight out, in which case it stays black.
+            if pixel.weight <= 0. {
+                continue;
+            }
+            // The film and the canvas share their size, so every index is in bounds.
+            canvas.set(
+                i % self.width,
+                i / self.width,
+                pixel.sum * (1. / pixel.weight),
+            );
+        }
+        canvas
+    }
+
+    // Index and filter weight of every pixel a sample contributes to.
+    fn footprint(&self, film_x: f32, film_y: f32) -> impl Iterator<Item = (usize, f32)> {
+        let (filter, width) = (self.filter, self.width);
+        let radius = filter.radius();
+        let range = |centre: f32, size: usize| {
+            let first = (centre - radius - 0.5).floor().max(0.) as usize;
+            let last = ((centre + radius - 0.5).ceil().max(0.) as usize).min(size - 1);
+            first..=last
+        };
+
+        let columns = range(film_x, self.width);
+        range(film_y, self.height).flat_map(move |py| {
+            columns.clone().filter_map(move |px| {
+                let weight = filter.weight(film_x - (px as f32 + 0.5), film_y - (py as f32 + 0.5));
+                (weight != 0.).then_some((py * width + px, weight))
+            })
+        })
+    }
+}
+
+// Adds tiles of samples to a film in tile order however they arrive, so the image doesn't depend
+// on which thread finished first. Only tiles that arrive ahead of an unfinished one are held
+// back, which keeps memory to a few tiles' samples rather than the whole frame's.
+pub(crate) struct OrderedSplats {
+    state: Mutex<OrderedState>,
+}
+
+struct OrderedState {
+    film: Film,
+    next: usize,
+    waiting: BTreeMap<usize, Vec<(f32, f32, Color)>>,
+}
+
+impl OrderedSplats {
+    pub(crate) fn new(film: Film) -> Self {
+        Self {
+            state: Mutex::new(OrderedState {
+                film,
+                next: 0,
+                waiting: BTreeMap::new(),
+            }),
+        }
+    }
+
+    // `samples` are (film x, film y, radiance) of tile number `index`.
+    pub(crate) fn add(&self, index: usize, samples: Vec<(f32, f32, Color)>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let OrderedState {
+            film,
+            next,
+            waiting,
+        } = &mut *state;
+        waiting.insert(index, samples);
+        while let Some(samples) = waiting.remove(next) {
+            for (film_x, film_y, color) in samples {
+                film.add_filtered_sample(film_x, film_y, color);
+            }
+            *next += 1;
+        }
+    }
+
+    pub(crate) fn into_film(self) -> Film {
+        self.state
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .film
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::film::{Film, OrderedSplats};
+    use crate::filter::Filter;
+    use crate::tuple::Color;
+    use itertools::Itertools;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn box_filter_splats_into_the_sample_pixel_only() {
+        let film = Film::new(4, 4, Filter::Box);
+        assert_eq!(film.footprint(1.0, 2.3).collect_vec(), vec![(9, 1.)]);
+    }
+
+    #[test]
+    pub fn wider_filters_splat_into_neighbours() {
+        let film = Film::new(4, 4, Filter::Tent);
+        assert_eq!(film.footprint(1.5, 1.5).collect_vec(), vec![(5, 1.)]);
+        assert_eq!(
+            film.footprint(1.25, 0.5).collect_vec(),
+            vec![(0, 0.25), (1, 0.75)]
+        );
+
+        let film = Film::new(4, 4, Filter::Mitchell);
+        assert!(film
+            .footprint(1.0, 0.5)
+            .collect_vec()
+            .iter()
+            .any(|&(_, w)| w < 0.));
+    }
+
+    #[test]
+    pub fn developed_pixels_are_the_average_of_their_samples() {
+        let mut film = Film::new(2, 1, Filter::Box);
+        film.add_sample(0, 0, Color::new(0.2, 0.4, 0.6));
+        film.add_sample(0, 0, Color::new(0.4, 0.6, 2.));
+        let canvas = film.develop();
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(0.3, 0.5, 1.3));
+        assert_eq!(canvas.pixel_at(1, 0).unwrap(), Color::black());
+        assert_eq!(film.sample_count(0, 0), 2);
+        assert_eq!(film.sample_count(1, 0), 0);
+    }
+
+    #[test]
+    pub fn variance_tracks_sample_spread() {
+        let mut film = Film::new(1, 1, Filter::Box);
+        film.add_sample(0, 0, Color::white());
+        assert_eq!(film.variance(0, 0), 0.);
+        film.add_sample(0, 0, Color::black());
+        film.add_sample(0, 0, Color::white());
+        film.add_sample(0, 0, Color::black());
+        assert!((film.variance(0, 0) - 1. / 3.).abs() < 1e-5);
+    }
+
+    #[test]
+    pub fn tiles_are_splatted_in_order_whatever_order_they_arrive_in() {
+        let tiles = (0..4)
+            .map(|i| {
+                vec![(
+                    1.5 + i as f32 * 0.3,
+                    1.25,
+                    Color::new(0.1 * i as f32, 0.7, 1.9),
+                )]
+            })
+            .collect_vec();
+        let in_order = OrderedSplats::new(Film::new(4, 4, Filter::Mitchell));
+        let shuffled = OrderedSplats::new(Film::new(4, 4, Filter::Mitchell));
+        for (i, samples) in tiles.iter().enumerate() {
+            in_order.add(i, samples.clone());
+        }
+        for i in [2, 0, 3, 1] {
+            shuffled.add(i, tiles[i].clone());
+        }
+        let bits = |film: Film| {
+            film.develop()
+                .iter_pixels()
+                .flat_map(|(_, _, c)| [c.r.to_bits(), c.g.to_bits(), c.b.to_bits()])
+                .collect_vec()
+        };
+        assert_eq!(bits(shuffled.into_film()), bits(in_order.into_film()));
+    }
+}
@@ -0,0 +1,89 @@
+// Pixel reconstruction filters. Offsets are measured in pixels from the pixel centre; every
+// filter is separable and zero outside its radius.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Filter {
+    #[default]
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+}
+
+const GAUSSIAN_ALPHA: f32 = 2.;
+const MITCHELL_B: f32 = 1. / 3.;
+const MITCHELL_C: f32 = 1. / 3.;
+
+impl Filter {
+    pub const fn radius(&self) -> f32 {
+        match self {
+            Self::Box => 0.5,
+            Self::Tent => 1.,
+            Self::Gaussian => 1.5,
+            Self::Mitchell => 2.,
+        }
+    }
+
+    pub fn weight(&self, dx: f32, dy: f32) -> f32 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, d: f32) -> f32 {
+        let radius = self.radius();
+        match self {
+            // Half-open so a sample on a pixel border belongs to exactly one pixel.
+            Self::Box => f32::from(u8::from((-radius..radius).contains(&d))),
+            Self::Tent => (radius - d.abs()).max(0.),
+            // Shifted down so the filter reaches zero at its radius instead of being cut off.
+            Self::Gaussian => {
+                let gaussian = |x: f32| (-GAUSSIAN_ALPHA * x * x).exp();
+                (gaussian(d) - gaussian(radius)).max(0.)
+            }
+            Self::Mitchell => mitchell(d.abs()),
+        }
+    }
+}
+
+fn mitchell(x: f32) -> f32 {
+    let (b, c) = (MITCHELL_B, MITCHELL_C);
+    let value = if x >= 2. {
+        0.
+    } else if x > 1. {
+        (-b - 6. * c) * x.powi(3)
+            + (6. * b + 30. * c) * x.powi(2)
+            + (-12. * b - 48. * c) * x
+            + (8. * b + 24. * c)
+    } else {
+        (12. - 9. * b - 6. * c) * x.powi(3) + (-18. + 12. * b + 6. * c) * x.powi(2) + (6. - 2. * b)
+    };
+    value / 6.
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filter::Filter;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(Filter::Box)]
+    #[test_case(Filter::Tent)]
+    #[test_case(Filter::Gaussian)]
+    #[test_case(Filter::Mitchell)]
+    pub fn filters_vanish_at_their_radius(filter: Filter) {
+        let r = filter.radius();
+        assert!(filter.weight(0., 0.) > 0.);
+        assert_eq!(filter.weight(r, 0.), 0.);
+        assert_eq!(filter.weight(0., -r - 0.1), 0.);
+    }
+
+    #[test]
+    pub fn box_filter_assigns_border_samples_once() {
+        assert_eq!(Filter::Box.weight(-0.5, 0.), 1.);
+        assert_eq!(Filter::Box.weight(0.5, 0.), 0.);
+    }
+
+    #[test]
+    pub fn mitchell_filter_has_negative_lobes() {
+        assert!((Filter::Mitchell.weight(0., 0.) - 0.79012).abs() < 1e-5);
+        assert!(Filter::Mitchell.weight(1.5, 0.) < 0.);
+    }
+}
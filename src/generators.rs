@@ -0,0 +1,234 @@
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::shape::{Cube, Shape, Sphere, Triangle};
+use crate::tuple::{Color, Point, Vector};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Settings for `scatter_spheres`. Heroes are the centre and radius of spheres the caller places
+// itself; scattered spheres keep clear of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scatter {
+    pub count: usize,
+    pub seed: u64,
+    // Must be positive, as for `Sphere::new`.
+    pub radius: f32,
+    // Spheres are placed within -extent..extent on x and z.
+    pub extent: f32,
+    pub heroes: Vec<(Point, f32)>,
+}
+
+// The layout of the final scene of "Ray Tracing in One Weekend".
+impl Default for Scatter {
+    fn default() -> Self {
+        Self {
+            count: 400,
+            seed: 0,
+            radius: 0.2,
+            extent: 11.,
+            heroes: vec![
+                (Point::new(0., 1., 0.), 1.),
+                (Point::new(-4., 1., 0.), 1.),
+                (Point::new(4., 1., 0.), 1.),
+            ],
+        }
+    }
+}
+
+// A Menger sponge filling the -1..1 cube. Every level splits each cube into 27 and keeps the 20
+// that aren't in the middle of a face or the centre, so `depth` n makes 20^n cubes.
+pub fn menger_sponge(depth: u32, material: impl Fn() -> Material) -> Vec<&'static dyn Shape> {
+    let mut cubes = vec![(Point::zero(), 1f32)];
+    for _ in 0..depth {
+        let mut next = Vec::with_capacity(cubes.len() * 20);
+        for (centre, half) in cubes {
+            let third = half / 3.;
+            for x in -1..=1 {
+                for y in -1..=1 {
+                    for z in -1..=1 {
+                        if [x, y, z].iter().filter(|&&o| o == 0).count() >= 2 {
+                            continue;
+                        }
+                        let offset = Vector::new(x as f32, y as f32, z as f32) * (2. * third);
+                        next.push((centre + offset, third));
+                    }
+                }
+            }
+        }
+        cubes = next;
+    }
+
+    cubes
+        .into_iter()
+        .map(|(centre, half)| {
+            let cube = Cube::default_with_material(material());
+            cube.set_transform(
+                Matrix4::identity()
+                    .scale(&Vector::new(half, half, half))
+                    .translate(&(centre - Point::zero())),
+            );
+            &*cube as &dyn Shape
+        })
+        .collect()
+}
+
+// A Sierpinski tetrahedron made of 4^depth solid tetrahedra, four triangles each, inscribed in
+// the unit sphere.
+pub fn sierpinski_tetrahedron(
+    depth: u32,
+    material: impl Fn() -> Material,
+) -> Vec<&'static dyn Shape> {
+    let corner = 1. / 3f32.sqrt();
+    let mut tetrahedra = vec![[
+        Point::new(corner, corner, corner),
+        Point::new(corner, -corner, -corner),
+        Point::new(-corner, corner, -corner),
+        Point::new(-corner, -corner, corner),
+    ]];
+    for _ in 0..depth {
+        tetrahedra = tetrahedra
+            .into_iter()
+            .flat_map(|vertices| {
+                let midpoint = |a: Point, b: Point| a + (b - a) * 0.5;
+                (0..4).map(move |keep| vertices.map(|v| midpoint(vertices[keep], v)))
+            })
+            .collect();
+    }
+
+    tetrahedra
+        .into_iter()
+        .flat_map(|[a, b, c, d]| [[a, b, c], [a, c, d], [a, d, b], [b, d, c]])
+        .map(|[p1, p2, p3]| Triangle::with_material(p1, p2, p3, material()) as &dyn Shape)
+        .collect()
+}
+
+// Small spheres resting on the y = 0 plane with randomly chosen diffuse, metal or glass
+// materials. Positions that would overlap a hero or an earlier sphere are skipped, so crowded
+// settings can return fewer than `count` spheres.
+pub fn scatter_spheres(scatter: &Scatter) -> Vec<&'static dyn Shape> {
+    let mut rng = StdRng::seed_from_u64(scatter.seed);
+    let radius = scatter.radius;
+    let mut placed: Vec<(Point, f32)> = vec![];
+    let mut spheres = vec![];
+
+    for _ in 0..scatter.count * 10 {
+        if spheres.len() == scatter.count {
+            break;
+        }
+        let centre = Point::new(
+            rng.gen_range(-scatter.extent..=scatter.extent),
+            radius,
+            rng.gen_range(-scatter.extent..=scatter.extent),
+        );
+        let clear = scatter
+            .heroes
+            .iter()
+            .chain(&placed)
+            .all(|(other, r)| (centre - *other).magnitude() > radius + r);
+        if !clear {
+            continue;
+        }
+        placed.push((centre, radius));
+
+        let material = match rng.gen_range(0.0..1.0) {
+            choice if choice < 0.8 => Material {
+                color: random_color(&mut rng).hadamard_product(&random_color(&mut rng)),
+                specular: 0.1,
+                ..Default::default()
+            },
+            choice if choice < 0.95 => Material {
+                color: random_color(&mut rng) * 0.5 + Color::new(0.5, 0.5, 0.5),
+                diffuse: 0.3,
+                reflective: rng.gen_range(0.6..=1.),
+                ..Default::default()
+            },
+            _ => Material {
+                color: Color::black(),
+                diffuse: 0.1,
+                ambient: 0.,
+                reflective: 0.9,
+                transparency: 1.,
+                refractive_index: 1.5,
+                ..Default::default()
+            },
+        };
+        let sphere = Sphere::new(centre, radius);
+        sphere.common.material = material;
+        spheres.push(&*sphere as &dyn Shape);
+    }
+    spheres
+}
+
+fn random_color(rng: &mut StdRng) -> Color {
+    Color::new(rng.gen(), rng.gen(), rng.gen())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::Bounds;
+    use crate::generators::{menger_sponge, scatter_spheres, sierpinski_tetrahedron, Scatter};
+    use crate::tuple::Point;
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    #[test]
+    pub fn menger_sponge_keeps_twenty_cubes_per_level() {
+        assert_eq!(menger_sponge(0, Default::default).len(), 1);
+        let sponge = menger_sponge(2, Default::default);
+        assert_eq!(sponge.len(), 400);
+
+        let bounds = sponge
+            .iter()
+            .fold(Bounds::empty(), |b, s| b.merge(&s.get_bounds()));
+        assert_eq!(bounds, Bounds::new(Point::new(-1., -1., -1.), Point::one()));
+        // Nothing is left at the centre.
+        assert!(sponge
+            .iter()
+            .all(|s| !s.get_bounds().contains_point(&Point::zero())));
+    }
+
+    #[test]
+    pub fn sierpinski_tetrahedron_has_four_faces_per_tetrahedron() {
+        assert_eq!(sierpinski_tetrahedron(0, Default::default).len(), 4);
+        assert_eq!(sierpinski_tetrahedron(3, Default::default).len(), 256);
+    }
+
+    #[test]
+    pub fn scattered_spheres_avoid_heroes_and_each_other() {
+        let scatter = Scatter {
+            count: 50,
+            extent: 4.,
+            ..Default::default()
+        };
+        let spheres = scatter_spheres(&scatter);
+        assert_eq!(spheres.len(), 50);
+        let centres: Vec<Point> = spheres
+            .iter()
+            .map(|s| *s.get_transform() * Point::zero())
+            .collect();
+        for (i, a) in centres.iter().enumerate() {
+            assert!((a.y - 0.2).abs() < 1e-5);
+            for (hero, r) in &scatter.heroes {
+                assert!((*a - *hero).magnitude() > r + 0.2);
+            }
+            for b in &centres[i + 1..] {
+                assert!((*a - *b).magnitude() > 0.4);
+            }
+        }
+    }
+
+    #[test]
+    pub fn scattering_is_reproducible_from_the_seed() {
+        let centres = |seed| {
+            scatter_spheres(&Scatter {
+                count: 5,
+                seed,
+                ..Default::default()
+            })
+            .iter()
+            .map(|s| *s.get_transform() * Point::zero())
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(centres(3), centres(3));
+        assert_ne!(centres(3), centres(4));
+    }
+}
@@ -0,0 +1,41 @@
+pub mod accel;
+pub mod arena;
+pub mod bezier;
+pub mod bounds;
+pub mod camera;
+pub mod canvas;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod config;
+pub mod debug_scene;
+pub mod error;
+pub mod exposure;
+pub mod film;
+pub mod filter;
+pub mod generators;
+pub mod light;
+pub mod material;
+pub mod matrix;
+pub mod obj;
+pub mod pattern;
+pub mod ply;
+pub mod point_cloud;
+pub mod postprocess;
+pub mod probes;
+pub mod ray;
+pub mod sampler;
+pub mod sampling;
+pub mod scene_graph;
+pub mod scene_stats;
+pub mod scenes;
+pub mod settings;
+mod shadow_cache;
+pub mod shape;
+pub mod sky;
+pub mod stats;
+mod term_preview;
+pub mod text;
+pub mod tile;
+pub mod tuple;
+pub mod validate;
+pub mod world;
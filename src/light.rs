@@ -1,15 +1,107 @@
+use crate::exposure::candela_from_lumens;
 use crate::material::Material;
 use crate::shape::Shape;
-use crate::tuple::{Color, Point, Vector};
-use derive_more::Constructor;
+use crate::tuple::{Color, Point, Vector, EPSILON};
+use std::f32::consts::PI;
+use uuid::Uuid;
 
-#[derive(Constructor, Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Clone, Eq, PartialEq)]
 pub struct PointLight {
     pub position: Point,
     pub intensity: Color,
+    pub links: LightLinks,
+    pub falloff: Falloff,
+}
+
+// How a light's intensity changes with distance. The book's lights don't fall off at all;
+// photometric lights follow the inverse-square law, so their rating is what they deliver at one
+// metre.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Falloff {
+    #[default]
+    None,
+    InverseSquare,
+}
+
+// Which objects a light illuminates and can be blocked by. Objects a light isn't linked to get
+// neither its light nor cast its shadows, so a rim light can pick out one object without
+// washing out, or being shadowed by, the rest of the scene.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub enum LightLinks {
+    #[default]
+    All,
+    Only(Vec<Uuid>),
+    Except(Vec<Uuid>),
+}
+
+impl LightLinks {
+    pub fn affects(&self, object: &Uuid) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(objects) => objects.contains(object),
+            Self::Except(objects) => !objects.contains(object),
+        }
+    }
+}
+
+pub const DEFAULT_LIGHT_GROUP: &str = "default";
+
+#[derive(Clone, PartialEq)]
+pub struct LightGroup {
+    pub name: String,
+    pub lights: Vec<PointLight>,
+    pub enabled: bool,
+}
+
+impl LightGroup {
+    pub fn new(name: impl Into<String>, lights: Vec<PointLight>) -> Self {
+        Self {
+            name: name.into(),
+            lights,
+            enabled: true,
+        }
+    }
 }
 
 impl PointLight {
+    pub fn new(position: Point, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            links: LightLinks::All,
+            falloff: Falloff::None,
+        }
+    }
+
+    // A light of `candela` luminous intensity, for use with a camera `Exposure`. Its light falls
+    // off with the square of the distance.
+    pub fn with_candela(position: Point, color: Color, candela: f32) -> Self {
+        Self {
+            falloff: Falloff::InverseSquare,
+            ..Self::new(position, color * candela)
+        }
+    }
+
+    // A light emitting `lumens` evenly in all directions, like a bare bulb's rating.
+    pub fn with_lumens(position: Point, color: Color, lumens: f32) -> Self {
+        Self::with_candela(position, color, candela_from_lumens(lumens))
+    }
+
+    pub fn with_links(self, links: LightLinks) -> Self {
+        Self { links, ..self }
+    }
+
+    // The intensity arriving at `point`.
+    pub fn intensity_at(&self, point: &Point) -> Color {
+        match self.falloff {
+            Falloff::None => self.intensity,
+            Falloff::InverseSquare => {
+                let distance_squared = (self.position - point).magnitude().powi(2);
+                self.intensity * (1. / distance_squared.max(EPSILON))
+            }
+        }
+    }
+
     pub fn calculate_lighting(
         &self,
         material: &Material,
@@ -19,19 +111,65 @@ impl PointLight {
         normal_vector: &Vector,
         in_shadow: bool,
     ) -> Color {
-        let diffuse;
-        let specular;
-
-        let effective_color = if let Some(p) = &material.pattern {
-            p.color_object(object, pos)
+        let light_filter = if in_shadow {
+            Color::black()
         } else {
-            material.color
+            Color::white()
         };
-        let effective_color = effective_color * self.intensity;
-        let ambient = effective_color * material.ambient;
-        if in_shadow {
+        self.calculate_filtered_lighting(
+            material,
+            object,
+            pos,
+            eye_vector,
+            normal_vector,
+            light_filter,
+        )
+    }
+
+    pub fn calculate_filtered_lighting(
+        &self,
+        material: &Material,
+        object: &dyn Shape,
+        pos: &Point,
+        eye_vector: &Vector,
+        normal_vector: &Vector,
+        light_filter: Color,
+    ) -> Color {
+        let (ambient, direct) = self.lighting_components(
+            material,
+            material.surface_color(object, pos, None),
+            object,
+            pos,
+            eye_vector,
+            normal_vector,
+        );
+        if light_filter == Color::black() {
             return ambient;
         }
+        ambient + direct * light_filter
+    }
+
+    // Splits the Phong result into the ambient term and the diffuse + specular term that
+    // shadows attenuate, for a surface colour the caller has already looked up.
+    pub fn lighting_components(
+        &self,
+        material: &Material,
+        surface_color: Color,
+        object: &dyn Shape,
+        pos: &Point,
+        eye_vector: &Vector,
+        normal_vector: &Vector,
+    ) -> (Color, Color) {
+        if !self.links.affects(object.get_id()) {
+            return (Color::black(), Color::black());
+        }
+
+        let diffuse;
+        let specular;
+
+        let intensity = self.intensity_at(pos);
+        let effective_color = surface_color * intensity;
+        let ambient = effective_color * material.ambient;
 
         let light_vector = (self.position - pos).normalize();
         let light_dot_normal = light_vector.dot(normal_vector);
@@ -40,29 +178,87 @@ impl PointLight {
             specular = Color::new(0., 0., 0.);
         } else {
             diffuse = effective_color * material.diffuse * light_dot_normal;
-            let reflect_vector = -light_vector.reflect(normal_vector);
-            let reflect_dot_eye = reflect_vector.dot(eye_vector);
 
-            if reflect_dot_eye < 0.0 {
-                specular = Color::new(0., 0., 0.);
+            if material.anisotropy == 0.0 {
+                let reflect_vector = -light_vector.reflect(normal_vector);
+                let reflect_dot_eye = reflect_vector.dot(eye_vector);
+
+                if reflect_dot_eye < 0.0 {
+                    specular = Color::new(0., 0., 0.);
+                } else {
+                    let factor = reflect_dot_eye.powf(material.shininess);
+                    specular = intensity * material.specular * factor;
+                }
             } else {
-                let factor = reflect_dot_eye.powf(material.shininess);
-                specular = self.intensity * material.specular * factor;
+                let tangent = object.get_tangent(pos);
+                let factor = anisotropic_specular_factor(
+                    material,
+                    &tangent,
+                    &light_vector,
+                    eye_vector,
+                    normal_vector,
+                );
+                specular = intensity * material.specular * factor;
             }
         }
 
-        ambient + diffuse + specular
+        // A photometric light's intensity is illuminance once it reaches the surface, and a
+        // Lambertian surface of albedo ρ under E lux has a luminance of ρE/π. The book's lights
+        // are unitless and keep its unscaled terms.
+        let direct = match self.falloff {
+            Falloff::None => diffuse + specular,
+            Falloff::InverseSquare => (diffuse + specular) * (1. / PI),
+        };
+        (ambient, direct)
+    }
+}
+
+// Ashikhmin-Shirley style lobe: the exponent is lowered along the (rotated) tangent, which
+// stretches the highlight in that direction.
+fn anisotropic_specular_factor(
+    material: &Material,
+    tangent: &Vector,
+    light_vector: &Vector,
+    eye_vector: &Vector,
+    normal_vector: &Vector,
+) -> f32 {
+    let half_vector = (*light_vector + *eye_vector).normalize();
+    let normal_dot_half = half_vector.dot(normal_vector);
+    if normal_dot_half <= 0.0 {
+        return 0.0;
     }
+
+    let tangent = (*tangent - *normal_vector * tangent.dot(normal_vector)).normalize();
+    let bitangent = normal_vector.cross(&tangent);
+    let (sin, cos) = material.anisotropy_rotation.sin_cos();
+    let rotated_tangent = tangent * cos + bitangent * sin;
+    let rotated_bitangent = bitangent * cos - tangent * sin;
+
+    let anisotropy = material.anisotropy.clamp(0.0, 1.0);
+    let tangent_exponent = (material.shininess * (1.0 - anisotropy)).max(1.0);
+    let bitangent_exponent = material.shininess;
+
+    let sin2 = normal_dot_half.mul_add(-normal_dot_half, 1.0);
+    let exponent = if sin2 < EPSILON {
+        bitangent_exponent
+    } else {
+        let h_dot_t = half_vector.dot(&rotated_tangent);
+        let h_dot_b = half_vector.dot(&rotated_bitangent);
+        tangent_exponent.mul_add(h_dot_t * h_dot_t, bitangent_exponent * h_dot_b * h_dot_b) / sin2
+    };
+
+    normal_dot_half.powf(exponent)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::light::PointLight;
+    use crate::light::{Falloff, PointLight};
     use crate::material::Material;
     use crate::pattern::Stripe;
     use crate::shape::Sphere;
     use crate::tuple::{Color, Point, Vector};
     use pretty_assertions::assert_eq;
+    use std::f32::consts::PI;
     use test_case::test_case;
 
     #[test_case(
@@ -138,4 +334,61 @@ mod tests {
         let c = light.calculate_lighting(&material, &obj, &p, &eyev, &normalv, false);
         assert_eq!(c, expected);
     }
+
+    #[test]
+    pub fn anisotropic_highlight_is_stretched_along_tangent() {
+        let material = Material {
+            anisotropy: 0.9,
+            ..Default::default()
+        };
+        let position = Point::new(0., 0., -1.);
+        let eyev = Vector::new(0., 0., -1.);
+        let normalv = Vector::new(0., 0., -1.);
+        let obj = Sphere::default();
+        let along_tangent = PointLight::new(Point::new(1., 0., -10.), Color::white());
+        let along_bitangent = PointLight::new(Point::new(0., 1., -10.), Color::white());
+
+        let c1 =
+            along_tangent.calculate_lighting(&material, &obj, &position, &eyev, &normalv, false);
+        let c2 =
+            along_bitangent.calculate_lighting(&material, &obj, &position, &eyev, &normalv, false);
+        assert!(c1.r > c2.r);
+    }
+
+    #[test]
+    pub fn rotating_anisotropy_swaps_highlight_direction() {
+        let material = Material {
+            anisotropy: 0.9,
+            anisotropy_rotation: PI / 2.,
+            ..Default::default()
+        };
+        let position = Point::new(0., 0., -1.);
+        let eyev = Vector::new(0., 0., -1.);
+        let normalv = Vector::new(0., 0., -1.);
+        let obj = Sphere::default();
+        let along_tangent = PointLight::new(Point::new(1., 0., -10.), Color::white());
+        let along_bitangent = PointLight::new(Point::new(0., 1., -10.), Color::white());
+
+        let c1 =
+            along_tangent.calculate_lighting(&material, &obj, &position, &eyev, &normalv, false);
+        let c2 =
+            along_bitangent.calculate_lighting(&material, &obj, &position, &eyev, &normalv, false);
+        assert!(c1.r < c2.r);
+    }
+
+    #[test]
+    pub fn photometric_lights_fall_off_with_distance() {
+        let light = PointLight::with_candela(Point::zero(), Color::white(), 8.);
+        assert_eq!(light.falloff, Falloff::InverseSquare);
+        assert_eq!(
+            light.intensity_at(&Point::new(0., 1., 0.)),
+            Color::new(8., 8., 8.)
+        );
+        assert_eq!(
+            light.intensity_at(&Point::new(0., 0., -2.)),
+            Color::new(2., 2., 2.)
+        );
+        let book = PointLight::new(Point::zero(), Color::white());
+        assert_eq!(book.intensity_at(&Point::new(0., 0., -2.)), Color::white());
+    }
 }
@@ -1,30 +1,180 @@
 use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use crate::matrix::Matrix4;
-use crate::shape::{Cube, Plane, Shape};
+use clap::{Parser, Subcommand, ValueEnum};
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
 
-use crate::tuple::{Color, Point, Vector};
-
-use crate::camera::Camera;
-use crate::light::PointLight;
-use crate::material::Material;
-use crate::pattern::Pattern;
+use ray_tracer_challange::accel::Accel;
+use ray_tracer_challange::camera::{Camera, MAX_REFLECTION_RECURSION_DEPTH};
+use ray_tracer_challange::canvas::color_space::ColorSpace;
+use ray_tracer_challange::canvas::dither::Dither;
+use ray_tracer_challange::canvas::metadata::RenderMetadata;
+use ray_tracer_challange::canvas::Canvas;
+use ray_tracer_challange::config::Config;
+use ray_tracer_challange::light::PointLight;
+use ray_tracer_challange::material::Material;
+use ray_tracer_challange::matrix::Matrix4;
+use ray_tracer_challange::pattern::{self, Pattern};
+use ray_tracer_challange::sampler::FrameNoise;
+use ray_tracer_challange::settings::OutputSettings;
+use ray_tracer_challange::shape::{Cube, Plane, Shape};
+use ray_tracer_challange::stats::HeatmapMetric;
+use ray_tracer_challange::tuple::{Color, Point, Vector};
+use ray_tracer_challange::world;
 use std::io;
 use std::io::{BufWriter, Write};
 
-mod camera;
-mod canvas;
-mod light;
-mod material;
-mod matrix;
-mod pattern;
-mod ray;
-mod shape;
-mod tuple;
-mod world;
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Output image; the format follows the extension (ppm, png, tif, jpg). PPM goes to stdout
+    /// when omitted.
+    output: Option<PathBuf>,
+    /// Image width; defaults come from `raytracer.toml` and `RAYTRACER_*` variables
+    #[arg(long)]
+    width: Option<usize>,
+    #[arg(long)]
+    height: Option<usize>,
+    /// Antialiasing samples per pixel
+    #[arg(long)]
+    samples: Option<usize>,
+    /// Render threads, 0 for one per core
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Directory that relative output paths are written into
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+    #[arg(long, default_value_t = 90)]
+    jpeg_quality: u8,
+    /// Write linear values instead of encoding the output as sRGB
+    #[arg(long)]
+    linear: bool,
+    /// Dither used when quantizing the output to 8 or 16 bits
+    #[arg(long, value_enum, default_value_t = DitherArg::None)]
+    dither: DitherArg,
+    /// Stamp the render parameters onto a strip below the image
+    #[arg(long)]
+    caption: bool,
+    /// Render a cost heatmap instead of the scene
+    #[arg(long, value_enum)]
+    heatmap: Option<Heatmap>,
+    /// Show a live colour preview of the render in the terminal, this many columns wide
+    #[arg(long, num_args = 0..=1, default_missing_value = "80")]
+    term_preview: Option<usize>,
+    /// Index of the frame being rendered when rendering an animation one frame at a time
+    #[arg(long, default_value_t = 0)]
+    frame: u64,
+    /// Whether sampling noise stays the same on every frame or changes with it
+    #[arg(long, value_enum, default_value_t = FrameNoiseArg::Static)]
+    frame_noise: FrameNoiseArg,
+    /// Print what the scene contains and exit without rendering
+    #[arg(long)]
+    stats: bool,
+    /// Most verbose log level written to stderr; `debug` reports every finished tile
+    #[arg(long, default_value_t = Level::INFO)]
+    log: Level,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two rendered images and report how far apart they are
+    Diff {
+        reference: PathBuf,
+        candidate: PathBuf,
+        /// Largest per-channel difference, from 0 to 1, for a pixel to count as matching
+        #[arg(long, default_value_t = 1. / 255.)]
+        tolerance: f32,
+        /// Write a heatmap of the per-pixel differences here
+        #[arg(long)]
+        heatmap: Option<PathBuf>,
+    },
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Heatmap {
+    /// Object intersection tests per sample
+    Tests,
+    /// Rays traced per sample
+    Rays,
+    /// Total internal reflections and near-total reflections off glass per sample
+    Tir,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum FrameNoiseArg {
+    /// The same noise on every frame
+    Static,
+    /// Noise that changes from frame to frame
+    Animated,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum DitherArg {
+    /// No dithering
+    None,
+    /// An 8x8 Bayer matrix
+    Ordered,
+    /// Interleaved gradient noise, which hides banding without a visible pattern
+    BlueNoise,
+}
+
+impl From<DitherArg> for Dither {
+    fn from(dither: DitherArg) -> Self {
+        match dither {
+            DitherArg::None => Self::None,
+            DitherArg::Ordered => Self::Ordered,
+            DitherArg::BlueNoise => Self::BlueNoise,
+        }
+    }
+}
+
+impl From<FrameNoiseArg> for FrameNoise {
+    fn from(noise: FrameNoiseArg) -> Self {
+        match noise {
+            FrameNoiseArg::Static => Self::Static,
+            FrameNoiseArg::Animated => Self::Animated,
+        }
+    }
+}
+
+impl From<Heatmap> for HeatmapMetric {
+    fn from(heatmap: Heatmap) -> Self {
+        match heatmap {
+            Heatmap::Tests => Self::IntersectionTests,
+            Heatmap::Rays => Self::Rays,
+            Heatmap::Tir => Self::InternalReflections,
+        }
+    }
+}
 
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
+    let args = Args::parse();
+    // Closing spans report how long scene loading, acceleration, rendering and export took.
+    tracing_subscriber::fmt()
+        .with_max_level(args.log)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .init();
+    if let Some(Command::Diff {
+        reference,
+        candidate,
+        tolerance,
+        heatmap,
+    }) = args.command
+    {
+        return diff(&reference, &candidate, tolerance, heatmap);
+    }
+    let config = Config::discover()?.merge(Config {
+        width: args.width,
+        height: args.height,
+        samples: args.samples,
+        threads: args.threads,
+        output_dir: args.output_dir.clone(),
+    });
 
     let _material = Material {
         color: Color::new(1., 0.9, 0.9),
@@ -40,13 +190,13 @@ fn main() -> color_eyre::Result<()> {
         transparency: 0.4,
         ..Default::default()
     });
-    let mut backdrop_pattern =
-        pattern::Checkers::new(Color::new(0.5, 0.5, 0.5), Color::new(0.75, 0.75, 0.75));
-    backdrop_pattern.set_transform(
-        &Matrix4::identity()
-            .rotate_x(PI / 2.)
-            .scale(&Vector::new(3., 3., 3.)),
-    );
+    let backdrop_pattern =
+        pattern::Checkers::new(Color::new(0.5, 0.5, 0.5), Color::new(0.75, 0.75, 0.75))
+            .transformed(
+                Matrix4::identity()
+                    .rotate_x(PI / 2.)
+                    .scale(&Vector::new(3., 3., 3.)),
+            );
     let backdrop: &'static dyn Shape = Plane::default_with_material(Material {
         pattern: Some(backdrop_pattern),
         ..Default::default()
@@ -79,20 +229,92 @@ fn main() -> color_eyre::Result<()> {
     c3.set_transform(Matrix4::identity().translate(&Vector::new(0.0, 3.5, 0.)));
 
     let light_source = PointLight::new(Point::new(-10., 1000., -1000.), Color::new(1., 1., 1.));
-    let world = world::World::new(light_source, vec![floor, backdrop, c1, c2, c3]);
+    let mut world = world::World::new(light_source, vec![floor, backdrop, c1, c2, c3]);
+    world.settings.accel = Accel::KdTree;
+    world.settings.term_preview = args.term_preview;
+    world.settings.threads = config.threads.unwrap_or_default();
+    world.settings.frame = args.frame;
+    world.settings.frame_noise = args.frame_noise.into();
+    world.build_acceleration();
+    if args.stats {
+        println!("{}", world.scene_stats());
+        return Ok(());
+    }
 
-    let mut camera = Camera::new(1000, 1000, PI / 3.);
+    let mut camera = Camera::new(
+        config.width.unwrap_or(1000),
+        config.height.unwrap_or(1000),
+        PI / 3.,
+    );
+    if let Some(samples) = config.samples {
+        camera.samples_pre_pixel = samples.max(1);
+    }
     camera.set_transform(
         Point::new(0., 1.5, -10.),
         Point::new(0., 1., 0.),
         Vector::new(0., 1., 0.),
     );
 
-    let canvas = camera.render(&world);
+    let start = Instant::now();
+    let mut canvas = match args.heatmap {
+        Some(metric) => camera.render_heatmap(&world, metric.into()),
+        None => camera.render(&world),
+    }?;
+    let metadata = RenderMetadata {
+        samples_per_pixel: camera.samples_pre_pixel,
+        max_depth: world
+            .settings
+            .max_depth
+            .unwrap_or(MAX_REFLECTION_RECURSION_DEPTH),
+        seed: Some(world.settings.seed),
+        render_time: start.elapsed(),
+        ..RenderMetadata::new("cubes")
+    };
+    if args.caption {
+        canvas = canvas.with_caption(&metadata.caption());
+    }
 
-    let ppm = canvas.convert_to_ppm();
-    dump_to_stdout(ppm.as_bytes())?;
+    let output = OutputSettings {
+        jpeg_quality: args.jpeg_quality,
+        color_space: if args.linear {
+            ColorSpace::Linear
+        } else {
+            ColorSpace::Srgb
+        },
+        dither: args.dither.into(),
+    };
+    let path = match (args.output, config.output_dir) {
+        (Some(path), Some(dir)) if path.is_relative() => {
+            std::fs::create_dir_all(&dir)?;
+            Some(dir.join(path))
+        }
+        (path, _) => path,
+    };
+    match path {
+        Some(path) => canvas.save_with_metadata(path, &output, Some(&metadata))?,
+        None => dump_to_stdout(canvas.convert_to_ppm_with(&output).as_bytes())?,
+    }
+
+    Ok(())
+}
 
+fn diff(
+    reference: &Path,
+    candidate: &Path,
+    tolerance: f32,
+    heatmap: Option<PathBuf>,
+) -> color_eyre::Result<()> {
+    let diff = Canvas::load(reference)?.diff(&Canvas::load(candidate)?, tolerance)?;
+    println!("rmse: {:.6}", diff.rmse);
+    println!("psnr: {:.2} dB", diff.psnr);
+    println!(
+        "differing pixels: {} of {}",
+        diff.differing,
+        diff.heatmap.pixels.len()
+    );
+    if let Some(path) = heatmap {
+        diff.heatmap.save(path, &OutputSettings::default())?;
+    }
     Ok(())
 }
 
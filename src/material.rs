@@ -1,19 +1,36 @@
 use crate::pattern::Pattern;
-use crate::tuple::Color;
-use derive_more::Constructor;
+use crate::ray::Footprint;
+use crate::shape::Shape;
+use crate::tuple::{Color, Point};
 use std::fmt::Debug;
 
-#[derive(Debug, Constructor)]
+#[derive(Debug)]
 pub struct Material {
     pub color: Color,
     pub ambient: f32,
     pub diffuse: f32,
     pub specular: f32,
     pub shininess: f32,
+    pub anisotropy: f32,
+    pub anisotropy_rotation: f32,
     pub reflective: f32,
     pub refractive_index: f32,
     pub transparency: f32,
     pub pattern: Option<Box<dyn Pattern>>,
+    // Patterns that vary other properties over the surface. Each scales its property by the
+    // luminance of the pattern's colour, so a white and black checker alternates between the
+    // property's value and zero.
+    pub channels: Vec<(MaterialChannel, Box<dyn Pattern>)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MaterialChannel {
+    Ambient,
+    Diffuse,
+    Specular,
+    Shininess,
+    Reflective,
+    Transparency,
 }
 
 impl Default for Material {
@@ -23,11 +40,102 @@ impl Default for Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            anisotropy: 0.0,
+            anisotropy_rotation: 0.0,
             reflective: 0.0,
             refractive_index: 1.0,
             transparency: 0.0,
             color: Color::new(1., 1., 1.),
             pattern: None,
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl Material {
+    pub fn surface_color(
+        &self,
+        object: &dyn Shape,
+        point: &Point,
+        footprint: Option<&Footprint>,
+    ) -> Color {
+        match (&self.pattern, footprint) {
+            (Some(p), Some(footprint)) => p.color_object_filtered(object, point, footprint),
+            (Some(p), None) => p.color_object(object, point),
+            (None, _) => self.color,
         }
     }
+
+    pub const fn base_value(&self, channel: MaterialChannel) -> f32 {
+        match channel {
+            MaterialChannel::Ambient => self.ambient,
+            MaterialChannel::Diffuse => self.diffuse,
+            MaterialChannel::Specular => self.specular,
+            MaterialChannel::Shininess => self.shininess,
+            MaterialChannel::Reflective => self.reflective,
+            MaterialChannel::Transparency => self.transparency,
+        }
+    }
+
+    pub fn value_at(&self, channel: MaterialChannel, object: &dyn Shape, point: &Point) -> f32 {
+        self.channels
+            .iter()
+            .filter(|(c, _)| *c == channel)
+            .fold(self.base_value(channel), |value, (_, pattern)| {
+                value * pattern.color_object(object, point).luminance()
+            })
+    }
+
+    // The properties at a point with every channel pattern applied, or `None` when the material
+    // has no channel patterns and can be used as it is. The copy carries no patterns; shading
+    // looks the surface colour up separately.
+    pub fn sampled_at(&self, object: &dyn Shape, point: &Point) -> Option<Self> {
+        if self.channels.is_empty() {
+            return None;
+        }
+        let value = |channel| self.value_at(channel, object, point);
+        Some(Self {
+            color: self.color,
+            ambient: value(MaterialChannel::Ambient),
+            diffuse: value(MaterialChannel::Diffuse),
+            specular: value(MaterialChannel::Specular),
+            shininess: value(MaterialChannel::Shininess),
+            anisotropy: self.anisotropy,
+            anisotropy_rotation: self.anisotropy_rotation,
+            reflective: value(MaterialChannel::Reflective),
+            refractive_index: self.refractive_index,
+            transparency: value(MaterialChannel::Transparency),
+            pattern: None,
+            channels: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::{Material, MaterialChannel};
+    use crate::pattern::Checkers;
+    use crate::shape::Sphere;
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn channel_pattern_scales_its_property() {
+        let m = Material {
+            reflective: 0.8,
+            channels: vec![(
+                MaterialChannel::Reflective,
+                Checkers::new(Color::white(), Color::black()),
+            )],
+            ..Default::default()
+        };
+        let s = Sphere::static_default();
+        let glossy = Point::new(0.5, 0.5, 0.5);
+        let matte = Point::new(1.5, 0.5, 0.5);
+        assert_eq!(m.value_at(MaterialChannel::Reflective, s, &glossy), 0.8);
+        assert_eq!(m.value_at(MaterialChannel::Reflective, s, &matte), 0.);
+        assert_eq!(m.value_at(MaterialChannel::Diffuse, s, &matte), 0.9);
+        assert_eq!(m.sampled_at(s, &matte).unwrap().reflective, 0.);
+        assert!(Material::default().sampled_at(s, &matte).is_none());
+    }
 }
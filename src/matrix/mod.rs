@@ -1,3 +1,4 @@
+use crate::error::{Error, Result};
 use crate::tuple::{approx_eq, Point, Vector};
 use std::ops::Mul;
 
@@ -164,8 +165,16 @@ impl Matrix4 {
         Self(self.0.transpose())
     }
 
+    pub fn determinant(&self) -> f32 {
+        self.0.determinant()
+    }
+
     pub fn inverse(self) -> Self {
-        Self(self.0.try_inverse().unwrap())
+        self.try_inverse().unwrap()
+    }
+
+    pub fn try_inverse(self) -> Result<Self> {
+        self.0.try_inverse().map(Self).ok_or(Error::SingularMatrix)
     }
 
     pub fn shear(self, xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Self {
@@ -218,6 +227,7 @@ impl Point {
 
 #[cfg(test)]
 mod tests {
+    use crate::error::Error;
     use crate::matrix::Matrix4;
     use crate::tuple::{Point, Vector};
     use nalgebra::matrix;
@@ -295,7 +305,7 @@ mod tests {
 
     #[test]
     pub fn multiply_by_point() {
-        let A: Matrix4 = matrix![
+        let a: Matrix4 = matrix![
             1., 2., 3., 4.;
             2., 4., 4., 2.;
             8., 6., 4., 1.;
@@ -303,7 +313,7 @@ mod tests {
         ]
         .into();
         let b = Point::new(1., 2., 3.);
-        let res: Point = A * b;
+        let res: Point = a * b;
 
         assert_eq!(res, Point::new(18., 24., 33.));
     }
@@ -375,6 +385,12 @@ mod tests {
         assert_eq!(res, a);
     }
 
+    #[test]
+    pub fn inverting_singular_matrix_is_an_error() {
+        let a = Matrix4::identity().scale(&Vector::new(1., 0., 1.));
+        assert!(matches!(a.try_inverse(), Err(Error::SingularMatrix)));
+    }
+
     #[test]
     pub fn translate_point() {
         let p = Point::new(5., -3., 2.).translate(&Vector::new(-3., 4., 5.));
@@ -463,12 +479,12 @@ mod tests {
     #[test]
     pub fn composing_transforms() {
         let p = Point::new(1., 0., 1.);
-        let A = p.rotate_x(PI / 2.);
-        assert_eq!(A, Point::new(1., -1., 0.));
-        let B = A.scale(&Vector::new(5., 5., 5.));
-        assert_eq!(B, Point::new(5., -5., 0.));
-        let C = B.translate(&Vector::new(10., 5., 7.));
-        assert_eq!(C, Point::new(15., 0., 7.));
+        let a = p.rotate_x(PI / 2.);
+        assert_eq!(a, Point::new(1., -1., 0.));
+        let b = a.scale(&Vector::new(5., 5., 5.));
+        assert_eq!(b, Point::new(5., -5., 0.));
+        let c = b.translate(&Vector::new(10., 5., 7.));
+        assert_eq!(c, Point::new(15., 0., 7.));
     }
 
     #[test]
@@ -0,0 +1,233 @@
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::shape::{Shape, Triangle};
+use crate::tuple::Point;
+use rayon::prelude::*;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Lines handed to each parsing task. Large enough that the per-task bookkeeping disappears next to
+// the parsing itself.
+const CHUNK_LINES: usize = 16 * 1024;
+
+// The geometry of a Wavefront OBJ file: vertex positions and polygonal faces. Texture coordinates,
+// normals, groups and materials are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Obj {
+    pub vertices: Vec<Point>,
+    pub faces: Vec<Vec<usize>>,
+    pub stats: ParseStats,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ParseStats {
+    pub lines: usize,
+    pub elapsed: Duration,
+}
+
+impl ParseStats {
+    pub fn lines_per_second(&self) -> f64 {
+        self.lines as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for ParseStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "parsed {} lines in {:.1?} ({:.0} lines/s)",
+            self.lines,
+            self.elapsed,
+            self.lines_per_second()
+        )
+    }
+}
+
+// A face corner before the chunks are stitched together. Negative OBJ indices count back from the
+// last vertex read, so they can only be resolved once the number of vertices in earlier chunks is
+// known.
+#[derive(Debug, Copy, Clone)]
+enum Index {
+    Absolute(usize),
+    // Position among the vertices of the chunk the face is in.
+    Relative(i64),
+}
+
+#[derive(Debug, Default)]
+struct Chunk {
+    vertices: Vec<Point>,
+    faces: Vec<(usize, Vec<Index>)>,
+}
+
+impl Obj {
+    pub fn load(path: &Path) -> Result<Self> {
+        let _span = tracing::info_span!("load_obj", path = %path.display()).entered();
+        let obj = Self::parse(&std::fs::read_to_string(path)?)?;
+        tracing::info!(
+            vertices = obj.vertices.len(),
+            faces = obj.faces.len(),
+            "{}",
+            obj.stats
+        );
+        Ok(obj)
+    }
+
+    // Chunks of lines are parsed in parallel and then joined in order, so the result is identical
+    // to reading the file front to back.
+    pub fn parse(source: &str) -> Result<Self> {
+        let start = Instant::now();
+        let lines: Vec<&str> = source.lines().collect();
+        let chunks = lines
+            .par_chunks(CHUNK_LINES)
+            .enumerate()
+            .map(|(n, chunk)| parse_chunk(chunk, n * CHUNK_LINES))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut vertices = Vec::with_capacity(chunks.iter().map(|c| c.vertices.len()).sum());
+        let mut faces = Vec::with_capacity(chunks.iter().map(|c| c.faces.len()).sum());
+        for chunk in chunks {
+            let base = vertices.len() as i64;
+            vertices.extend(chunk.vertices);
+            for (line, corners) in chunk.faces {
+                let face = corners
+                    .into_iter()
+                    .map(|index| match index {
+                        Index::Absolute(i) => Some(i),
+                        Index::Relative(i) => usize::try_from(base + i).ok(),
+                    })
+                    .map(|i| i.filter(|&i| i < vertices.len()))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| import_error(line, "face refers to a missing vertex"))?;
+                faces.push(face);
+            }
+        }
+
+        Ok(Self {
+            vertices,
+            faces,
+            stats: ParseStats {
+                lines: lines.len(),
+                elapsed: start.elapsed(),
+            },
+        })
+    }
+
+    // Fans every face into triangles, in parallel. The triangles are open surfaces, so a closed
+    // transparent mesh refracts like a hollow shell.
+    pub fn to_triangles(&self, material: impl Fn() -> Material + Sync) -> Vec<&'static dyn Shape> {
+        self.faces
+            .par_iter()
+            .flat_map_iter(|face| {
+                let material = &material;
+                (1..face.len().saturating_sub(1)).map(move |i| {
+                    let [p1, p2, p3] = [face[0], face[i], face[i + 1]].map(|v| self.vertices[v]);
+                    Triangle::with_material(p1, p2, p3, material()) as &'static dyn Shape
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_chunk(lines: &[&str], first_line: usize) -> Result<Chunk> {
+    let mut chunk = Chunk::default();
+    for (offset, line) in lines.iter().enumerate() {
+        let number = first_line + offset + 1;
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("v") => {
+                let values = fields
+                    .take(3)
+                    .map(str::parse::<f32>)
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| import_error(number, &e.to_string()))?;
+                let [x, y, z] = values[..] else {
+                    return Err(import_error(number, "vertex needs three coordinates"));
+                };
+                chunk.vertices.push(Point::new(x, y, z));
+            }
+            Some("f") => {
+                let corners = fields
+                    .map(|corner| {
+                        // Only the position index before any `/` matters.
+                        let index = corner.split('/').next().unwrap_or_default();
+                        match index.parse::<i64>() {
+                            Ok(i) if i > 0 => Ok(Index::Absolute(i as usize - 1)),
+                            Ok(i) if i < 0 => Ok(Index::Relative(chunk.vertices.len() as i64 + i)),
+                            _ => Err(import_error(number, "invalid face index")),
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if corners.len() < 3 {
+                    return Err(import_error(number, "face needs at least three vertices"));
+                }
+                chunk.faces.push((number, corners));
+            }
+            _ => {}
+        }
+    }
+    Ok(chunk)
+}
+
+fn import_error(line: usize, msg: &str) -> Error {
+    Error::Import(format!("OBJ line {line}: {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::obj::{Obj, CHUNK_LINES};
+    use crate::tuple::Point;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn vertices_and_faces_are_read() {
+        let source = "# a quad
+v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+f -3 -2 -1
+";
+        let obj = Obj::parse(source).unwrap();
+        assert_eq!(
+            obj.vertices,
+            vec![
+                Point::new(-1., 1., 0.),
+                Point::new(-1., 0.5, 0.),
+                Point::new(1., 0., 0.),
+                Point::new(1., 1., 0.),
+            ]
+        );
+        assert_eq!(obj.faces, vec![vec![0, 1, 2, 3], vec![1, 2, 3]]);
+        assert_eq!(obj.stats.lines, 8);
+        assert_eq!(obj.to_triangles(Default::default).len(), 3);
+    }
+
+    #[test]
+    pub fn relative_indices_resolve_across_chunks() {
+        let mut source = String::new();
+        for i in 0..CHUNK_LINES {
+            source.push_str(&format!("v {i} 0 0\n"));
+        }
+        // The face lands in the second chunk but refers back into the first.
+        source.push_str("f -1 -2 -3\nv 0 1 0\nf -1 -2 1\n");
+
+        let obj = Obj::parse(&source).unwrap();
+        let last = CHUNK_LINES - 1;
+        assert_eq!(
+            obj.faces,
+            vec![vec![last, last - 1, last - 2], vec![CHUNK_LINES, last, 0]]
+        );
+    }
+
+    #[test]
+    pub fn missing_vertices_are_reported_with_their_line() {
+        assert!(matches!(
+            Obj::parse("v 0 0 0\nv 1 0 0\nf 1 2 3\n"),
+            Err(Error::Import(msg)) if msg.contains("line 3")
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::pattern::{Pattern, Solid};
+use crate::tuple::{Color, Point};
+
+const DEFAULT_BRICK_WIDTH: f32 = 2.;
+const DEFAULT_BRICK_HEIGHT: f32 = 1.;
+const DEFAULT_MORTAR: f32 = 0.1;
+
+// Running-bond brickwork over the xz plane: rows of `width` by `height` bricks along x, each row
+// shifted by half a brick, separated by mortar joints `mortar` wide.
+#[derive(Debug)]
+pub struct Brick {
+    brick: Box<dyn Pattern>,
+    mortar: Box<dyn Pattern>,
+    width: f32,
+    height: f32,
+    joint: f32,
+}
+
+impl Brick {
+    pub fn new(brick: Color, mortar: Color) -> Box<Self> {
+        Self::with_patterns(Solid::new(brick), Solid::new(mortar))
+    }
+
+    pub fn with_patterns(brick: Box<dyn Pattern>, mortar: Box<dyn Pattern>) -> Box<Self> {
+        Box::new(Self {
+            brick,
+            mortar,
+            width: DEFAULT_BRICK_WIDTH,
+            height: DEFAULT_BRICK_HEIGHT,
+            joint: DEFAULT_MORTAR,
+        })
+    }
+
+    pub fn with_size(mut self: Box<Self>, width: f32, height: f32, mortar: f32) -> Box<Self> {
+        self.width = width;
+        self.height = height;
+        self.joint = mortar;
+        self
+    }
+
+    fn in_mortar(&self, point: &Point) -> bool {
+        let row = (point.z / self.height).floor();
+        let x = point.x + if row % 2. == 0. { 0. } else { self.width / 2. };
+        let u = x.rem_euclid(self.width);
+        let v = point.z.rem_euclid(self.height);
+        let half = self.joint / 2.;
+        u.min(self.width - u) < half || v.min(self.height - v) < half
+    }
+}
+
+impl Pattern for Brick {
+    fn color_at(&self, point: &Point) -> Color {
+        if self.in_mortar(point) {
+            self.mortar.color_at(point)
+        } else {
+            self.brick.color_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::{Brick, Pattern};
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(Point::new(1., 0., 0.5), Color::white() ; "middle of a brick")]
+    #[test_case(Point::new(2.02, 0., 0.5), Color::black() ; "head joint")]
+    #[test_case(Point::new(1., 0., 0.98), Color::black() ; "bed joint")]
+    #[test_case(Point::new(2., 0., 1.5), Color::white() ; "offset row covers the joint below")]
+    #[test_case(Point::new(-1., 0., 1.5), Color::black() ; "head joint of the offset row")]
+    pub fn bricks_are_laid_in_running_bond(point: Point, expected: Color) {
+        let pattern = Brick::new(Color::white(), Color::black());
+        assert_eq!(pattern.color_at(&point), expected);
+    }
+}
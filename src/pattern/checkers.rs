@@ -1,21 +1,27 @@
-use crate::matrix::Matrix4;
-use crate::pattern::Pattern;
-use crate::tuple::{Color, Point};
+use crate::pattern::{footprint_width, odd_fraction, Pattern};
+use crate::ray::Footprint;
+use crate::tuple::{Color, Point, Vector};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Checkers {
     even: Color,
     odd: Color,
-    transform: Matrix4,
 }
 
 impl Checkers {
     pub fn new(even: Color, odd: Color) -> Box<Self> {
-        Box::new(Self {
-            even,
-            odd,
-            transform: Matrix4::identity(),
-        })
+        Box::new(Self { even, odd })
+    }
+
+    // Box-filtered colour over `width` in pattern space. The cell parity is the XOR of the
+    // per-axis parities, and for independent axes the odd fraction of an XOR is
+    // (1 - product of (1 - 2 * fraction)) / 2.
+    pub fn color_at_filtered(&self, point: &Point, width: &Vector) -> Color {
+        let balance = (1. - 2. * odd_fraction(point.x, width.x))
+            * (1. - 2. * odd_fraction(point.y, width.y))
+            * (1. - 2. * odd_fraction(point.z, width.z));
+        let odd = (1. - balance) / 2.;
+        self.even * (1. - odd) + self.odd * odd
     }
 }
 
@@ -28,19 +34,15 @@ impl Pattern for Checkers {
         }
     }
 
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
-    }
-
-    fn set_transform(&mut self, transform: &Matrix4) {
-        self.transform = *transform;
+    fn color_at_footprint(&self, point: &Point, footprint: &Footprint) -> Color {
+        self.color_at_filtered(point, &footprint_width(footprint))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::pattern::{Checkers, Pattern};
-    use crate::tuple::{Color, Point};
+    use crate::tuple::{Color, Point, Vector};
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -66,4 +68,32 @@ mod tests {
         assert_eq!(pattern.color_at(&Point::new(0., 0., 0.99)), Color::white());
         assert_eq!(pattern.color_at(&Point::new(0., 0., 1.01)), Color::black());
     }
+
+    #[test]
+    pub fn filtered_checkers_without_width_match_point_samples() {
+        let pattern = Checkers::new(Color::white(), Color::black());
+        let p = Point::new(1.5, 0.2, 0.7);
+        assert_eq!(
+            pattern.color_at_filtered(&p, &Vector::new(0., 0., 0.)),
+            pattern.color_at(&p)
+        );
+    }
+
+    #[test]
+    pub fn filtered_checkers_fade_to_the_average_when_wide() {
+        let pattern = Checkers::new(Color::white(), Color::black());
+        let grey = Color::new(0.5, 0.5, 0.5);
+        assert_eq!(
+            pattern.color_at_filtered(&Point::new(0.3, 0., 0.), &Vector::new(8., 0., 0.)),
+            grey
+        );
+        assert_eq!(
+            pattern.color_at_filtered(&Point::new(1., 0., 1.), &Vector::new(1., 0., 0.)),
+            grey
+        );
+        assert_eq!(
+            pattern.color_at_filtered(&Point::new(0.75, 0., 1.5), &Vector::new(1., 0., 0.)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
 }
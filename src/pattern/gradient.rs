@@ -1,48 +1,155 @@
-use crate::matrix::Matrix4;
 use crate::pattern::Pattern;
-use crate::tuple::{Color, Point};
+use crate::postprocess::smoothstep;
+use crate::tuple::{Color, Point, Vector};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    // Eases in and out of every stop, hiding the creases linear ramps show at each one.
+    Smooth,
+}
+
+// Colours at positions along a gradient, blended in between. Positions are sorted on construction
+// and the end colours hold beyond the first and last stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRamp {
+    stops: Vec<(f32, Color)>,
+    interpolation: Interpolation,
+}
+
+impl ColorRamp {
+    pub fn new(mut stops: Vec<(f32, Color)>, interpolation: Interpolation) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            stops,
+            interpolation,
+        }
+    }
+
+    pub fn two(start: Color, end: Color) -> Self {
+        Self::new(vec![(0., start), (1., end)], Interpolation::Linear)
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    pub fn at(&self, t: f32) -> Color {
+        let Some(&(first, first_color)) = self.stops.first() else {
+            return Color::black();
+        };
+        let next = self.stops.partition_point(|&(position, _)| position <= t);
+        if next == 0 || t <= first {
+            return first_color;
+        }
+        let Some(&(end, end_color)) = self.stops.get(next) else {
+            return self.stops[next - 1].1;
+        };
+        let (start, start_color) = self.stops[next - 1];
+        let blend = match self.interpolation {
+            Interpolation::Linear => (t - start) / (end - start),
+            Interpolation::Smooth => smoothstep(start, end, t),
+        };
+        Color::lerp(start_color, end_color, blend)
+    }
+}
+
+// Blends along a line through a colour ramp. `new` runs along the x axis and mirrors back and
+// forth every unit; `along` runs from one point to another and holds the end colours beyond them.
+#[derive(Debug, Clone)]
 pub struct LinearGradient {
-    start: Color,
-    end: Color,
-    transform: Matrix4,
-    distance: Color,
+    ramp: ColorRamp,
+    origin: Point,
+    // Scaled so that its dot product with an offset from `origin` is the position along the ramp.
+    axis: Vector,
+    mirrored: bool,
 }
 
 impl LinearGradient {
     pub fn new(start: Color, end: Color) -> Box<Self> {
         Box::new(Self {
-            start,
-            end,
-            transform: Matrix4::identity(),
-            distance: end - start,
+            ramp: ColorRamp::two(start, end),
+            origin: Point::zero(),
+            axis: Vector::new(1., 0., 0.),
+            mirrored: true,
+        })
+    }
+
+    pub fn along(from: Point, to: Point, start: Color, end: Color) -> Box<Self> {
+        Self::along_ramp(from, to, ColorRamp::two(start, end))
+    }
+
+    // Positions 0 and 1 of the ramp fall on `from` and `to`.
+    pub fn along_ramp(from: Point, to: Point, ramp: ColorRamp) -> Box<Self> {
+        let direction = to - from;
+        let length_squared = direction.dot(&direction);
+        Box::new(Self {
+            ramp,
+            origin: from,
+            axis: if length_squared > 0. {
+                direction * (1. / length_squared)
+            } else {
+                Vector::zero()
+            },
+            mirrored: false,
         })
     }
 }
 
 impl Pattern for LinearGradient {
     fn color_at(&self, point: &Point) -> Color {
-        let fraction = point.x - point.x.floor();
-        if point.x.floor() % 2. == 0. {
-            self.start + self.distance * fraction
+        let t = (*point - self.origin).dot(&self.axis);
+        if !self.mirrored {
+            return self.ramp.at(t);
+        }
+        let fraction = t - t.floor();
+        if t.floor() % 2. == 0. {
+            self.ramp.at(fraction)
         } else {
-            self.end - self.distance * fraction
+            self.ramp.at(1. - fraction)
         }
     }
+}
+
+// Blends from the start of a ramp at `center` to its end at `radius` away, and holds the end
+// colour beyond.
+#[derive(Debug, Clone)]
+pub struct SphericalGradient {
+    center: Point,
+    radius: f32,
+    ramp: ColorRamp,
+}
 
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
+impl SphericalGradient {
+    pub fn new(center: Point, radius: f32, inner: Color, outer: Color) -> Box<Self> {
+        Self::with_ramp(center, radius, ColorRamp::two(inner, outer))
+    }
+
+    pub fn with_ramp(center: Point, radius: f32, ramp: ColorRamp) -> Box<Self> {
+        Box::new(Self {
+            center,
+            radius,
+            ramp,
+        })
     }
+}
 
-    fn set_transform(&mut self, transform: &Matrix4) {
-        self.transform = *transform;
+impl Pattern for SphericalGradient {
+    fn color_at(&self, point: &Point) -> Color {
+        let distance = (*point - self.center).magnitude();
+        let t = if self.radius > 0. {
+            distance / self.radius
+        } else {
+            1.
+        };
+        self.ramp.at(t)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::pattern::{LinearGradient, Pattern};
+    use crate::pattern::{ColorRamp, Interpolation, LinearGradient, Pattern, SphericalGradient};
     use crate::tuple::{Color, Point};
     use pretty_assertions::assert_eq;
 
@@ -63,4 +170,63 @@ mod tests {
             Color::new(0.25, 0.25, 0.25)
         );
     }
+
+    #[test]
+    pub fn gradient_between_points_holds_its_end_colors() {
+        let pattern = LinearGradient::along(
+            Point::new(0., 1., 0.),
+            Point::new(0., 3., 0.),
+            Color::black(),
+            Color::white(),
+        );
+        assert_eq!(
+            pattern.color_at(&Point::new(5., 2., -1.)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.color_at(&Point::new(0., 2.5, 0.)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(pattern.color_at(&Point::new(0., -4., 0.)), Color::black());
+        assert_eq!(pattern.color_at(&Point::new(0., 9., 0.)), Color::white());
+    }
+
+    #[test]
+    pub fn spherical_gradient_fades_out_from_its_center() {
+        let pattern =
+            SphericalGradient::new(Point::new(1., 0., 0.), 2., Color::white(), Color::black());
+        assert_eq!(pattern.color_at(&Point::new(1., 0., 0.)), Color::white());
+        assert_eq!(
+            pattern.color_at(&Point::new(1., 0., -1.)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(pattern.color_at(&Point::new(1., 5., 0.)), Color::black());
+    }
+
+    #[test]
+    pub fn ramp_blends_between_neighbouring_stops() {
+        let red = Color::new(1., 0., 0.);
+        let blue = Color::new(0., 0., 1.);
+        let ramp = ColorRamp::new(
+            vec![(1., blue), (0., Color::black()), (0.5, red)],
+            Interpolation::Linear,
+        );
+        assert_eq!(ramp.stops()[1], (0.5, red));
+        assert_eq!(ramp.at(-1.), Color::black());
+        assert_eq!(ramp.at(0.25), Color::new(0.5, 0., 0.));
+        assert_eq!(ramp.at(0.5), red);
+        assert_eq!(ramp.at(0.75), Color::new(0.5, 0., 0.5));
+        assert_eq!(ramp.at(2.), blue);
+    }
+
+    #[test]
+    pub fn smooth_ramp_eases_around_stops() {
+        let ramp = ColorRamp::new(
+            vec![(0., Color::black()), (1., Color::white())],
+            Interpolation::Smooth,
+        );
+        assert_eq!(ramp.at(0.5), Color::new(0.5, 0.5, 0.5));
+        assert!(ramp.at(0.1).r < 0.1);
+        assert!(ramp.at(0.9).r > 0.9);
+    }
 }
@@ -0,0 +1,96 @@
+use crate::pattern::{Pattern, Solid};
+use crate::tuple::{Color, Point};
+
+const SQRT_3: f32 = 1.732_050_8;
+const DEFAULT_MORTAR: f32 = 0.1;
+
+// Hexagonal tiles over the xz plane with a corner pointing along z, `size` from centre to corner,
+// separated by grout `mortar` wide.
+#[derive(Debug)]
+pub struct HexTile {
+    tile: Box<dyn Pattern>,
+    mortar: Box<dyn Pattern>,
+    size: f32,
+    joint: f32,
+}
+
+impl HexTile {
+    pub fn new(tile: Color, mortar: Color) -> Box<Self> {
+        Self::with_patterns(Solid::new(tile), Solid::new(mortar))
+    }
+
+    pub fn with_patterns(tile: Box<dyn Pattern>, mortar: Box<dyn Pattern>) -> Box<Self> {
+        Box::new(Self {
+            tile,
+            mortar,
+            size: 1.,
+            joint: DEFAULT_MORTAR,
+        })
+    }
+
+    pub fn with_size(mut self: Box<Self>, size: f32, mortar: f32) -> Box<Self> {
+        self.size = size;
+        self.joint = mortar;
+        self
+    }
+
+    // Offset from the centre of the tile the point falls in.
+    fn tile_offset(&self, point: &Point) -> (f32, f32) {
+        let q = (SQRT_3 / 3.).mul_add(point.x, -point.z / 3.) / self.size;
+        let r = (2. / 3.) * point.z / self.size;
+        let (q, r) = round_axial(q, r);
+        let center_x = self.size * SQRT_3 * (q + r / 2.);
+        let center_z = self.size * 1.5 * r;
+        (point.x - center_x, point.z - center_z)
+    }
+
+    fn in_mortar(&self, point: &Point) -> bool {
+        let (dx, dz) = self.tile_offset(point);
+        let (dx, dz) = (dx.abs(), dz.abs());
+        let inradius = self.size * SQRT_3 / 2.;
+        let edge_distance = inradius - dx.max(0.5f32.mul_add(dx, SQRT_3 / 2. * dz));
+        edge_distance < self.joint / 2.
+    }
+}
+
+// Rounds fractional axial coordinates to the nearest hexagon through cube coordinates.
+fn round_axial(q: f32, r: f32) -> (f32, f32) {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq, rr)
+}
+
+impl Pattern for HexTile {
+    fn color_at(&self, point: &Point) -> Color {
+        if self.in_mortar(point) {
+            self.mortar.color_at(point)
+        } else {
+            self.tile.color_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::hex_tile::SQRT_3;
+    use crate::pattern::{HexTile, Pattern};
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(Point::new(0., 0., 0.), Color::white() ; "tile centre")]
+    #[test_case(Point::new(SQRT_3, 0., 0.), Color::white() ; "neighbouring centre")]
+    #[test_case(Point::new(SQRT_3 / 2., 0., 0.), Color::black() ; "shared flat edge")]
+    #[test_case(Point::new(0., 0., 0.98), Color::black() ; "near the corner")]
+    #[test_case(Point::new(SQRT_3 / 2., 0., 1.5), Color::white() ; "centre of the next row")]
+    pub fn hexagons_are_separated_by_mortar(point: Point, expected: Color) {
+        let pattern = HexTile::new(Color::white(), Color::black());
+        assert_eq!(pattern.color_at(&point), expected);
+    }
+}
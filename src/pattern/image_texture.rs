@@ -0,0 +1,57 @@
+use crate::canvas::Canvas;
+use crate::pattern::Pattern;
+use crate::postprocess::sample_bilinear;
+use crate::tuple::{Color, Point};
+
+// An image projected straight down onto the xz plane, one copy per unit square with the top row
+// of the image towards +z. Load the image with `Canvas::load_texture` so its colours are linear.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    image: Canvas,
+}
+
+impl ImageTexture {
+    pub fn new(image: Canvas) -> Box<Self> {
+        Box::new(Self { image })
+    }
+}
+
+impl Pattern for ImageTexture {
+    fn color_at(&self, point: &Point) -> Color {
+        if self.image.pixels.is_empty() {
+            return Color::black();
+        }
+        let u = point.x.rem_euclid(1.);
+        let v = 1. - point.z.rem_euclid(1.);
+        sample_bilinear(
+            &self.image,
+            u * self.image.width as f32,
+            v * self.image.height as f32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::pattern::{ImageTexture, Pattern};
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn image_repeats_across_the_xz_plane() {
+        let mut image = Canvas::new(2, 2);
+        image.write_pixel(0, 0, Color::new(1., 0., 0.)).unwrap();
+        image.write_pixel(1, 1, Color::new(0., 0., 1.)).unwrap();
+        let texture = ImageTexture::new(image);
+
+        assert_eq!(
+            texture.color_at(&Point::new(0.25, 0., 0.75)),
+            Color::new(1., 0., 0.)
+        );
+        assert_eq!(
+            texture.color_at(&Point::new(-0.25, 3., -0.75)),
+            Color::new(0., 0., 1.)
+        );
+    }
+}
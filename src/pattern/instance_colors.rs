@@ -0,0 +1,95 @@
+use crate::pattern::Pattern;
+use crate::ray::Footprint;
+use crate::sampler::splitmix;
+use crate::shape::Shape;
+use crate::tuple::{Color, Point};
+
+const SATURATION: f32 = 0.6;
+const VALUE: f32 = 0.85;
+
+// Gives every object it is applied to a colour of its own, picked by hashing the object's id.
+// Many instances can share one material and still be told apart. Colours are evenly spread hues
+// unless a palette is given. Ids are generated per run, so the assignment changes between runs;
+// change the seed to reshuffle it within one.
+#[derive(Debug, Clone, Default)]
+pub struct InstanceColors {
+    palette: Vec<Color>,
+    seed: u64,
+}
+
+impl InstanceColors {
+    pub fn new() -> Box<Self> {
+        Box::default()
+    }
+
+    pub fn with_palette(palette: Vec<Color>) -> Box<Self> {
+        Box::new(Self { palette, seed: 0 })
+    }
+
+    pub fn with_seed(mut self: Box<Self>, seed: u64) -> Box<Self> {
+        self.seed = seed;
+        self
+    }
+
+    pub fn color_for(&self, id: u128) -> Color {
+        let hash = splitmix(self.seed ^ splitmix(id as u64 ^ splitmix((id >> 64) as u64)));
+        if self.palette.is_empty() {
+            let hue = (hash >> 40) as f32 / (1u64 << 24) as f32 * 360.;
+            Color::from_hsv(hue, SATURATION, VALUE)
+        } else {
+            self.palette[(hash % self.palette.len() as u64) as usize]
+        }
+    }
+}
+
+impl Pattern for InstanceColors {
+    fn color_object(&self, object: &dyn Shape, _point: &Point) -> Color {
+        self.color_for(object.get_id().as_u128())
+    }
+
+    fn color_object_filtered(
+        &self,
+        object: &dyn Shape,
+        point: &Point,
+        _footprint: &Footprint,
+    ) -> Color {
+        self.color_object(object, point)
+    }
+
+    // Outside of an object there is no id to go on.
+    fn color_at(&self, _point: &Point) -> Color {
+        self.color_for(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::{InstanceColors, Pattern};
+    use crate::shape::Sphere;
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    #[test]
+    pub fn objects_get_stable_distinct_colors() {
+        let pattern = InstanceColors::new();
+        let (a, b) = (Sphere::static_default(), Sphere::static_default());
+        let color_a = pattern.color_object(a, &Point::zero());
+        assert_eq!(pattern.color_object(a, &Point::new(1., 0., 0.)), color_a);
+        assert_ne!(pattern.color_object(b, &Point::zero()), color_a);
+        assert_ne!(
+            InstanceColors::new()
+                .with_seed(1)
+                .color_object(a, &Point::zero()),
+            color_a
+        );
+    }
+
+    #[test]
+    pub fn colors_come_from_the_palette() {
+        let palette = vec![Color::new(1., 0., 0.), Color::new(0., 0., 1.)];
+        let pattern = InstanceColors::with_palette(palette.clone());
+        for id in 0..32 {
+            assert!(palette.contains(&pattern.color_for(id)));
+        }
+    }
+}
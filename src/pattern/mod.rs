@@ -1,28 +1,110 @@
+mod brick;
 mod checkers;
 mod gradient;
+mod hex_tile;
+mod image_texture;
+mod instance_colors;
 mod ring;
+mod solid;
 mod stripe;
+mod transformed;
+mod triplanar;
+mod vertex_colors;
 
-use crate::tuple::{Color, Point};
+use crate::tuple::{Color, Point, Vector};
 use std::fmt::{Debug, Formatter};
 
 use crate::matrix::Matrix4;
+use crate::ray::Footprint;
 use crate::shape::Shape;
 
+pub use brick::Brick;
 pub use checkers::Checkers;
-pub use gradient::LinearGradient;
+pub use gradient::{ColorRamp, Interpolation, LinearGradient, SphericalGradient};
+pub use hex_tile::HexTile;
+pub use image_texture::ImageTexture;
+pub use instance_colors::InstanceColors;
 pub use ring::Ring;
+pub use solid::Solid;
 pub use stripe::Stripe;
+pub use transformed::TransformedPattern;
+pub use triplanar::Triplanar;
+pub use vertex_colors::VertexColors;
 
+const FOOTPRINT_SAMPLES: [(f32, f32); 4] = [
+    (-0.125, -0.375),
+    (0.375, -0.125),
+    (0.125, 0.375),
+    (-0.375, 0.125),
+];
+
+// A pattern only has to colour points in its own space. Mapping shading points through the
+// object's transform happens here, and placing the pattern on the object is left to
+// `TransformedPattern`.
 pub trait Pattern {
+    fn color_at(&self, point: &Point) -> Color;
+    // Colour averaged over a pixel footprint, both in pattern space. The default averages a
+    // rotated-grid set of samples spread over the footprint, which removes most of the shimmer on
+    // hard-edged patterns at grazing angles.
+    fn color_at_footprint(&self, point: &Point, footprint: &Footprint) -> Color {
+        FOOTPRINT_SAMPLES
+            .iter()
+            .map(|(a, b)| self.color_at(&(*point + footprint.dpdx * *a + footprint.dpdy * *b)))
+            .fold(Color::black(), |acc, c| acc + c)
+            * (1. / FOOTPRINT_SAMPLES.len() as f32)
+    }
     fn color_object(&self, object: &dyn Shape, point: &Point) -> Color {
-        let object_point = object.get_inverse_transform() * point;
-        let pattern_point = self.get_transform().inverse() * object_point;
-        self.color_at(&pattern_point)
+        self.color_at(&(object.get_inverse_transform() * point))
     }
-    fn color_at(&self, point: &Point) -> Color;
-    fn get_transform(&self) -> &Matrix4;
-    fn set_transform(&mut self, transform: &Matrix4);
+    fn color_object_filtered(
+        &self,
+        object: &dyn Shape,
+        point: &Point,
+        footprint: &Footprint,
+    ) -> Color {
+        let to_object = object.get_inverse_transform();
+        self.color_at_footprint(
+            &(to_object * point),
+            &transform_footprint(to_object, footprint),
+        )
+    }
+    fn transformed(self: Box<Self>, transform: Matrix4) -> Box<TransformedPattern<Self>>
+    where
+        Self: Sized,
+    {
+        TransformedPattern::new(self, transform)
+    }
+}
+
+fn transform_footprint(transform: &Matrix4, footprint: &Footprint) -> Footprint {
+    Footprint {
+        dpdx: *transform * footprint.dpdx,
+        dpdy: *transform * footprint.dpdy,
+    }
+}
+
+// Extent of a footprint along each pattern axis, suitable for an axis-aligned box filter.
+fn footprint_width(footprint: &Footprint) -> Vector {
+    let (dx, dy) = (footprint.dpdx, footprint.dpdy);
+    Vector::new(
+        dx.x.abs() + dy.x.abs(),
+        dx.y.abs() + dy.y.abs(),
+        dx.z.abs() + dy.z.abs(),
+    )
+}
+
+// Fraction of [x - width / 2, x + width / 2] that falls on cells with an odd floor, using the
+// closed-form integral of the unit square wave.
+fn odd_fraction(x: f32, width: f32) -> f32 {
+    if width < 1e-6 {
+        return (x.floor() as i32 % 2 != 0).into();
+    }
+
+    let integral = |x: f32| {
+        let period = (x / 2.).floor();
+        period + (2. * (x / 2. - period) - 1.).max(0.)
+    };
+    (integral(x + width / 2.) - integral(x - width / 2.)) / width
 }
 
 impl Debug for dyn Pattern {
@@ -33,14 +115,12 @@ impl Debug for dyn Pattern {
 
 #[cfg(test)]
 #[derive(Debug, Default)]
-pub struct TestPattern {
-    pub transform: Matrix4,
-}
+pub struct TestPattern;
 
 #[cfg(test)]
 impl TestPattern {
     pub fn new() -> Box<Self> {
-        Box::<Self>::default()
+        Box::new(Self)
     }
 }
 
@@ -49,12 +129,4 @@ impl Pattern for TestPattern {
     fn color_at(&self, point: &Point) -> Color {
         Color::new(point.x, point.y, point.z)
     }
-
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
-    }
-
-    fn set_transform(&mut self, transform: &Matrix4) {
-        self.transform = *transform;
-    }
 }
@@ -1,4 +1,3 @@
-use crate::matrix::Matrix4;
 use crate::pattern::Pattern;
 use crate::tuple::{Color, Point};
 
@@ -6,16 +5,11 @@ use crate::tuple::{Color, Point};
 pub struct Ring {
     even: Color,
     odd: Color,
-    transform: Matrix4,
 }
 
 impl Ring {
     pub fn new(even: Color, odd: Color) -> Box<Self> {
-        Box::new(Self {
-            even,
-            odd,
-            transform: Matrix4::identity(),
-        })
+        Box::new(Self { even, odd })
     }
 }
 
@@ -27,14 +21,6 @@ impl Pattern for Ring {
             self.odd
         }
     }
-
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
-    }
-
-    fn set_transform(&mut self, transform: &Matrix4) {
-        self.transform = *transform;
-    }
 }
 
 #[cfg(test)]
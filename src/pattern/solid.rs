@@ -0,0 +1,18 @@
+use crate::pattern::Pattern;
+use crate::tuple::{Color, Point};
+
+// One colour everywhere, for slots in composite patterns that take a sub-pattern.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Solid(pub Color);
+
+impl Solid {
+    pub fn new(color: Color) -> Box<Self> {
+        Box::new(Self(color))
+    }
+}
+
+impl Pattern for Solid {
+    fn color_at(&self, _point: &Point) -> Color {
+        self.0
+    }
+}
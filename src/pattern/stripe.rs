@@ -1,22 +1,22 @@
-use crate::matrix::Matrix4;
-
-use crate::pattern::Pattern;
-use crate::tuple::{Color, Point};
+use crate::pattern::{footprint_width, odd_fraction, Pattern};
+use crate::ray::Footprint;
+use crate::tuple::{Color, Point, Vector};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Stripe {
     pub even: Color,
     pub odd: Color,
-    transform: Matrix4,
 }
 
 impl Stripe {
     pub fn new(even: Color, odd: Color) -> Box<Self> {
-        Box::new(Self {
-            even,
-            odd,
-            transform: Matrix4::identity(),
-        })
+        Box::new(Self { even, odd })
+    }
+
+    // Box-filtered colour over `width` in pattern space.
+    pub fn color_at_filtered(&self, point: &Point, width: &Vector) -> Color {
+        let odd = odd_fraction(point.x, width.x);
+        self.even * (1. - odd) + self.odd * odd
     }
 }
 
@@ -29,12 +29,8 @@ impl Pattern for Stripe {
         }
     }
 
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
-    }
-
-    fn set_transform(&mut self, transform: &Matrix4) {
-        self.transform = *transform;
+    fn color_at_footprint(&self, point: &Point, footprint: &Footprint) -> Color {
+        self.color_at_filtered(point, &footprint_width(footprint))
     }
 }
 
@@ -43,6 +39,7 @@ mod tests {
     use crate::matrix::Matrix4;
     use crate::pattern::stripe::Stripe;
     use crate::pattern::Pattern;
+    use crate::ray::Footprint;
     use crate::shape::Sphere;
     use crate::tuple::{Color, Point, Vector};
     use pretty_assertions::assert_eq;
@@ -87,8 +84,7 @@ mod tests {
     pub fn stripe_with_pattern_transformation() {
         let obj = Sphere::static_default();
         let pattern_transform = Matrix4::identity().scale(&Vector::new(2., 2., 2.));
-        let mut pattern = Stripe::new(Color::white(), Color::black());
-        pattern.set_transform(&pattern_transform);
+        let pattern = Stripe::new(Color::white(), Color::black()).transformed(pattern_transform);
         let c = pattern.color_object(obj, &Point::new(1.5, 0., 0.));
         assert_eq!(c, Color::white());
     }
@@ -97,9 +93,27 @@ mod tests {
     pub fn stripe_with_both_transforms() {
         let obj = Sphere::static_default()
             .set_transform(&Matrix4::identity().scale(&Vector::new(2., 2., 2.)));
-        let mut pattern = Stripe::new(Color::white(), Color::black());
-        pattern.set_transform(&Matrix4::identity().translate(&Vector::new(0.5, 0., 0.)));
+        let pattern = Stripe::new(Color::white(), Color::black())
+            .transformed(Matrix4::identity().translate(&Vector::new(0.5, 0., 0.)));
         let c = pattern.color_object(obj, &Point::new(2.5, 0., 0.));
         assert_eq!(c, Color::white());
     }
+
+    #[test]
+    pub fn filtered_stripe_blends_across_an_edge() {
+        let obj = Sphere::static_default();
+        let pattern = Stripe::new(Color::white(), Color::black());
+        let footprint = Footprint {
+            dpdx: Vector::new(1., 0., 0.),
+            dpdy: Vector::new(0., 0., 1.),
+        };
+        assert_eq!(
+            pattern.color_object_filtered(obj, &Point::new(0., 0., 0.), &footprint),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.color_object_filtered(obj, &Point::new(0.5, 0., 0.), &footprint),
+            Color::white()
+        );
+    }
 }
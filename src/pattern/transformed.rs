@@ -0,0 +1,105 @@
+use crate::error::Result;
+use crate::matrix::Matrix4;
+use crate::pattern::{transform_footprint, Pattern};
+use crate::ray::Footprint;
+use crate::tuple::{Color, Point};
+
+// Places a pattern within its object's space. The inverse is computed once when the transform is
+// set rather than for every shaded point.
+#[derive(Debug, Clone)]
+pub struct TransformedPattern<P> {
+    pattern: Box<P>,
+    transform: Matrix4,
+    inverse: Matrix4,
+}
+
+impl<P: Pattern> TransformedPattern<P> {
+    pub fn new(pattern: Box<P>, transform: Matrix4) -> Box<Self> {
+        let mut transformed = Box::new(Self {
+            pattern,
+            transform: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        });
+        transformed.set_transform(transform);
+        transformed
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.try_set_transform(transform).unwrap();
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<()> {
+        self.inverse = transform.try_inverse()?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    pub const fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    pub fn pattern(&self) -> &P {
+        &self.pattern
+    }
+}
+
+impl<P: Pattern> Pattern for TransformedPattern<P> {
+    fn color_at(&self, point: &Point) -> Color {
+        self.pattern.color_at(&(self.inverse * *point))
+    }
+
+    fn color_at_footprint(&self, point: &Point, footprint: &Footprint) -> Color {
+        self.pattern.color_at_footprint(
+            &(self.inverse * *point),
+            &transform_footprint(&self.inverse, footprint),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::matrix::Matrix4;
+    use crate::pattern::{Pattern, TestPattern, TransformedPattern};
+    use crate::shape::Sphere;
+    use crate::tuple::{Color, Point, Vector};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn pattern_with_object_transformation() {
+        let obj = Sphere::static_default()
+            .set_transform(&Matrix4::identity().scale(&Vector::new(2., 2., 2.)));
+        let c = TestPattern::new().color_object(obj, &Point::new(2., 3., 4.));
+        assert_eq!(c, Color::new(1., 1.5, 2.));
+    }
+
+    #[test]
+    pub fn pattern_with_pattern_transformation() {
+        let obj = Sphere::static_default();
+        let pattern =
+            TestPattern::new().transformed(Matrix4::identity().scale(&Vector::new(2., 2., 2.)));
+        let c = pattern.color_object(obj, &Point::new(2., 3., 4.));
+        assert_eq!(c, Color::new(1., 1.5, 2.));
+    }
+
+    #[test]
+    pub fn pattern_with_both_transforms() {
+        let obj = Sphere::static_default()
+            .set_transform(&Matrix4::identity().scale(&Vector::new(2., 2., 2.)));
+        let pattern = TestPattern::new()
+            .transformed(Matrix4::identity().translate(&Vector::new(0.5, 1., 1.5)));
+        let c = pattern.color_object(obj, &Point::new(2.5, 3., 3.5));
+        assert_eq!(c, Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    pub fn singular_transforms_are_rejected() {
+        let mut pattern = TransformedPattern::new(TestPattern::new(), Matrix4::identity());
+        let flat = Matrix4::identity().scale(&Vector::new(1., 0., 1.));
+        assert!(matches!(
+            pattern.try_set_transform(flat),
+            Err(Error::SingularMatrix)
+        ));
+        assert_eq!(pattern.transform(), &Matrix4::identity());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::pattern::Pattern;
+use crate::ray::Footprint;
+use crate::shape::Shape;
+use crate::tuple::{Color, Point};
+
+const DEFAULT_SHARPNESS: f32 = 4.;
+
+// Projects a planar pattern, one that varies over x and z like `ImageTexture`, along all three
+// object axes and blends the projections by how squarely the surface faces each one. Surfaces
+// without texture coordinates get textured without the stretching a single projection shows on
+// the sides. Transform the inner pattern to scale or offset the texture.
+#[derive(Debug)]
+pub struct Triplanar {
+    pattern: Box<dyn Pattern>,
+    // Exponent on the normal components; higher values narrow the seams between projections.
+    sharpness: f32,
+}
+
+impl Triplanar {
+    pub fn new(pattern: Box<dyn Pattern>) -> Box<Self> {
+        Self::with_sharpness(pattern, DEFAULT_SHARPNESS)
+    }
+
+    pub fn with_sharpness(pattern: Box<dyn Pattern>, sharpness: f32) -> Box<Self> {
+        Box::new(Self { pattern, sharpness })
+    }
+}
+
+impl Pattern for Triplanar {
+    // Without a normal to go on, only the top-down projection is used.
+    fn color_at(&self, point: &Point) -> Color {
+        self.pattern.color_at(&Point::new(point.x, 0., point.z))
+    }
+
+    fn color_object(&self, object: &dyn Shape, point: &Point) -> Color {
+        let p = object.get_inverse_transform() * point;
+        let n = object.local_normal(&p);
+        let weights = [n.x, n.y, n.z].map(|c| c.abs().powf(self.sharpness));
+        let total: f32 = weights.iter().sum();
+        if total <= 0. {
+            return self.color_at(&p);
+        }
+
+        let projections = [
+            Point::new(p.z, 0., p.y),
+            Point::new(p.x, 0., p.z),
+            Point::new(p.x, 0., p.y),
+        ];
+        projections
+            .iter()
+            .zip(weights)
+            .filter(|(_, weight)| *weight > 0.)
+            .fold(Color::black(), |acc, (projected, weight)| {
+                acc + self.pattern.color_at(projected) * (weight / total)
+            })
+    }
+
+    // The blend already softens the seams, so the footprint isn't used.
+    fn color_object_filtered(
+        &self,
+        object: &dyn Shape,
+        point: &Point,
+        _footprint: &Footprint,
+    ) -> Color {
+        self.color_object(object, point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::{Pattern, Stripe, Triplanar};
+    use crate::shape::Cube;
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn each_face_uses_the_projection_it_faces() {
+        // Stripes along the projected x: object x on top, z on the sides facing x.
+        let pattern = Triplanar::new(Stripe::new(Color::white(), Color::black()));
+        let cube = Cube::static_default();
+        assert_eq!(
+            pattern.color_object(cube, &Point::new(0.5, 1., 0.2)),
+            Color::white()
+        );
+        assert_eq!(
+            pattern.color_object(cube, &Point::new(-0.5, 1., 0.2)),
+            Color::black()
+        );
+        assert_eq!(
+            pattern.color_object(cube, &Point::new(1., 0.2, -0.5)),
+            Color::black()
+        );
+    }
+
+    #[test]
+    pub fn blends_where_the_normal_is_between_axes() {
+        let pattern = Triplanar::with_sharpness(Stripe::new(Color::white(), Color::black()), 1.);
+        let sphere = crate::shape::Sphere::static_default();
+        let p = Point::new(0.5, 0.5f32.sqrt(), -0.5);
+        // x projection reads z = -0.5 (black), y reads x = 0.5 (white), z reads x (white).
+        let c = pattern.color_object(sphere, &p);
+        let weights = [0.5, 0.5f32.sqrt(), 0.5];
+        let expected = (weights[1] + weights[2]) / weights.iter().sum::<f32>();
+        assert_eq!(c, Color::new(expected, expected, expected));
+    }
+}
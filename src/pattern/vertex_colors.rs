@@ -0,0 +1,76 @@
+use crate::pattern::Pattern;
+use crate::tuple::{Color, Point};
+
+// Blends the colours assigned to a triangle's corners by the barycentric coordinates of the
+// point, as imported meshes specify them. Vertices are in the object space of the triangle. The
+// blended colour is multiplied by `tint`, which is white unless the vertex colours are meant to
+// modulate a base colour.
+#[derive(Debug, Copy, Clone)]
+pub struct VertexColors {
+    vertices: [Point; 3],
+    colors: [Color; 3],
+    tint: Color,
+}
+
+impl VertexColors {
+    pub fn new(vertices: [Point; 3], colors: [Color; 3]) -> Box<Self> {
+        Self::tinted(vertices, colors, Color::white())
+    }
+
+    pub fn tinted(vertices: [Point; 3], colors: [Color; 3], tint: Color) -> Box<Self> {
+        Box::new(Self {
+            vertices,
+            colors,
+            tint,
+        })
+    }
+}
+
+impl Pattern for VertexColors {
+    fn color_at(&self, point: &Point) -> Color {
+        let [a, b, c] = self.vertices;
+        let (v0, v1, v2) = (b - a, c - a, *point - a);
+        let (d00, d01, d11) = (v0.dot(&v0), v0.dot(&v1), v1.dot(&v1));
+        let (d20, d21) = (v2.dot(&v0), v2.dot(&v1));
+        let denominator = d00.mul_add(d11, -d01 * d01);
+        if denominator.abs() < f32::EPSILON {
+            return self.colors[0].hadamard_product(&self.tint);
+        }
+
+        let v = d11.mul_add(d20, -d01 * d21) / denominator;
+        let w = d00.mul_add(d21, -d01 * d20) / denominator;
+        (self.colors[0] * (1. - v - w) + self.colors[1] * v + self.colors[2] * w)
+            .hadamard_product(&self.tint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::{Pattern, VertexColors};
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn colors_are_blended_across_the_triangle() {
+        let pattern = VertexColors::new(
+            [
+                Point::new(0., 0., 0.),
+                Point::new(1., 0., 0.),
+                Point::new(0., 1., 0.),
+            ],
+            [
+                Color::new(1., 0., 0.),
+                Color::new(0., 1., 0.),
+                Color::new(0., 0., 1.),
+            ],
+        );
+        assert_eq!(
+            pattern.color_at(&Point::new(1., 0., 0.)),
+            Color::new(0., 1., 0.)
+        );
+        assert_eq!(
+            pattern.color_at(&Point::new(0.25, 0.5, 0.)),
+            Color::new(0.25, 0.25, 0.5)
+        );
+    }
+}
@@ -0,0 +1,456 @@
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::shape::{Shape, Triangle, VertexColorBlend};
+use crate::tuple::{Color, Point};
+use std::io::BufRead;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Scalar {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PropertyKind {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// The values of one element, one entry per property. Scalars are stored as single item lists.
+type Row = Vec<Vec<f64>>;
+
+// A parsed ASCII or binary little-endian PLY file. Every element declared in the header is read,
+// but only vertex positions and colours and the face indices are interpreted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ply {
+    elements: Vec<(Element, Vec<Row>)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlyVertex {
+    pub position: Point,
+    pub color: Option<Color>,
+}
+
+impl Ply {
+    pub fn parse(mut reader: impl BufRead) -> Result<Self> {
+        let (format, declared) = parse_header(&mut reader)?;
+        let mut body = vec![];
+        reader.read_to_end(&mut body)?;
+
+        let mut values = match format {
+            Format::Ascii => Values::Ascii(
+                std::str::from_utf8(&body)
+                    .map_err(|_| import_error("ASCII body is not valid text"))?
+                    .split_whitespace(),
+            ),
+            Format::BinaryLittleEndian => Values::Binary(&body),
+        };
+
+        // Counts come from the header, so they only size the rows up to what the body could hold.
+        let mut elements = vec![];
+        for element in declared {
+            let mut rows = Vec::with_capacity(element.count.min(body.len()));
+            for _ in 0..element.count {
+                let mut row = Vec::with_capacity(element.properties.len());
+                for property in &element.properties {
+                    row.push(match property.kind {
+                        PropertyKind::Scalar(ty) => vec![values.next(ty)?],
+                        PropertyKind::List { count, item } => {
+                            let count = values.next(count)? as usize;
+                            (0..count)
+                                .map(|_| values.next(item))
+                                .collect::<Result<_>>()?
+                        }
+                    });
+                }
+                rows.push(row);
+            }
+            elements.push((element, rows));
+        }
+        Ok(Self { elements })
+    }
+
+    pub fn vertices(&self) -> Result<Vec<PlyVertex>> {
+        let (element, rows) = self
+            .elements
+            .iter()
+            .find(|(e, _)| e.name == "vertex")
+            .ok_or_else(|| import_error("no vertex element"))?;
+        let find = |name: &str| element.properties.iter().position(|p| p.name == name);
+        let position = ["x", "y", "z"]
+            .map(find)
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| import_error("vertices have no position"))?;
+        let color = ["red", "green", "blue"]
+            .map(find)
+            .into_iter()
+            .collect::<Option<Vec<_>>>();
+        // Integer colour channels run from 0 to 255, floating point ones from 0 to 1.
+        let color_scale =
+            color
+                .as_ref()
+                .map(|channels| match element.properties[channels[0]].kind {
+                    PropertyKind::Scalar(Scalar::Float | Scalar::Double) => 1.,
+                    _ => 255.,
+                });
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let value = |index: usize| row[index][0] as f32;
+                PlyVertex {
+                    position: Point::new(
+                        value(position[0]),
+                        value(position[1]),
+                        value(position[2]),
+                    ),
+                    color: color.as_ref().zip(color_scale).map(|(channels, scale)| {
+                        Color::new(
+                            value(channels[0]) / scale,
+                            value(channels[1]) / scale,
+                            value(channels[2]) / scale,
+                        )
+                    }),
+                }
+            })
+            .collect())
+    }
+
+    // Vertex indices of every polygon in the face element, or none if the file has no faces.
+    pub fn faces(&self) -> Result<Vec<Vec<usize>>> {
+        let Some((element, rows)) = self.elements.iter().find(|(e, _)| e.name == "face") else {
+            return Ok(vec![]);
+        };
+        let index = element
+            .properties
+            .iter()
+            .position(|p| p.name == "vertex_indices" || p.name == "vertex_index")
+            .ok_or_else(|| import_error("faces have no vertex indices"))?;
+        rows.iter()
+            .map(|row| {
+                row[index]
+                    .iter()
+                    .map(|&i| {
+                        if i < 0. || i.fract() != 0. {
+                            return Err(import_error(&format!("invalid vertex index {i}")));
+                        }
+                        Ok(i as usize)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Fans every face into triangles. Meshes with vertex colours get a `VertexColors` pattern on
+    // each triangle in place of whatever pattern `material` sets. As with OBJ meshes, the
+    // triangles are open surfaces, so a closed transparent mesh refracts like a hollow shell.
+    pub fn to_triangles(&self, material: impl Fn() -> Material) -> Result<Vec<&'static dyn Shape>> {
+        let vertices = self.vertices()?;
+        let mut triangles: Vec<&'static dyn Shape> = vec![];
+        for face in self.faces()? {
+            let corners = face
+                .iter()
+                .map(|&i| vertices.get(i).copied())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| import_error("face refers to a missing vertex"))?;
+            for i in 1..corners.len().saturating_sub(1) {
+                let corners = [corners[0], corners[i], corners[i + 1]];
+                let [p1, p2, p3] = corners.map(|c| c.position);
+                let triangle = Triangle::with_material(p1, p2, p3, material());
+                if let [Some(a), Some(b), Some(c)] = corners.map(|c| c.color) {
+                    triangle.set_vertex_colors([a, b, c], VertexColorBlend::Replace);
+                }
+                triangles.push(triangle);
+            }
+        }
+        Ok(triangles)
+    }
+}
+
+// Source of property values in the order they appear in the body.
+enum Values<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary(&'a [u8]),
+}
+
+impl Values<'_> {
+    fn next(&mut self, ty: Scalar) -> Result<f64> {
+        match self {
+            Self::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| import_error("unexpected end of data"))?;
+                token
+                    .parse()
+                    .map_err(|_| import_error(&format!("invalid number {token:?}")))
+            }
+            Self::Binary(bytes) => {
+                if bytes.len() < ty.size() {
+                    return Err(import_error("unexpected end of data"));
+                }
+                let (value, rest) = bytes.split_at(ty.size());
+                *bytes = rest;
+                Ok(ty.read_le(value))
+            }
+        }
+    }
+}
+
+impl Scalar {
+    const fn size(self) -> usize {
+        match self {
+            Self::Char | Self::UChar => 1,
+            Self::Short | Self::UShort => 2,
+            Self::Int | Self::UInt | Self::Float => 4,
+            Self::Double => 8,
+        }
+    }
+
+    // `bytes` holds exactly `size()` bytes.
+    fn read_le(self, bytes: &[u8]) -> f64 {
+        match self {
+            Self::Char => f64::from(i8::from_le_bytes([bytes[0]])),
+            Self::UChar => f64::from(bytes[0]),
+            Self::Short => f64::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+            Self::UShort => f64::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+            Self::Int => f64::from(i32::from_le_bytes(bytes.try_into().unwrap())),
+            Self::UInt => f64::from(u32::from_le_bytes(bytes.try_into().unwrap())),
+            Self::Float => f64::from(f32::from_le_bytes(bytes.try_into().unwrap())),
+            Self::Double => f64::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+}
+
+fn parse_header(reader: &mut impl BufRead) -> Result<(Format, Vec<Element>)> {
+    let mut line = String::new();
+    let mut next_line = |line: &mut String| -> Result<()> {
+        line.clear();
+        if reader.read_line(line)? == 0 {
+            return Err(import_error("header is not terminated"));
+        }
+        Ok(())
+    };
+
+    next_line(&mut line)?;
+    if line.trim() != "ply" {
+        return Err(import_error("missing ply magic number"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    loop {
+        next_line(&mut line)?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["end_header"] => break,
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", other, _] => {
+                return Err(import_error(&format!("unsupported format {other}")));
+            }
+            ["element", name, count] => elements.push(Element {
+                name: (*name).to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| import_error(&format!("invalid element count {count:?}")))?,
+                properties: vec![],
+            }),
+            ["property", "list", count, item, name] => {
+                push_property(
+                    &mut elements,
+                    name,
+                    PropertyKind::List {
+                        count: scalar(count)?,
+                        item: scalar(item)?,
+                    },
+                )?;
+            }
+            ["property", ty, name] => {
+                push_property(&mut elements, name, PropertyKind::Scalar(scalar(ty)?))?;
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(import_error(&format!("unexpected header line {line:?}"))),
+        }
+    }
+
+    let format = format.ok_or_else(|| import_error("missing format line"))?;
+    Ok((format, elements))
+}
+
+fn push_property(elements: &mut [Element], name: &str, kind: PropertyKind) -> Result<()> {
+    let element = elements
+        .last_mut()
+        .ok_or_else(|| import_error("property declared before any element"))?;
+    element.properties.push(Property {
+        name: name.to_string(),
+        kind,
+    });
+    Ok(())
+}
+
+fn scalar(name: &str) -> Result<Scalar> {
+    Ok(match name {
+        "char" | "int8" => Scalar::Char,
+        "uchar" | "uint8" => Scalar::UChar,
+        "short" | "int16" => Scalar::Short,
+        "ushort" | "uint16" => Scalar::UShort,
+        "int" | "int32" => Scalar::Int,
+        "uint" | "uint32" => Scalar::UInt,
+        "float" | "float32" => Scalar::Float,
+        "double" | "float64" => Scalar::Double,
+        other => return Err(import_error(&format!("unknown property type {other}"))),
+    })
+}
+
+fn import_error(msg: &str) -> Error {
+    Error::Import(format!("PLY: {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::ply::Ply;
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+
+    const ASCII: &str = "ply
+format ascii 1.0
+comment two coloured points
+element vertex 2
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 1 2 255 0 0
+-1.5 0 0.25 0 51 255
+3 0 1 0
+";
+
+    #[test]
+    pub fn ascii_vertices_are_read_with_colors() {
+        let ply = Ply::parse(ASCII.as_bytes()).unwrap();
+        let vertices = ply.vertices().unwrap();
+        assert_eq!(vertices.len(), 2);
+        assert_eq!(vertices[0].position, Point::new(0., 1., 2.));
+        assert_eq!(vertices[0].color, Some(Color::new(1., 0., 0.)));
+        assert_eq!(vertices[1].position, Point::new(-1.5, 0., 0.25));
+        assert_eq!(vertices[1].color, Some(Color::new(0., 0.2, 1.)));
+    }
+
+    #[test]
+    pub fn binary_files_match_their_ascii_equivalent() {
+        let mut binary = ASCII
+            .replace("format ascii", "format binary_little_endian")
+            .split("end_header\n")
+            .next()
+            .unwrap()
+            .to_string()
+            .into_bytes();
+        binary.extend_from_slice(b"end_header\n");
+        for (position, color) in [
+            ([0f32, 1., 2.], [255u8, 0, 0]),
+            ([-1.5, 0., 0.25], [0, 51, 255]),
+        ] {
+            for v in position {
+                binary.extend_from_slice(&v.to_le_bytes());
+            }
+            binary.extend_from_slice(&color);
+        }
+        binary.push(3);
+        for i in [0i32, 1, 0] {
+            binary.extend_from_slice(&i.to_le_bytes());
+        }
+
+        let ascii = Ply::parse(ASCII.as_bytes()).unwrap();
+        assert_eq!(Ply::parse(binary.as_slice()).unwrap(), ascii);
+    }
+
+    #[test]
+    pub fn polygons_are_fanned_into_vertex_colored_triangles() {
+        let quad = "ply
+format ascii 1.0
+element vertex 4
+property float x
+property float y
+property float z
+property float red
+property float green
+property float blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 1 0 0
+1 0 0 0 1 0
+1 1 0 0 0 1
+0 1 0 1 1 1
+4 0 1 2 3
+";
+        let triangles = Ply::parse(quad.as_bytes())
+            .unwrap()
+            .to_triangles(Default::default)
+            .unwrap();
+        assert_eq!(triangles.len(), 2);
+        let color =
+            triangles[0]
+                .get_material()
+                .surface_color(triangles[0], &Point::new(1., 0., 0.), None);
+        assert_eq!(color, Color::new(0., 1., 0.));
+    }
+
+    #[test]
+    pub fn negative_vertex_indices_are_rejected() {
+        let negative = ASCII.replace("3 0 1 0", "3 0 1 -1");
+        let ply = Ply::parse(negative.as_bytes()).unwrap();
+        assert!(matches!(ply.faces(), Err(Error::Import(_))));
+    }
+
+    #[test]
+    pub fn oversized_element_count_is_an_error() {
+        let oversized = ASCII.replace("element face 1", &format!("element face {}", usize::MAX));
+        assert!(matches!(
+            Ply::parse(oversized.as_bytes()),
+            Err(Error::Import(_))
+        ));
+    }
+
+    #[test]
+    pub fn truncated_body_is_an_error() {
+        let truncated = &ASCII[..ASCII.len() - 8];
+        assert!(matches!(
+            Ply::parse(truncated.as_bytes()),
+            Err(Error::Import(_))
+        ));
+    }
+}
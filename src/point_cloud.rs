@@ -0,0 +1,119 @@
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ply::Ply;
+use crate::shape::{Shape, Sphere};
+use crate::tuple::{Color, Point, Vector};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CloudPoint {
+    pub position: Point,
+    pub color: Color,
+}
+
+// Points without a colour of their own are drawn white.
+pub fn load(path: &Path) -> Result<Vec<CloudPoint>> {
+    let _span = tracing::info_span!("load_point_cloud", path = %path.display()).entered();
+    let reader = BufReader::new(File::open(path)?);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("ply") => Ok(Ply::parse(reader)?
+            .vertices()?
+            .into_iter()
+            .map(|v| CloudPoint {
+                position: v.position,
+                color: v.color.unwrap_or_else(Color::white),
+            })
+            .collect()),
+        _ => parse_xyz(reader),
+    }
+}
+
+// One point per line as `x y z`, optionally followed by `r g b` in the 0 to 255 range. Blank
+// lines and lines starting with `#` are skipped.
+pub fn parse_xyz(reader: impl BufRead) -> Result<Vec<CloudPoint>> {
+    let mut points = vec![];
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let values = line
+            .split_whitespace()
+            .map(str::parse::<f32>)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::Import(format!("XYZ line {}: {e}", number + 1)))?;
+        let color = match values.len() {
+            3 => Color::white(),
+            6 => Color::new(values[3] / 255., values[4] / 255., values[5] / 255.),
+            n => {
+                return Err(Error::Import(format!(
+                    "XYZ line {}: expected 3 or 6 values, found {n}",
+                    number + 1
+                )))
+            }
+        };
+        points.push(CloudPoint {
+            position: Point::new(values[0], values[1], values[2]),
+            color,
+        });
+    }
+    Ok(points)
+}
+
+// Renders every point as a small sphere taking its colour from the point. Large clouds should be
+// rendered with an acceleration structure enabled in the render settings.
+pub fn to_spheres(points: &[CloudPoint], radius: f32) -> Vec<&'static dyn Shape> {
+    points
+        .iter()
+        .map(|p| {
+            let sphere = Sphere::static_default().set_transform(
+                &Matrix4::identity()
+                    .scale(&Vector::new(radius, radius, radius))
+                    .translate(&(p.position - Point::zero())),
+            );
+            sphere.common.material = Material {
+                color: p.color,
+                ..Default::default()
+            };
+            &*sphere as &dyn Shape
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::point_cloud::{parse_xyz, to_spheres};
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn xyz_points_are_read_with_optional_colors() {
+        let data = "# scan\n0 0 0\n\n1 2 3 255 0 51\n";
+        let points = parse_xyz(data.as_bytes()).unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].color, Color::white());
+        assert_eq!(points[1].position, Point::new(1., 2., 3.));
+        assert_eq!(points[1].color, Color::new(1., 0., 0.2));
+
+        assert!(matches!(
+            parse_xyz("1 2\n".as_bytes()),
+            Err(Error::Import(msg)) if msg.contains("line 1")
+        ));
+    }
+
+    #[test]
+    pub fn points_become_small_colored_spheres() {
+        let points = parse_xyz("1 2 3 0 255 0\n".as_bytes()).unwrap();
+        let spheres = to_spheres(&points, 0.1);
+        assert_eq!(spheres.len(), 1);
+        assert_eq!(spheres[0].get_material().color, Color::new(0., 1., 0.));
+        let bounds = spheres[0].get_bounds();
+        assert_eq!(bounds.min, Point::new(0.9, 1.9, 2.9));
+    }
+}
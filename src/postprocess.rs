@@ -0,0 +1,218 @@
+use crate::canvas::Canvas;
+use crate::tuple::Color;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Effect {
+    Bloom {
+        threshold: f32,
+        radius: usize,
+        intensity: f32,
+    },
+    Vignette {
+        strength: f32,
+        radius: f32,
+    },
+    ChromaticAberration {
+        strength: f32,
+    },
+    FilmGrain {
+        amount: f32,
+        seed: u64,
+    },
+}
+
+impl Effect {
+    pub fn apply(&self, canvas: &mut Canvas) {
+        match *self {
+            Self::Bloom {
+                threshold,
+                radius,
+                intensity,
+            } => bloom(canvas, threshold, radius, intensity),
+            Self::Vignette { strength, radius } => vignette(canvas, strength, radius),
+            Self::ChromaticAberration { strength } => chromatic_aberration(canvas, strength),
+            Self::FilmGrain { amount, seed } => film_grain(canvas, amount, seed),
+        }
+    }
+}
+
+pub fn apply_all(canvas: &mut Canvas, effects: &[Effect]) {
+    for effect in effects {
+        effect.apply(canvas);
+    }
+}
+
+fn bloom(canvas: &mut Canvas, threshold: f32, radius: usize, intensity: f32) {
+    let mut bright = canvas.clone();
+    bright.par_map_pixels(|_, _, c| {
+        Color::new(
+            (c.r - threshold).max(0.),
+            (c.g - threshold).max(0.),
+            (c.b - threshold).max(0.),
+        )
+    });
+    let blurred = box_blur(&box_blur(&bright, radius, true), radius, false);
+
+    canvas.par_map_pixels(|x, y, c| c + blurred.pixels[y * blurred.width + x] * intensity);
+}
+
+fn box_blur(canvas: &Canvas, radius: usize, horizontal: bool) -> Canvas {
+    let mut out = canvas.clone();
+    let window = (2 * radius + 1) as f32;
+    out.par_map_pixels(|x, y, _| {
+        let mut sum = Color::black();
+        for offset in 0..=2 * radius {
+            let (sx, sy) = if horizontal {
+                ((x + offset).saturating_sub(radius), y)
+            } else {
+                (x, (y + offset).saturating_sub(radius))
+            };
+            let sx = sx.min(canvas.width - 1);
+            let sy = sy.min(canvas.height - 1);
+            sum += canvas.pixels[sy * canvas.width + sx];
+        }
+        sum * (1. / window)
+    });
+    out
+}
+
+fn vignette(canvas: &mut Canvas, strength: f32, radius: f32) {
+    let (cx, cy) = (canvas.width as f32 / 2., canvas.height as f32 / 2.);
+    let max_distance = cx.hypot(cy);
+    canvas.par_map_pixels(|x, y, c| {
+        let d = (x as f32 + 0.5 - cx).hypot(y as f32 + 0.5 - cy) / max_distance;
+        c * strength.mul_add(-smoothstep(radius, 1., d), 1.)
+    });
+}
+
+fn chromatic_aberration(canvas: &mut Canvas, strength: f32) {
+    let source = canvas.clone();
+    let (cx, cy) = (canvas.width as f32 / 2., canvas.height as f32 / 2.);
+    canvas.par_map_pixels(|x, y, c| {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        let red = sample_bilinear(
+            &source,
+            dx.mul_add(1. - strength, cx),
+            dy.mul_add(1. - strength, cy),
+        );
+        let blue = sample_bilinear(
+            &source,
+            dx.mul_add(1. + strength, cx),
+            dy.mul_add(1. + strength, cy),
+        );
+        Color::new(red.r, c.g, blue.b)
+    });
+}
+
+fn film_grain(canvas: &mut Canvas, amount: f32, seed: u64) {
+    canvas.par_map_pixels(|x, y, c| {
+        let noise = hash_to_unit(seed ^ ((y as u64) << 32 | x as u64)) - 0.5;
+        c + Color::new(1., 1., 1.) * (noise * amount)
+    });
+}
+
+pub(crate) fn sample_bilinear(canvas: &Canvas, x: f32, y: f32) -> Color {
+    let x = (x - 0.5).clamp(0., (canvas.width - 1) as f32);
+    let y = (y - 0.5).clamp(0., (canvas.height - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = (
+        (x0 + 1).min(canvas.width - 1),
+        (y0 + 1).min(canvas.height - 1),
+    );
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let at = |x: usize, y: usize| canvas.pixels[y * canvas.width + x];
+
+    let top = Color::lerp(at(x0, y0), at(x1, y0), fx);
+    let bottom = Color::lerp(at(x0, y1), at(x1, y1), fx);
+    Color::lerp(top, bottom, fy)
+}
+
+pub(crate) fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * 2f32.mul_add(-t, 3.)
+}
+
+fn hash_to_unit(mut v: u64) -> f32 {
+    v = v.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    v = (v ^ (v >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    v = (v ^ (v >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    v ^= v >> 31;
+    (v >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::postprocess::{apply_all, Effect};
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+
+    fn filled(width: usize, height: usize, color: Color) -> Canvas {
+        let mut c = Canvas::new(width, height);
+        c.par_map_pixels(|_, _, _| color);
+        c
+    }
+
+    #[test]
+    pub fn vignette_darkens_corners_but_not_center() {
+        let mut c = filled(21, 21, Color::white());
+        Effect::Vignette {
+            strength: 0.8,
+            radius: 0.3,
+        }
+        .apply(&mut c);
+        assert_eq!(c.pixel_at(10, 10).unwrap(), Color::white());
+        assert!(c.pixel_at(0, 0).unwrap().r < 0.5);
+    }
+
+    #[test]
+    pub fn bloom_spreads_bright_pixels() {
+        let mut c = Canvas::new(9, 9);
+        c.write_pixel(4, 4, Color::new(2., 2., 2.)).unwrap();
+        Effect::Bloom {
+            threshold: 1.,
+            radius: 1,
+            intensity: 1.,
+        }
+        .apply(&mut c);
+        assert!(c.pixel_at(5, 5).unwrap().r > 0.);
+        assert_eq!(c.pixel_at(7, 7).unwrap(), Color::black());
+    }
+
+    #[test]
+    pub fn bloom_ignores_pixels_below_threshold() {
+        let mut c = filled(5, 5, Color::new(0.5, 0.5, 0.5));
+        Effect::Bloom {
+            threshold: 0.8,
+            radius: 2,
+            intensity: 1.,
+        }
+        .apply(&mut c);
+        assert_eq!(c.pixel_at(2, 2).unwrap(), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    pub fn chromatic_aberration_keeps_green_and_center() {
+        let mut c = Canvas::new(11, 11);
+        c.par_map_pixels(|x, _, _| Color::new(x as f32 / 10., 0.3, x as f32 / 10.));
+        Effect::ChromaticAberration { strength: 0.1 }.apply(&mut c);
+        assert_eq!(c.pixel_at(5, 5).unwrap(), Color::new(0.5, 0.3, 0.5));
+        let edge = c.pixel_at(10, 5).unwrap();
+        assert_eq!(edge.g, 0.3);
+        assert!(edge.r < edge.b);
+    }
+
+    #[test]
+    pub fn film_grain_is_deterministic_for_a_seed() {
+        let grain = [Effect::FilmGrain {
+            amount: 0.2,
+            seed: 7,
+        }];
+        let mut a = filled(4, 4, Color::new(0.5, 0.5, 0.5));
+        let mut b = filled(4, 4, Color::new(0.5, 0.5, 0.5));
+        apply_all(&mut a, &grain);
+        apply_all(&mut b, &grain);
+        assert_eq!(a.pixels, b.pixels);
+        assert!(a.pixels.iter().any(|&p| p != Color::new(0.5, 0.5, 0.5)));
+    }
+}
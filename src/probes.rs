@@ -0,0 +1,159 @@
+use crate::bounds::{axis_of, Bounds};
+use crate::ray::Ray;
+use crate::sampler;
+use crate::sampling::uniform_sphere;
+use crate::tuple::{Color, Point};
+use crate::world::World;
+use rand::Rng;
+use rayon::prelude::*;
+
+// How deep the rays gathering light for a probe are traced.
+const PROBE_DEPTH: i32 = 1;
+
+// Incoming light averaged over every direction at the points of a regular grid, interpolated
+// between them during shading. It stands in for the diffuse light bouncing between surfaces,
+// which the Whitted integrator otherwise approximates with a constant ambient term.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeGrid {
+    bounds: Bounds,
+    resolution: usize,
+    irradiance: Vec<Color>,
+}
+
+impl ProbeGrid {
+    // Places `resolution` probes along each axis of `bounds` and gathers `samples` rays at each.
+    // Each probe reseeds the sampler like a pixel does, so a bake doesn't depend on which thread
+    // traced which probe.
+    pub fn bake(world: &World, bounds: Bounds, resolution: usize, samples: usize) -> Self {
+        let resolution = resolution.max(1);
+        let seed = world.settings.seed;
+        let irradiance = (0..resolution.pow(3))
+            .into_par_iter()
+            .map(|index| {
+                sampler::seed_pixel(seed, index, 0);
+                let origin = probe_position(&bounds, resolution, index);
+                let total = (0..samples.max(1))
+                    .map(|_| {
+                        let (u, v) = sampler::with_rng(|rng| (rng.gen(), rng.gen()));
+                        let direction = uniform_sphere(u, v);
+                        world.color_at(&Ray::new(origin, direction), PROBE_DEPTH)
+                    })
+                    .fold(Color::black(), |acc, c| acc + c);
+                total * (1. / samples.max(1) as f32)
+            })
+            .collect();
+
+        Self {
+            bounds,
+            resolution,
+            irradiance,
+        }
+    }
+
+    // Trilinearly interpolated between the surrounding probes. Points outside the grid use the
+    // nearest probes on its boundary.
+    pub fn irradiance_at(&self, point: &Point) -> Color {
+        let last = self.resolution - 1;
+        let mut cells = [(0, 0, 0.); 3];
+        for (axis, cell) in cells.iter_mut().enumerate() {
+            let (lo, hi) = (self.bounds.min_at(axis), self.bounds.max_at(axis));
+            let f = if last == 0 || hi <= lo {
+                0.
+            } else {
+                ((axis_of(point, axis) - lo) / (hi - lo) * last as f32).clamp(0., last as f32)
+            };
+            let i0 = (f.floor() as usize).min(last);
+            *cell = (i0, (i0 + 1).min(last), f - i0 as f32);
+        }
+
+        let [(x0, x1, tx), (y0, y1, ty), (z0, z1, tz)] = cells;
+        let probe = |x: usize, y: usize, z: usize| {
+            self.irradiance[(z * self.resolution + y) * self.resolution + x]
+        };
+        let lerp = |a: Color, b: Color, t: f32| a * (1. - t) + b * t;
+        let along_x = |y, z| lerp(probe(x0, y, z), probe(x1, y, z), tx);
+        let along_y = |z| lerp(along_x(y0, z), along_x(y1, z), ty);
+        lerp(along_y(z0), along_y(z1), tz)
+    }
+}
+
+fn probe_position(bounds: &Bounds, resolution: usize, index: usize) -> Point {
+    let coordinate = |axis: usize, i: usize| {
+        let (lo, hi) = (bounds.min_at(axis), bounds.max_at(axis));
+        if resolution == 1 {
+            (lo + hi) / 2.
+        } else {
+            lo + (hi - lo) * i as f32 / (resolution - 1) as f32
+        }
+    };
+    let (x, y, z) = (
+        index % resolution,
+        index / resolution % resolution,
+        index / (resolution * resolution),
+    );
+    Point::new(coordinate(0, x), coordinate(1, y), coordinate(2, z))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::Bounds;
+    use crate::light::PointLight;
+    use crate::material::Material;
+    use crate::probes::ProbeGrid;
+    use crate::shape::Sphere;
+    use crate::tuple::{Color, Point};
+    use crate::world::World;
+    use pretty_assertions::assert_eq;
+
+    fn grid(irradiance: Vec<Color>) -> ProbeGrid {
+        ProbeGrid {
+            bounds: Bounds::new(Point::zero(), Point::new(2., 2., 2.)),
+            resolution: 2,
+            irradiance,
+        }
+    }
+
+    #[test]
+    pub fn irradiance_is_interpolated_between_probes() {
+        let mut irradiance = vec![Color::black(); 8];
+        irradiance[1] = Color::white();
+        irradiance[3] = Color::white();
+        irradiance[5] = Color::white();
+        irradiance[7] = Color::white();
+        let grid = grid(irradiance);
+        assert_eq!(
+            grid.irradiance_at(&Point::new(0.5, 1., 1.)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+        assert_eq!(grid.irradiance_at(&Point::new(5., -1., 1.)), Color::white());
+        assert_eq!(grid.irradiance_at(&Point::new(-5., 1., 1.)), Color::black());
+    }
+
+    #[test]
+    pub fn probes_gather_light_from_surrounding_surfaces() {
+        let room = Sphere::new(Point::zero(), 10.);
+        room.common.material = Material {
+            color: Color::new(0.2, 0.4, 0.6),
+            ambient: 1.,
+            diffuse: 0.,
+            specular: 0.,
+            ..Default::default()
+        };
+        let w = World::new(PointLight::new(Point::zero(), Color::white()), vec![room]);
+        let grid = ProbeGrid::bake(&w, Bounds::unit_cube(), 2, 8);
+        assert_eq!(
+            grid.irradiance_at(&Point::zero()),
+            Color::new(0.2, 0.4, 0.6)
+        );
+    }
+
+    #[test]
+    pub fn baking_is_repeatable() {
+        let w = World::default();
+        let bounds = Bounds::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.));
+        assert_eq!(
+            ProbeGrid::bake(&w, bounds, 2, 16),
+            ProbeGrid::bake(&w, bounds, 2, 16)
+        );
+    }
+}
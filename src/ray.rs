@@ -1,28 +1,120 @@
 use crate::matrix;
 
-use crate::tuple::{Point, Vector};
-use derive_more::Constructor;
+use crate::tuple::{Point, Vector, EPSILON};
 
-#[derive(Debug, Constructor, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    pub differential: Option<RayDifferential>,
+}
+
+// Auxiliary rays offset by one pixel in x and y, used to estimate how much surface a single
+// camera sample covers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RayDifferential {
+    pub rx_origin: Point,
+    pub rx_direction: Vector,
+    pub ry_origin: Point,
+    pub ry_direction: Vector,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Footprint {
+    pub dpdx: Vector,
+    pub dpdy: Vector,
 }
 
 impl Ray {
+    pub const fn new(origin: Point, direction: Vector) -> Self {
+        Self {
+            origin,
+            direction,
+            differential: None,
+        }
+    }
+
+    pub const fn with_differential(mut self, differential: RayDifferential) -> Self {
+        self.differential = Some(differential);
+        self
+    }
+
     pub fn position(&self, t: f32) -> Point {
         self.origin + self.direction * t
     }
 
     pub fn transform(self, matrix: &matrix::Matrix4) -> Self {
-        Self::new(matrix * self.origin, matrix * self.direction)
+        Self {
+            origin: matrix * self.origin,
+            direction: matrix * self.direction,
+            differential: self.differential.map(|d| RayDifferential {
+                rx_origin: matrix * d.rx_origin,
+                rx_direction: matrix * d.rx_direction,
+                ry_origin: matrix * d.ry_origin,
+                ry_direction: matrix * d.ry_direction,
+            }),
+        }
+    }
+}
+
+impl RayDifferential {
+    pub fn footprint(&self, point: &Point, normal: &Vector) -> Option<Footprint> {
+        let on_tangent_plane = |origin: Point, direction: Vector| {
+            let denominator = normal.dot(&direction);
+            if denominator.abs() < EPSILON {
+                return None;
+            }
+            let t = normal.dot(&(*point - origin)) / denominator;
+            Some(origin + direction * t - *point)
+        };
+
+        Some(Footprint {
+            dpdx: on_tangent_plane(self.rx_origin, self.rx_direction)?,
+            dpdy: on_tangent_plane(self.ry_origin, self.ry_direction)?,
+        })
+    }
+
+    // Normal derivatives are ignored, so curved mirrors get a slightly too narrow footprint.
+    pub fn reflect(&self, point: &Point, normal: &Vector, footprint: &Footprint) -> Self {
+        Self {
+            rx_origin: *point + footprint.dpdx,
+            rx_direction: self.rx_direction.reflect(normal),
+            ry_origin: *point + footprint.dpdy,
+            ry_direction: self.ry_direction.reflect(normal),
+        }
+    }
+
+    pub fn refract(
+        &self,
+        point: &Point,
+        normal: &Vector,
+        footprint: &Footprint,
+        n_ratio: f32,
+    ) -> Option<Self> {
+        Some(Self {
+            rx_origin: *point + footprint.dpdx,
+            rx_direction: refract(&-self.rx_direction.normalize(), normal, n_ratio)?,
+            ry_origin: *point + footprint.dpdy,
+            ry_direction: refract(&-self.ry_direction.normalize(), normal, n_ratio)?,
+        })
     }
 }
 
+pub fn refract(eye: &Vector, normal: &Vector, n_ratio: f32) -> Option<Vector> {
+    let cos_i = eye.dot(normal);
+    let sin2_t = n_ratio.powi(2) * cos_i.mul_add(-cos_i, 1.0);
+    if sin2_t > 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(*normal * n_ratio.mul_add(cos_i, -cos_t) - *eye * n_ratio)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::matrix::Matrix4;
-    use crate::ray::Ray;
+    use crate::ray::{refract, Ray, RayDifferential};
     use crate::tuple::{Point, Vector};
     use pretty_assertions::assert_eq;
 
@@ -64,4 +156,56 @@ mod tests {
         assert_eq!(r2.origin, Point::new(2., 6., 12.));
         assert_eq!(r2.direction, Vector::new(0., 3., 0.));
     }
+
+    #[test]
+    pub fn footprint_of_differential_on_facing_plane() {
+        let d = RayDifferential {
+            rx_origin: Point::zero(),
+            rx_direction: Vector::new(0.1, 0., 1.),
+            ry_origin: Point::zero(),
+            ry_direction: Vector::new(0., 0.1, 1.),
+        };
+        let f = d
+            .footprint(&Point::new(0., 0., 5.), &Vector::new(0., 0., -1.))
+            .unwrap();
+        assert_eq!(f.dpdx, Vector::new(0.5, 0., 0.));
+        assert_eq!(f.dpdy, Vector::new(0., 0.5, 0.));
+    }
+
+    #[test]
+    pub fn footprint_grows_at_grazing_angles() {
+        let d = RayDifferential {
+            rx_origin: Point::zero(),
+            rx_direction: Vector::new(0.01, -0.1, 1.),
+            ry_origin: Point::zero(),
+            ry_direction: Vector::new(0., -0.11, 1.),
+        };
+        let p = Point::new(0., -1., 10.);
+        let f = d.footprint(&p, &Vector::new(0., 1., 0.)).unwrap();
+        assert!(f.dpdy.magnitude() > 0.5);
+        assert_eq!(f.dpdy.y, 0.);
+    }
+
+    #[test]
+    pub fn transforming_ray_transforms_differential() {
+        let d = RayDifferential {
+            rx_origin: Point::zero(),
+            rx_direction: Vector::new(1., 0., 0.),
+            ry_origin: Point::zero(),
+            ry_direction: Vector::new(0., 1., 0.),
+        };
+        let r = Ray::new(Point::zero(), Vector::new(0., 0., 1.)).with_differential(d);
+        let t = r.transform(&Matrix4::identity().translate(&Vector::new(1., 2., 3.)));
+        let td = t.differential.unwrap();
+        assert_eq!(td.rx_origin, Point::new(1., 2., 3.));
+        assert_eq!(td.ry_direction, Vector::new(0., 1., 0.));
+    }
+
+    #[test]
+    pub fn total_internal_reflection_has_no_refracted_direction() {
+        let eye = Vector::new(0.9, 0.43589, 0.);
+        let n = Vector::new(0., 1., 0.);
+        assert_eq!(refract(&eye, &n, 1.5), None);
+        assert_eq!(refract(&n, &n, 1.), Some(-n));
+    }
 }
@@ -0,0 +1,148 @@
+use std::cell::{Cell, RefCell};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::sampling::cmj;
+
+// Every random decision during rendering draws from this per-thread generator. It is reseeded
+// from the render seed and pixel coordinates before each pixel, so a pixel's samples do not
+// depend on which thread renders it or in what order.
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(0));
+    static PIXEL: Cell<u64> = const { Cell::new(0) };
+    static STRATUM: Cell<Option<Stratum>> = const { Cell::new(None) };
+}
+
+// How the pixel position and lens position of each camera sample are chosen.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SamplePattern {
+    #[default]
+    Independent,
+    // Correlated multi-jittered samples, scrambled per pixel.
+    MultiJittered,
+}
+
+// How the noise of an animation changes from frame to frame. `Static` seeds every frame the same
+// way, so the noise stays put and only moving content changes; `Animated` decorrelates it, so it
+// averages out across frames instead of sticking to the screen.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum FrameNoise {
+    #[default]
+    Static,
+    Animated,
+}
+
+// The seed that the pixels of `frame` are seeded from.
+pub const fn frame_seed(seed: u64, frame: u64, noise: FrameNoise) -> u64 {
+    match noise {
+        FrameNoise::Static => seed,
+        FrameNoise::Animated => seed ^ splitmix(frame),
+    }
+}
+
+// The 2D sample domains a camera sample draws from. Each gets its own scrambling, so the
+// pixel and lens positions of a sample aren't correlated.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Dimension {
+    Pixel = 1,
+    Lens = 2,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Stratum {
+    index: u32,
+    count: u32,
+}
+
+pub fn seed_pixel(seed: u64, x: usize, y: usize) {
+    let pixel = ((y as u64) << 32) | x as u64;
+    let state = splitmix(seed ^ splitmix(pixel));
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(state));
+    PIXEL.set(state);
+    STRATUM.set(None);
+}
+
+// Marks the start of sample `index` of `count` for the current pixel.
+pub fn start_sample(pattern: SamplePattern, index: usize, count: usize) {
+    STRATUM.set(match pattern {
+        SamplePattern::Independent => None,
+        SamplePattern::MultiJittered => Some(Stratum {
+            index: index as u32,
+            count: count as u32,
+        }),
+    });
+}
+
+// The current sample's stratified position in `dimension`, or `None` when samples are drawn
+// independently.
+pub fn stratified_2d(dimension: Dimension) -> Option<(f32, f32)> {
+    let stratum = STRATUM.get()?;
+    let pattern = splitmix(PIXEL.get() ^ dimension as u64) as u32;
+    Some(cmj(stratum.index, stratum.count, pattern))
+}
+
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+pub(crate) const fn splitmix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sampler::{
+        frame_seed, seed_pixel, start_sample, stratified_2d, with_rng, Dimension, FrameNoise,
+        SamplePattern,
+    };
+    use pretty_assertions::{assert_eq, assert_ne};
+    use rand::Rng;
+
+    #[test]
+    pub fn reseeding_a_pixel_repeats_its_sequence() {
+        seed_pixel(7, 3, 4);
+        let first = with_rng(|rng| rng.gen::<u64>());
+        seed_pixel(7, 4, 3);
+        let other = with_rng(|rng| rng.gen::<u64>());
+        seed_pixel(7, 3, 4);
+        assert_eq!(with_rng(|rng| rng.gen::<u64>()), first);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    pub fn animated_noise_differs_between_frames() {
+        assert_eq!(
+            frame_seed(7, 1, FrameNoise::Static),
+            frame_seed(7, 2, FrameNoise::Static)
+        );
+        let first = frame_seed(7, 1, FrameNoise::Animated);
+        assert_ne!(first, frame_seed(7, 2, FrameNoise::Animated));
+
+        seed_pixel(first, 3, 4);
+        let sample = with_rng(|rng| rng.gen::<u64>());
+        seed_pixel(frame_seed(7, 2, FrameNoise::Animated), 3, 4);
+        assert_ne!(with_rng(|rng| rng.gen::<u64>()), sample);
+    }
+
+    #[test]
+    pub fn multi_jittered_samples_are_scrambled_per_pixel_and_dimension() {
+        seed_pixel(7, 3, 4);
+        start_sample(SamplePattern::Independent, 0, 4);
+        assert_eq!(stratified_2d(Dimension::Pixel), None);
+
+        start_sample(SamplePattern::MultiJittered, 0, 4);
+        let pixel = stratified_2d(Dimension::Pixel).unwrap();
+        assert_ne!(stratified_2d(Dimension::Lens).unwrap(), pixel);
+        seed_pixel(7, 4, 3);
+        start_sample(SamplePattern::MultiJittered, 0, 4);
+        assert_ne!(stratified_2d(Dimension::Pixel).unwrap(), pixel);
+        seed_pixel(7, 3, 4);
+        assert_eq!(stratified_2d(Dimension::Pixel), None);
+        start_sample(SamplePattern::MultiJittered, 0, 4);
+        assert_eq!(stratified_2d(Dimension::Pixel).unwrap(), pixel);
+    }
+}
@@ -0,0 +1,223 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use crate::tuple::Vector;
+
+// An orthonormal basis around a surface normal, for turning directions sampled around the z axis
+// into directions around the normal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Onb {
+    pub u: Vector,
+    pub v: Vector,
+    pub w: Vector,
+}
+
+impl Onb {
+    // Builds the tangents without branching on which axis the normal is closest to (Duff et al.,
+    // "Building an Orthonormal Basis, Revisited"), so nearby normals get nearby tangents.
+    pub fn from_normal(normal: &Vector) -> Self {
+        let w = normal.normalize();
+        let sign = 1f32.copysign(w.z);
+        let a = -1. / (sign + w.z);
+        let b = w.x * w.y * a;
+        Self {
+            u: Vector::new((sign * w.x * w.x).mul_add(a, 1.), sign * b, -sign * w.x),
+            v: Vector::new(b, (w.y * w.y).mul_add(a, sign), -w.y),
+            w,
+        }
+    }
+
+    pub fn to_world(&self, local: &Vector) -> Vector {
+        self.u * local.x + self.v * local.y + self.w * local.z
+    }
+
+    pub fn to_local(&self, world: &Vector) -> Vector {
+        Vector::new(world.dot(&self.u), world.dot(&self.v), world.dot(&self.w))
+    }
+}
+
+// The samplers below map two uniform numbers in [0, 1) to a direction or point. Directions are
+// around the z axis; `Onb::to_world` turns them around a normal.
+
+pub fn uniform_sphere(u: f32, v: f32) -> Vector {
+    let z = 1. - 2. * u;
+    let r = (1. - z * z).max(0.).sqrt();
+    let phi = 2. * PI * v;
+    Vector::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn uniform_hemisphere(u: f32, v: f32) -> Vector {
+    let z = u;
+    let r = (1. - z * z).max(0.).sqrt();
+    let phi = 2. * PI * v;
+    Vector::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Directions with density proportional to their cosine with z, which cancels the cosine term of
+// diffuse lighting.
+pub fn cosine_hemisphere(u: f32, v: f32) -> Vector {
+    let (x, y) = concentric_disk(u, v);
+    Vector::new(x, y, (1. - x * x - y * y).max(0.).sqrt())
+}
+
+pub fn cosine_hemisphere_pdf(cos_theta: f32) -> f32 {
+    cos_theta.max(0.) / PI
+}
+
+// A point on the unit disk. Shirley's concentric mapping keeps strata of the unit square
+// compact on the disk, unlike the polar mapping.
+pub fn concentric_disk(u: f32, v: f32) -> (f32, f32) {
+    let (a, b) = (2. * u - 1., 2. * v - 1.);
+    if a == 0. && b == 0. {
+        return (0., 0.);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+// Sample `index` of `count` from Kensler's correlated multi-jittered pattern ("Correlated
+// Multi-Jittered Sampling", 2013). The samples are stratified both on a grid and along each
+// axis, and `pattern` scrambles them into an independent set with the same properties, so
+// neighbouring pixels don't share the structure that shows up as banding at low sample counts.
+pub fn cmj(index: u32, count: u32, pattern: u32) -> (f32, f32) {
+    let count = count.max(1);
+    let m = ((count as f32).sqrt() as u32).max(1);
+    let n = count.div_ceil(m);
+    let s = permute(index % count, count, pattern.wrapping_mul(0x5163_3e2d));
+    let sx = permute(s % m, m, pattern.wrapping_mul(0x68bc_21eb));
+    let sy = permute(s / m, n, pattern.wrapping_mul(0x02e5_be93));
+    let jx = hash_to_float(s, pattern.wrapping_mul(0x967a_889b));
+    let jy = hash_to_float(s, pattern.wrapping_mul(0x368c_c8b7));
+    (
+        ((s % m) as f32 + (sy as f32 + jx) / n as f32) / m as f32,
+        ((s / m) as f32 + (sx as f32 + jy) / m as f32) / n as f32,
+    )
+}
+
+// A pseudorandom permutation of 0..len chosen by `pattern`, evaluated for one element.
+fn permute(mut i: u32, len: u32, pattern: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= pattern;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= pattern >> 16;
+        i ^= (i & w) >> 4;
+        i ^= pattern >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= pattern >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | pattern >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    (i.wrapping_add(pattern)) % len
+}
+
+fn hash_to_float(mut i: u32, pattern: u32) -> f32 {
+    i ^= pattern;
+    i ^= i >> 17;
+    i ^= i >> 10;
+    i = i.wrapping_mul(0xb365_34e5);
+    i ^= i >> 12;
+    i ^= i >> 21;
+    i = i.wrapping_mul(0x93fc_4795);
+    i ^= 0xdf6e_307f;
+    i ^= i >> 17;
+    i = i.wrapping_mul(1 | pattern >> 18);
+    // Scaled just short of 1 so the largest value doesn't round up to it.
+    (i as f64 / 4_294_967_808.) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sampling::{
+        cmj, concentric_disk, cosine_hemisphere, uniform_hemisphere, uniform_sphere, Onb,
+    };
+    use crate::tuple::Vector;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(Vector::new(0., 0., 1.) ; "z")]
+    #[test_case(Vector::new(0., 0., -1.) ; "negative z")]
+    #[test_case(Vector::new(1., 2., -3.) ; "oblique")]
+    pub fn basis_is_orthonormal_around_the_normal(normal: Vector) {
+        let onb = Onb::from_normal(&normal);
+        assert_eq!(onb.w, normal.normalize());
+        for (a, b) in [(onb.u, onb.v), (onb.v, onb.w), (onb.w, onb.u)] {
+            assert!(a.dot(&b).abs() < 1e-5);
+            assert!((a.magnitude() - 1.).abs() < 1e-5);
+        }
+        let d = Vector::new(0.3, -0.4, 0.5);
+        let round_trip = onb.to_local(&onb.to_world(&d));
+        assert!((round_trip - d).magnitude() < 1e-5);
+        assert!((onb.to_world(&Vector::new(0., 0., 1.)) - onb.w).magnitude() < 1e-6);
+    }
+
+    #[test]
+    pub fn samples_land_where_they_should() {
+        for i in 0..16 {
+            for j in 0..16 {
+                let (u, v) = (i as f32 / 16., j as f32 / 16.);
+                assert!((uniform_sphere(u, v).magnitude() - 1.).abs() < 1e-5);
+                let h = uniform_hemisphere(u, v);
+                assert!(h.z >= 0. && (h.magnitude() - 1.).abs() < 1e-5);
+                let c = cosine_hemisphere(u, v);
+                assert!(c.z >= 0. && (c.magnitude() - 1.).abs() < 1e-5);
+                let (x, y) = concentric_disk(u, v);
+                assert!(x.hypot(y) <= 1. + 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    pub fn cosine_samples_average_two_thirds_up() {
+        // E[cos θ] under a cosine-weighted hemisphere is 2/3.
+        let n = 64;
+        let mean = (0..n * n)
+            .map(|i| {
+                let (u, v) = ((i % n) as f32 + 0.5, (i / n) as f32 + 0.5);
+                cosine_hemisphere(u / n as f32, v / n as f32).z
+            })
+            .sum::<f32>()
+            / (n * n) as f32;
+        assert!((mean - 2. / 3.).abs() < 2e-3, "{mean}");
+    }
+
+    #[test_case(16, 0 ; "square count")]
+    #[test_case(16, 0xdead_beef ; "scrambled")]
+    #[test_case(12, 7 ; "non-square count")]
+    pub fn multi_jittered_samples_fill_every_stratum(count: u32, pattern: u32) {
+        let samples = (0..count)
+            .map(|i| cmj(i, count, pattern))
+            .collect::<Vec<_>>();
+        for axis in [0, 1] {
+            let mut strata = samples
+                .iter()
+                .map(|&(x, y)| ([x, y][axis] * count as f32) as u32)
+                .collect::<Vec<_>>();
+            strata.sort_unstable();
+            assert_eq!(strata, (0..count).collect::<Vec<_>>());
+        }
+        assert!(samples
+            .iter()
+            .all(|&(x, y)| (0. ..1.).contains(&x) && (0. ..1.).contains(&y)));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::Result;
+use crate::matrix::Matrix4;
+use crate::shape::Shape;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+struct Node {
+    name: String,
+    transform: Matrix4,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    shapes: Vec<(Matrix4, &'static mut dyn Shape)>,
+}
+
+// Nodes carry transforms relative to their parent. Shapes attached to a node keep the transform
+// they had when attached as their placement relative to that node; `build` bakes the full chain
+// into each shape.
+pub struct SceneGraph {
+    nodes: Vec<Node>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: "root".to_string(),
+                transform: Matrix4::identity(),
+                parent: None,
+                children: vec![],
+                shapes: vec![],
+            }],
+        }
+    }
+
+    pub const fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn add_node(
+        &mut self,
+        parent: NodeId,
+        name: impl Into<String>,
+        transform: Matrix4,
+    ) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            name: name.into(),
+            transform,
+            parent: Some(parent),
+            children: vec![],
+            shapes: vec![],
+        });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name).map(NodeId)
+    }
+
+    pub fn name(&self, node: NodeId) -> &str {
+        &self.nodes[node.0].name
+    }
+
+    pub fn children(&self, node: NodeId) -> &[NodeId] {
+        &self.nodes[node.0].children
+    }
+
+    pub fn transform(&self, node: NodeId) -> &Matrix4 {
+        &self.nodes[node.0].transform
+    }
+
+    pub fn set_transform(&mut self, node: NodeId, transform: Matrix4) {
+        self.nodes[node.0].transform = transform;
+    }
+
+    pub fn world_transform(&self, node: NodeId) -> Matrix4 {
+        let node = &self.nodes[node.0];
+        match node.parent {
+            Some(parent) => self.world_transform(parent) * node.transform,
+            None => node.transform,
+        }
+    }
+
+    pub fn attach(&mut self, node: NodeId, shape: &'static mut dyn Shape) {
+        let local = *shape.get_transform();
+        self.nodes[node.0].shapes.push((local, shape));
+    }
+
+    pub fn build(self) -> Result<Vec<&'static dyn Shape>> {
+        let mut world_transforms = vec![Matrix4::identity(); self.nodes.len()];
+        let mut stack = vec![(self.root(), Matrix4::identity())];
+        while let Some((id, parent_transform)) = stack.pop() {
+            let node = &self.nodes[id.0];
+            let world = parent_transform * node.transform;
+            world_transforms[id.0] = world;
+            stack.extend(node.children.iter().rev().map(|&child| (child, world)));
+        }
+
+        let mut objects = vec![];
+        for (node, world) in self.nodes.into_iter().zip(world_transforms) {
+            for (local, shape) in node.shapes {
+                shape.replace_transform(world * local)?;
+                objects.push(&*shape);
+            }
+        }
+        Ok(objects)
+    }
+}
+
+impl Default for SceneGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::matrix::Matrix4;
+    use crate::scene_graph::SceneGraph;
+    use crate::shape::{Cube, Sphere};
+    use crate::tuple::Vector;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn nodes_are_found_by_name() {
+        let mut g = SceneGraph::new();
+        let table = g.add_node(g.root(), "table", Matrix4::identity());
+        let cup = g.add_node(table, "cup", Matrix4::identity());
+        assert_eq!(g.find("cup"), Some(cup));
+        assert_eq!(g.children(table), &[cup]);
+        assert_eq!(g.name(table), "table");
+        assert_eq!(g.find("chair"), None);
+    }
+
+    #[test]
+    pub fn world_transform_composes_parents() {
+        let mut g = SceneGraph::new();
+        let table = g.add_node(
+            g.root(),
+            "table",
+            Matrix4::identity().translate(&Vector::new(5., 0., 0.)),
+        );
+        let cup = g.add_node(
+            table,
+            "cup",
+            Matrix4::identity().translate(&Vector::new(0., 1., 0.)),
+        );
+        assert_eq!(
+            g.world_transform(cup),
+            Matrix4::identity().translate(&Vector::new(5., 1., 0.))
+        );
+    }
+
+    #[test]
+    pub fn moving_a_node_moves_attached_shapes() {
+        let mut g = SceneGraph::new();
+        let table = g.add_node(g.root(), "table", Matrix4::identity());
+        let top = Cube::static_default();
+        top.set_transform(Matrix4::identity().scale(&Vector::new(2., 0.1, 1.)));
+        g.attach(table, top);
+        let cup = Sphere::static_default()
+            .set_transform(&Matrix4::identity().translate(&Vector::new(0., 1., 0.)));
+        g.attach(table, cup);
+
+        g.set_transform(
+            table,
+            Matrix4::identity().translate(&Vector::new(0., 3., 0.)),
+        );
+        let objects = g.build().unwrap();
+
+        assert_eq!(objects.len(), 2);
+        assert_eq!(
+            *objects[1].get_transform(),
+            Matrix4::identity().translate(&Vector::new(0., 4., 0.))
+        );
+        assert_eq!(
+            *objects[0].get_transform(),
+            Matrix4::identity()
+                .scale(&Vector::new(2., 0.1, 1.))
+                .translate(&Vector::new(0., 3., 0.))
+        );
+    }
+
+    #[test]
+    pub fn collapsed_node_is_reported_as_singular() {
+        let mut g = SceneGraph::new();
+        let flat = g.add_node(
+            g.root(),
+            "flat",
+            Matrix4::identity().scale(&Vector::new(1., 0., 1.)),
+        );
+        let s = Sphere::static_default();
+        let id = s.common.id;
+        g.attach(flat, s);
+        assert!(matches!(g.build(), Err(Error::SingularTransform(e)) if e == id));
+    }
+}
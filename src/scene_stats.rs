@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::bounds::Bounds;
+use crate::shape::ShapeType;
+use crate::world::World;
+
+// A summary of what a world contains, for checking a scene before committing to a render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneStats {
+    // Object counts per shape type, most common first.
+    pub objects: Vec<(ShapeType, usize)>,
+    // Bounds of every finite object; unbounded objects such as planes are only counted.
+    pub bounds: Bounds,
+    pub unbounded: usize,
+    pub patterned: usize,
+    pub reflective: usize,
+    pub transparent: usize,
+    pub lights: usize,
+    pub light_groups: usize,
+    // Estimated size of the acceleration structure in bytes, if one has been built.
+    pub acceleration_bytes: Option<usize>,
+}
+
+impl SceneStats {
+    pub fn object_count(&self) -> usize {
+        self.objects.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn count_of(&self, shape_type: ShapeType) -> usize {
+        self.objects
+            .iter()
+            .find(|(t, _)| *t == shape_type)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+impl fmt::Display for SceneStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "objects: {}", self.object_count())?;
+        for (shape_type, count) in &self.objects {
+            writeln!(f, "  {shape_type}: {count}")?;
+        }
+        writeln!(f, "triangles: {}", self.count_of(ShapeType::Triangle))?;
+        if self.bounds.is_empty() {
+            writeln!(f, "bounds: none")?;
+        } else {
+            let (min, max) = (self.bounds.min, self.bounds.max);
+            writeln!(
+                f,
+                "bounds: ({:.3}, {:.3}, {:.3}) to ({:.3}, {:.3}, {:.3})",
+                min.x, min.y, min.z, max.x, max.y, max.z
+            )?;
+        }
+        writeln!(f, "unbounded objects: {}", self.unbounded)?;
+        writeln!(
+            f,
+            "materials: {} patterned, {} reflective, {} transparent",
+            self.patterned, self.reflective, self.transparent
+        )?;
+        writeln!(f, "lights: {} in {} groups", self.lights, self.light_groups)?;
+        match self.acceleration_bytes {
+            Some(bytes) => write!(f, "acceleration: {:.1} KiB", bytes as f64 / 1024.),
+            None => write!(f, "acceleration: none"),
+        }
+    }
+}
+
+impl World {
+    pub fn scene_stats(&self) -> SceneStats {
+        let mut counts: HashMap<ShapeType, usize> = HashMap::new();
+        let mut bounds = Bounds::empty();
+        let mut unbounded = 0;
+        for object in &self.objects {
+            *counts.entry(object.shape_type()).or_default() += 1;
+            let object_bounds = object.get_bounds();
+            if object_bounds.is_finite() {
+                bounds = bounds.merge(&object_bounds);
+            } else {
+                unbounded += 1;
+            }
+        }
+        let mut objects: Vec<_> = counts.into_iter().collect();
+        objects.sort_by(|(a, m), (b, n)| n.cmp(m).then_with(|| a.to_string().cmp(&b.to_string())));
+
+        let materials = || self.objects.iter().map(|o| o.get_material());
+        SceneStats {
+            objects,
+            bounds,
+            unbounded,
+            patterned: materials().filter(|m| m.pattern.is_some()).count(),
+            reflective: materials().filter(|m| m.reflective > 0.).count(),
+            transparent: materials().filter(|m| m.transparency > 0.).count(),
+            lights: self.light_groups.iter().map(|g| g.lights.len()).sum(),
+            light_groups: self.light_groups.len(),
+            acceleration_bytes: self.acceleration().map(|tree| tree.memory_estimate()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::accel::Accel;
+    use crate::bounds::Bounds;
+    use crate::material::Material;
+    use crate::matrix::Matrix4;
+    use crate::shape::{Plane, Shape, ShapeType, Sphere, Triangle};
+    use crate::tuple::{Point, Vector};
+    use crate::world::World;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn objects_are_counted_by_type() {
+        let mut w = World::default();
+        let triangle = Triangle::new(
+            Point::new(0., 0., 0.),
+            Point::new(1., 0., 0.),
+            Point::new(0., 3., 0.),
+        );
+        let mirror: &dyn Shape = Sphere::default_with_material(Material {
+            reflective: 1.,
+            ..Default::default()
+        })
+        .set_transform(&Matrix4::identity().translate(&Vector::new(0., 0., -4.)));
+        w.objects
+            .extend([triangle, mirror, Plane::static_default()]);
+
+        let stats = w.scene_stats();
+        assert_eq!(
+            stats.objects,
+            vec![
+                (ShapeType::Sphere, 3),
+                (ShapeType::Plane, 1),
+                (ShapeType::Triangle, 1)
+            ]
+        );
+        assert_eq!(stats.count_of(ShapeType::Triangle), 1);
+        assert_eq!(
+            stats.bounds,
+            Bounds::new(Point::new(-1., -1., -5.), Point::new(1., 3., 1.))
+        );
+        assert_eq!(stats.unbounded, 1);
+        assert_eq!(stats.reflective, 1);
+        assert_eq!(stats.lights, 1);
+        assert_eq!(stats.acceleration_bytes, None);
+    }
+
+    #[test]
+    pub fn built_acceleration_is_sized() {
+        let mut w = World::default();
+        w.settings.accel = Accel::KdTree;
+        w.build_acceleration();
+        let stats = w.scene_stats();
+        assert!(stats.acceleration_bytes.unwrap() > 0);
+        assert!(stats.to_string().contains("acceleration: "));
+    }
+}
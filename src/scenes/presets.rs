@@ -0,0 +1,304 @@
+use std::f32::consts::PI;
+use std::path::Path;
+
+use crate::bounds::Bounds;
+use crate::camera::Camera;
+use crate::error::Result;
+use crate::light::{PointLight, DEFAULT_LIGHT_GROUP};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::obj::Obj;
+use crate::pattern::Checkers;
+use crate::shape::{Cube, Plane, Shape, Sphere};
+use crate::tuple::{Color, Point, Vector};
+use crate::world::World;
+
+// Canonical scenes shared by examples, benchmarks and tests. Cameras are square at
+// `PRESET_RESOLUTION`; build a new camera with the same transform for other sizes.
+pub const PRESET_RESOLUTION: usize = 400;
+
+const CORNELL_RED: Color = Color {
+    r: 0.63,
+    g: 0.065,
+    b: 0.05,
+};
+const CORNELL_GREEN: Color = Color {
+    r: 0.14,
+    g: 0.45,
+    b: 0.091,
+};
+const CORNELL_WHITE: Color = Color {
+    r: 0.725,
+    g: 0.71,
+    b: 0.68,
+};
+
+// Point lights spread over each side of a Cornell box ceiling light. Together they stand in for
+// an area light and give the boxes soft shadows.
+const CORNELL_LIGHT_SAMPLES: usize = 4;
+
+// The Cornell box: a white room `size` units across with a red wall on the left, a green wall on
+// the right and a tall and a short box on the floor. A square light `light_size` across hangs
+// under the middle of the ceiling. The front is open towards the camera.
+pub fn cornell_box(size: f32, light_size: f32) -> (World, Camera) {
+    let half = size / 2.;
+    let wall = |color: Color| Material {
+        color,
+        specular: 0.,
+        ..Default::default()
+    };
+    let slab = |material: Material, centre: Vector, extent: Vector| -> &'static dyn Shape {
+        let c = Cube::default_with_material(material);
+        c.set_transform(Matrix4::identity().scale(&extent).translate(&centre));
+        c
+    };
+
+    let thin = 0.01 * size;
+    let objects = vec![
+        slab(
+            wall(CORNELL_WHITE),
+            Vector::new(0., -thin, 0.),
+            Vector::new(half, thin, half),
+        ),
+        slab(
+            wall(CORNELL_WHITE),
+            Vector::new(0., size + thin, 0.),
+            Vector::new(half, thin, half),
+        ),
+        slab(
+            wall(CORNELL_WHITE),
+            Vector::new(0., half, half + thin),
+            Vector::new(half, half, thin),
+        ),
+        slab(
+            wall(CORNELL_RED),
+            Vector::new(-half - thin, half, 0.),
+            Vector::new(thin, half, half),
+        ),
+        slab(
+            wall(CORNELL_GREEN),
+            Vector::new(half + thin, half, 0.),
+            Vector::new(thin, half, half),
+        ),
+        // The glowing panel of the light, set into the ceiling so it casts no shadow.
+        slab(
+            Material {
+                ambient: 1.,
+                diffuse: 0.,
+                specular: 0.,
+                ..Default::default()
+            },
+            Vector::new(0., size + thin * 0.5, 0.),
+            Vector::new(light_size / 2., thin, light_size / 2.),
+        ),
+        cornell_block(0.6 * size, 0.3, Vector::new(-0.17, 0., 0.15) * size, size),
+        cornell_block(0.3 * size, -0.3, Vector::new(0.17, 0., -0.17) * size, size),
+    ];
+
+    let n = CORNELL_LIGHT_SAMPLES;
+    let intensity = Color::white() * (1. / (n * n) as f32);
+    let mut lights = (0..n * n).map(|i| {
+        let offset = |k: usize| ((k as f32 + 0.5) / n as f32 - 0.5) * light_size;
+        let position = Point::new(offset(i % n), size - thin, offset(i / n));
+        PointLight::new(position, intensity)
+    });
+    let mut world = World::new(lights.next().unwrap(), objects);
+    for light in lights {
+        world.add_light(DEFAULT_LIGHT_GROUP, light);
+    }
+    (world, cornell_camera(size))
+}
+
+// A glass sphere resting on a green and white checkerboard, the classic refraction test.
+pub fn glass_sphere_on_checkerboard() -> (World, Camera) {
+    let floor = Plane::default_with_material(Material {
+        pattern: Some(Checkers::new(
+            Color::new(0.15, 0.35, 0.15),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+        specular: 0.,
+        ..Default::default()
+    });
+    let glass = Sphere::default_with_material(Material {
+        color: Color::black(),
+        ambient: 0.,
+        diffuse: 0.1,
+        specular: 1.,
+        shininess: 300.,
+        reflective: 0.9,
+        transparency: 0.9,
+        refractive_index: 1.5,
+        ..Default::default()
+    })
+    .set_transform(&Matrix4::identity().translate(&Vector::new(0., 1., 0.)));
+
+    let light = PointLight::new(Point::new(-10., 10., -10.), Color::white());
+    let mut camera = preset_camera(PI / 3.);
+    camera.set_transform(
+        Point::new(0., 2.5, -5.),
+        Point::new(0., 0.8, 0.),
+        Vector::new(0., 1., 0.),
+    );
+    (World::new(light, vec![floor, glass]), camera)
+}
+
+// Two facing mirrors with a red sphere between them, so every reflection bounces until the depth
+// limit runs out. Useful for checking reflection limits and their cost.
+pub fn mirror_room() -> (World, Camera) {
+    let mirror = || Material {
+        color: Color::new(0.05, 0.05, 0.05),
+        diffuse: 0.1,
+        reflective: 0.95,
+        ..Default::default()
+    };
+    let floor = Plane::default_with_material(Material {
+        pattern: Some(Checkers::new(
+            Color::new(0.3, 0.3, 0.3),
+            Color::new(0.8, 0.8, 0.8),
+        )),
+        specular: 0.,
+        ..Default::default()
+    });
+    let left = Plane::default_with_material(mirror()).set_transform(
+        Matrix4::identity()
+            .rotate_z(PI / 2.)
+            .translate(&Vector::new(-3., 0., 0.)),
+    );
+    let right = Plane::default_with_material(mirror()).set_transform(
+        Matrix4::identity()
+            .rotate_z(PI / 2.)
+            .translate(&Vector::new(3., 0., 0.)),
+    );
+    let ball = Sphere::default_with_material(Material {
+        color: Color::new(0.8, 0.1, 0.1),
+        ..Default::default()
+    })
+    .set_transform(&Matrix4::identity().translate(&Vector::new(0., 1., 0.)));
+
+    let light = PointLight::new(Point::new(0., 8., -6.), Color::white());
+    let mut camera = preset_camera(PI / 2.5);
+    camera.set_transform(
+        Point::new(-1., 2., -6.),
+        Point::new(0.5, 1., 0.),
+        Vector::new(0., 1., 0.),
+    );
+    (World::new(light, vec![floor, left, right, ball]), camera)
+}
+
+// Any OBJ model, such as the Utah teapot, scaled to two units tall and standing on a floor at the
+// origin.
+pub fn obj_model(path: &Path) -> Result<(World, Camera)> {
+    let mut obj = Obj::load(path)?;
+    let bounds = obj.vertices.iter().fold(Bounds::empty(), |mut bounds, v| {
+        bounds.add_point(v);
+        bounds
+    });
+    let height = (bounds.max.y - bounds.min.y).max(f32::EPSILON);
+    let scale = 2. / height;
+    let centre = (bounds.min + bounds.max) * 0.5;
+    let placement = Matrix4::identity()
+        .translate(&Vector::new(-centre.x, -bounds.min.y, -centre.z))
+        .scale(&Vector::new(scale, scale, scale));
+    for v in &mut obj.vertices {
+        *v = placement * *v;
+    }
+
+    let mut objects = obj.to_triangles(|| Material {
+        color: Color::new(0.9, 0.6, 0.3),
+        ..Default::default()
+    });
+    objects.push(Plane::default_with_material(Material {
+        pattern: Some(Checkers::new(
+            Color::new(0.4, 0.4, 0.4),
+            Color::new(0.8, 0.8, 0.8),
+        )),
+        specular: 0.,
+        ..Default::default()
+    }));
+
+    let light = PointLight::new(Point::new(-6., 8., -8.), Color::white());
+    let mut camera = preset_camera(PI / 3.);
+    camera.set_transform(
+        Point::new(0., 2.5, -5.),
+        Point::new(0., 1., 0.),
+        Vector::new(0., 1., 0.),
+    );
+    Ok((World::new(light, objects), camera))
+}
+
+fn preset_camera(fov: f32) -> Camera {
+    Camera::new(PRESET_RESOLUTION, PRESET_RESOLUTION, fov)
+}
+
+// Looks into the open front of a Cornell box of the given size with the usual 39.3° field of
+// view, close enough that the box fills the frame.
+fn cornell_camera(size: f32) -> Camera {
+    let mut camera = preset_camera(39.3f32.to_radians());
+    camera.set_transform(
+        Point::new(0., size / 2., -1.85 * size),
+        Point::new(0., size / 2., 0.),
+        Vector::new(0., 1., 0.),
+    );
+    camera
+}
+
+// A white block standing on the floor of the box, turned by `angle` around its own axis.
+fn cornell_block(height: f32, angle: f32, position: Vector, size: f32) -> &'static dyn Shape {
+    let side = 0.15 * size;
+    let block = Cube::default_with_material(Material {
+        color: CORNELL_WHITE,
+        specular: 0.,
+        ..Default::default()
+    });
+    block.set_transform(
+        Matrix4::identity()
+            .scale(&Vector::new(side, height / 2., side))
+            .rotate_y(angle)
+            .translate(&(position + Vector::new(0., height / 2., 0.))),
+    );
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scenes::presets;
+    use crate::shape::Intersection;
+    use crate::tuple::Color;
+    use crate::validate::Diagnostic;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn presets_are_valid_and_in_view() {
+        for (world, camera) in [
+            presets::cornell_box(5., 1.),
+            presets::glass_sphere_on_checkerboard(),
+            presets::mirror_room(),
+        ] {
+            assert_eq!(world.validate_view(&camera), Vec::<Diagnostic>::new());
+        }
+    }
+
+    #[test]
+    pub fn cornell_box_fills_the_frame() {
+        let (world, camera) = presets::cornell_box(5., 1.);
+        let last = camera.hsize - 1;
+        for (x, y) in [(0, 0), (last, 0), (0, last), (last, last)] {
+            let xs = world.intersect_world(&camera.pixel_center_ray(x, y));
+            assert!(Intersection::get_hit(&xs).is_some(), "({x}, {y})");
+        }
+    }
+
+    #[test]
+    pub fn cornell_light_is_spread_over_its_panel() {
+        let (world, _) = presets::cornell_box(10., 2.);
+        let lights = world.lights().collect::<Vec<_>>();
+        assert_eq!(lights.len(), 16);
+        let total = lights
+            .iter()
+            .fold(Color::black(), |acc, light| acc + light.intensity);
+        assert_eq!(total, Color::white());
+        assert!(lights
+            .iter()
+            .all(|l| l.position.x.abs() < 1. && l.position.z.abs() < 1. && l.position.y < 10.));
+    }
+}
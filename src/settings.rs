@@ -0,0 +1,177 @@
+use crate::accel::Accel;
+use crate::canvas::color_space::ColorSpace;
+use crate::canvas::dither::Dither;
+use crate::filter::Filter;
+use crate::postprocess::Effect;
+use crate::sampler::{self, FrameNoise, SamplePattern};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderSettings {
+    pub accel: Accel,
+    pub threads: usize,
+    pub pin_threads: bool,
+    pub translucent_shadows: bool,
+    pub shadow_transmittance: Transmittance,
+    // Total number of reflections and refractions a camera ray may spawn, defaulting to
+    // `MAX_REFLECTION_RECURSION_DEPTH`. The per-effect limits cap each kind of bounce within it,
+    // so deep glass doesn't also pay for deep mirror recursion.
+    pub max_depth: Option<i32>,
+    pub max_reflection_depth: Option<i32>,
+    pub max_refraction_depth: Option<i32>,
+    // Depth after which reflection and refraction paths are ended by Russian roulette instead
+    // of the fixed recursion limit.
+    pub russian_roulette: Option<i32>,
+    // Samples brighter than this are scaled down before they are averaged into a pixel.
+    pub max_radiance: Option<f32>,
+    pub reject_outliers: bool,
+    // Cell size, in world units, under which the antialiasing samples of a pixel reuse one
+    // another's shadow rays. `None` traces every shadow ray.
+    pub shadow_cache: Option<f32>,
+    pub filter: Filter,
+    pub seed: u64,
+    // Index of the frame being rendered in an animation, mixed into the seed by `frame_noise`.
+    pub frame: u64,
+    pub frame_noise: FrameNoise,
+    pub sample_pattern: SamplePattern,
+    pub irradiance_probes: Option<ProbeSettings>,
+    pub post_process: Vec<Effect>,
+    // Columns of a live ANSI-colour preview drawn to stderr as tiles finish. `None` only reports
+    // the number of tiles left.
+    pub term_preview: Option<usize>,
+}
+
+// How translucent shadows combine the occluders between a point and a light. `Stochastic` keeps
+// `samples` paths that each stop at the first occluder they are absorbed by, so long stacks of
+// glass cost roughly the same as a few panes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Transmittance {
+    #[default]
+    Exact,
+    Stochastic {
+        samples: usize,
+    },
+}
+
+// Probes baked by `World::bake_probes` replace the constant ambient term with interpolated
+// indirect light. `resolution` probes are placed along each axis of the scene's bounds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProbeSettings {
+    pub resolution: usize,
+    pub samples: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutputSettings {
+    pub dither: Dither,
+    pub jpeg_quality: u8,
+    pub color_space: ColorSpace,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            dither: Dither::None,
+            jpeg_quality: 90,
+            color_space: ColorSpace::default(),
+        }
+    }
+}
+
+impl RenderSettings {
+    // The seed for this frame's pixels.
+    pub const fn frame_seed(&self) -> u64 {
+        sampler::frame_seed(self.seed, self.frame, self.frame_noise)
+    }
+
+    pub fn build_thread_pool(&self) -> Result<ThreadPool, ThreadPoolBuildError> {
+        let mut builder = ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .thread_name(|i| format!("render-{i}"));
+
+        if self.pin_threads {
+            if let Some(cores) = core_affinity::get_core_ids().filter(|c| !c.is_empty()) {
+                builder = builder.start_handler(move |i| {
+                    core_affinity::set_for_current(cores[i % cores.len()]);
+                });
+            }
+        }
+
+        builder.build()
+    }
+}
+
+// The pool built from the last thread settings, kept so that repeated renders reuse its threads
+// instead of starting new ones. It is rebuilt only when the thread settings change.
+#[derive(Default)]
+pub struct ThreadPoolCache {
+    pool: Mutex<Option<CachedPool>>,
+}
+
+struct CachedPool {
+    threads: usize,
+    pin_threads: bool,
+    pool: Arc<ThreadPool>,
+}
+
+impl ThreadPoolCache {
+    pub fn get(&self, settings: &RenderSettings) -> Result<Arc<ThreadPool>, ThreadPoolBuildError> {
+        let mut cached = self.pool.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cached.as_ref() {
+            if cached.threads == settings.threads && cached.pin_threads == settings.pin_threads {
+                return Ok(Arc::clone(&cached.pool));
+            }
+        }
+        let pool = Arc::new(settings.build_thread_pool()?);
+        *cached = Some(CachedPool {
+            threads: settings.threads,
+            pin_threads: settings.pin_threads,
+            pool: Arc::clone(&pool),
+        });
+        Ok(pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::{RenderSettings, ThreadPoolCache};
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+
+    #[test]
+    pub fn thread_pool_uses_requested_thread_count() {
+        let settings = RenderSettings {
+            threads: 3,
+            ..Default::default()
+        };
+        let pool = settings.build_thread_pool().unwrap();
+        assert_eq!(pool.current_num_threads(), 3);
+    }
+
+    #[test]
+    pub fn pinned_thread_pool_runs_work() {
+        let settings = RenderSettings {
+            threads: 2,
+            pin_threads: true,
+            ..Default::default()
+        };
+        let pool = settings.build_thread_pool().unwrap();
+        assert_eq!(pool.install(|| 21 * 2), 42);
+    }
+
+    #[test]
+    pub fn cached_pool_is_reused_until_the_thread_count_changes() {
+        let cache = ThreadPoolCache::default();
+        let mut settings = RenderSettings {
+            threads: 2,
+            ..Default::default()
+        };
+        let first = cache.get(&settings).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.get(&settings).unwrap()));
+        settings.threads = 3;
+        let rebuilt = cache.get(&settings).unwrap();
+        assert!(!Arc::ptr_eq(&first, &rebuilt));
+        assert_eq!(rebuilt.current_num_threads(), 3);
+    }
+}
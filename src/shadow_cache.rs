@@ -0,0 +1,61 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::tuple::{Color, Point};
+use uuid::Uuid;
+
+type Key = (Uuid, [i64; 3], usize);
+
+// Shadow results for the pixel being rendered, keyed by the object that was hit, the hit point
+// snapped to a grid of `cell` sized cubes and the light. The antialiasing samples of one pixel
+// mostly land close together on the same surfaces, so on smooth geometry most of their shadow
+// rays would repeat one another. The camera clears the cache before every pixel.
+thread_local! {
+    static CACHE: RefCell<HashMap<Key, Color>> = RefCell::new(HashMap::new());
+}
+
+pub(crate) fn clear() {
+    CACHE.with(|c| c.borrow_mut().clear());
+}
+
+pub(crate) fn get_or_insert_with(
+    object: Uuid,
+    point: &Point,
+    cell: f32,
+    light: usize,
+    filter: impl FnOnce() -> Color,
+) -> Color {
+    let snap = |v: f32| (v / cell).floor() as i64;
+    let key = (object, [snap(point.x), snap(point.y), snap(point.z)], light);
+    if let Some(cached) = CACHE.with(|c| c.borrow().get(&key).copied()) {
+        return cached;
+    }
+    let result = filter();
+    CACHE.with(|c| c.borrow_mut().insert(key, result));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shadow_cache::{clear, get_or_insert_with};
+    use crate::tuple::{Color, Point};
+    use pretty_assertions::assert_eq;
+    use uuid::Uuid;
+
+    #[test]
+    pub fn nearby_points_share_a_result_until_cleared() {
+        let id = Uuid::new_v4();
+        clear();
+        let first = get_or_insert_with(id, &Point::new(0.01, 0., 0.), 0.1, 0, Color::white);
+        let near = get_or_insert_with(id, &Point::new(0.05, 0., 0.), 0.1, 0, Color::black);
+        let other_light = get_or_insert_with(id, &Point::new(0.05, 0., 0.), 0.1, 1, Color::black);
+        assert_eq!(
+            (first, near, other_light),
+            (Color::white(), Color::white(), Color::black())
+        );
+
+        clear();
+        let fresh = get_or_insert_with(id, &Point::new(0.05, 0., 0.), 0.1, 0, Color::black);
+        assert_eq!(fresh, Color::black());
+    }
+}
@@ -0,0 +1,56 @@
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+
+// State every shape carries. Shapes embed it and expose it through `Shape::common`, which gives
+// them the transform, material and id accessors for free.
+#[derive(Debug)]
+pub struct ShapeCommon {
+    pub id: Uuid,
+    pub material: Material,
+    // Flips the world normal, for shapes meant to be seen from inside such as a sphere enclosing
+    // the scene as a sky dome or a cube used as a room.
+    pub invert_normals: bool,
+    transform: Matrix4,
+    inverse_transform: Matrix4,
+}
+
+impl ShapeCommon {
+    pub fn with_material(material: Material) -> Self {
+        Self {
+            material,
+            ..Default::default()
+        }
+    }
+
+    pub const fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    pub const fn inverse_transform(&self) -> &Matrix4 {
+        &self.inverse_transform
+    }
+
+    // Leaves the previous transform in place when the new one cannot be inverted.
+    pub fn set_transform(&mut self, transform: Matrix4) -> Result<()> {
+        self.inverse_transform = transform
+            .try_inverse()
+            .map_err(|_| Error::SingularTransform(self.id))?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Default for ShapeCommon {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            material: Material::default(),
+            invert_normals: false,
+            transform: Matrix4::identity(),
+            inverse_transform: Matrix4::identity(),
+        }
+    }
+}
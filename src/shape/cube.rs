@@ -1,18 +1,18 @@
+use std::any::Any;
 use std::mem::swap;
 
+use crate::bounds::Bounds;
+use crate::error::Result;
 use crate::material::Material;
 use crate::matrix::Matrix4;
 use crate::ray::Ray;
-use crate::shape::{Intersection, Shape};
+use crate::shape::{Intersection, Shape, ShapeCommon, ShapeType};
 use crate::tuple::{approx_cmp, Point, Vector, EPSILON};
 use smallvec::{smallvec, SmallVec};
-use uuid::Uuid;
 
+#[derive(Default)]
 pub struct Cube {
-    transform: Matrix4,
-    inverse_transform: Matrix4,
-    id: Uuid,
-    material: Material,
+    common: ShapeCommon,
 }
 
 impl Cube {
@@ -22,24 +22,16 @@ impl Cube {
 
     pub fn default_with_material(m: Material) -> &'static mut Self {
         let c = Self::static_default();
-        c.material = m;
+        c.common.material = m;
         c
     }
 
     pub fn set_transform(&mut self, t: Matrix4) {
-        self.transform = t;
-        self.inverse_transform = self.transform.inverse();
+        self.try_set_transform(t).unwrap();
     }
-}
 
-impl Default for Cube {
-    fn default() -> Self {
-        Self {
-            transform: Matrix4::identity(),
-            inverse_transform: Matrix4::identity().inverse(),
-            id: Uuid::new_v4(),
-            material: Material::default(),
-        }
+    pub fn try_set_transform(&mut self, t: Matrix4) -> Result<()> {
+        self.replace_transform(t)
     }
 }
 
@@ -86,20 +78,24 @@ impl Shape for Cube {
         }
     }
 
-    fn get_material(&self) -> &Material {
-        &self.material
+    fn local_bounds(&self) -> Bounds {
+        Bounds::unit_cube()
+    }
+
+    fn common(&self) -> &ShapeCommon {
+        &self.common
     }
 
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
+    fn common_mut(&mut self) -> &mut ShapeCommon {
+        &mut self.common
     }
 
-    fn get_inverse_transform(&self) -> &Matrix4 {
-        &self.inverse_transform
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::Cube
     }
 
-    fn get_id(&self) -> &Uuid {
-        &self.id
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 }
 
@@ -112,9 +108,12 @@ fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
     if direction.abs() >= EPSILON {
         tmin = tmin_numerator / direction;
         tmax = tmax_numerator / direction;
+    } else if (-1. ..=1.).contains(&origin) {
+        // Parallel to the slab and within it, including on its faces, where multiplying the
+        // zero numerator by infinity would give NaN.
+        return (f32::NEG_INFINITY, f32::INFINITY);
     } else {
-        tmin = tmin_numerator * f32::INFINITY;
-        tmax = tmax_numerator * f32::INFINITY;
+        return (f32::INFINITY, f32::NEG_INFINITY);
     }
 
     if tmin > tmax {
@@ -126,6 +125,8 @@ fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
 
 #[cfg(test)]
 mod tests {
+    use crate::error::Error;
+    use crate::matrix::Matrix4;
     use crate::ray::Ray;
     use crate::shape::{Cube, Shape};
     use crate::tuple::{Point, Vector};
@@ -147,6 +148,14 @@ mod tests {
         assert_eq!(xs[1].t, t2);
     }
 
+    #[test]
+    pub fn singular_transform_is_rejected_and_keeps_previous_transform() {
+        let c = Cube::static_default();
+        let result = c.try_set_transform(Matrix4::identity().scale(&Vector::new(1., 1., 0.)));
+        assert!(matches!(result, Err(Error::SingularTransform(id)) if &id == c.get_id()));
+        assert_eq!(c.get_transform(), &Matrix4::identity());
+    }
+
     #[test_case(Point::new(-2., 0.0, 0.), Vector::new(0.2673, 0.5345, 0.8018))]
     #[test_case(Point::new(0., -2.0, 0.), Vector::new(0.8018, 0.2673, 0.5345))]
     #[test_case(Point::new(0., 0., -2.), Vector::new(0.5345, 0.8018, 0.2673))]
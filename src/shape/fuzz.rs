@@ -0,0 +1,148 @@
+// Fires random rays at every shape and checks the invariants analytic intersection code tends to
+// break at its edge cases: NaN or infinite t values, normals that aren't unit length, hits that
+// don't lie on the surface, closed shapes whose entries and exits don't pair up and normals that
+// point into a closed shape instead of out of it. Rays are biased towards the awkward cases:
+// directions along the axes and origins on the planes of faces. Set `FUZZ_RAYS` to fire more than
+// the default number per shape.
+
+use crate::ray::Ray;
+use crate::sampling::uniform_sphere;
+use crate::shape::{Cube, Lathe, Plane, RoundedCube, Shape, Sphere, Triangle, VoxelGrid};
+use crate::tuple::{Point, Vector};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const DEFAULT_RAYS: usize = 20_000;
+const SURFACE_TOLERANCE: f32 = 1e-3;
+const NORMAL_TOLERANCE: f32 = 1e-3;
+
+struct Case {
+    shape: &'static dyn Shape,
+    // Signed distance to the surface, where one is easy to write down.
+    surface: Option<fn(&Point) -> f32>,
+}
+
+fn cases() -> Vec<Case> {
+    let voxels = VoxelGrid::new([3, 2, 2], vec![]);
+    voxels.set(0, 0, 0, 1);
+    voxels.set(2, 1, 1, 1);
+    vec![
+        Case {
+            shape: Sphere::static_default(),
+            surface: Some(|p| Vector::new(p.x, p.y, p.z).magnitude() - 1.),
+        },
+        Case {
+            shape: Cube::static_default(),
+            surface: Some(|p| p.x.abs().max(p.y.abs()).max(p.z.abs()) - 1.),
+        },
+        Case {
+            shape: Plane::static_default(),
+            surface: Some(|p| p.y),
+        },
+        Case {
+            shape: RoundedCube::new(0.3),
+            surface: None,
+        },
+        Case {
+            shape: Triangle::new(
+                Point::new(0., 1., 0.),
+                Point::new(-1., 0., 0.),
+                Point::new(1., 0., 0.),
+            ),
+            surface: Some(|p| p.z),
+        },
+        Case {
+            shape: Lathe::new(vec![(0., -1.), (1., -1.), (0.5, 1.), (0., 1.)]),
+            surface: None,
+        },
+        Case {
+            // The same solid with the profile running top to bottom.
+            shape: Lathe::new(vec![(0., 1.), (0.5, 1.), (1., -1.), (0., -1.)]),
+            surface: None,
+        },
+        Case {
+            shape: voxels,
+            surface: None,
+        },
+    ]
+}
+
+fn random_ray(rng: &mut StdRng) -> Ray {
+    let coordinate = |rng: &mut StdRng| {
+        if rng.gen_ratio(1, 4) {
+            // On a face plane, an edge or the axis.
+            [-1., 0., 1., 0.5][rng.gen_range(0..4)]
+        } else {
+            rng.gen_range(-4.0..4.)
+        }
+    };
+    let origin = Point::new(coordinate(rng), coordinate(rng), coordinate(rng));
+    let mut direction = uniform_sphere(rng.gen(), rng.gen());
+    if rng.gen_ratio(1, 4) {
+        let axis = rng.gen_range(0..3);
+        direction = Vector::new(
+            if axis == 0 { 0. } else { direction.x },
+            if axis == 1 { 0. } else { direction.y },
+            if axis == 2 { 0. } else { direction.z },
+        );
+    }
+    if rng.gen_ratio(1, 8) {
+        direction = [
+            Vector::new(1., 0., 0.),
+            Vector::new(0., -1., 0.),
+            Vector::new(0., 0., 1.),
+        ][rng.gen_range(0..3)];
+    }
+    Ray::new(origin, direction.normalize())
+}
+
+#[test]
+pub fn random_rays_keep_intersection_invariants() {
+    let rays = std::env::var("FUZZ_RAYS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(DEFAULT_RAYS);
+    let mut failures = vec![];
+    for case in cases() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let bounds = case.shape.local_bounds();
+        for _ in 0..rays {
+            let ray = random_ray(&mut rng);
+            let Some(xs) = case.shape.local_intersect(&ray) else {
+                continue;
+            };
+            let mut fail = |what: String| {
+                if failures.len() < 20 {
+                    failures.push(format!("{}: {what} for {ray:?}", case.shape.shape_type()));
+                }
+            };
+            let from_outside = case.shape.is_closed() && !bounds.contains_point(&ray.origin);
+            if from_outside && xs.len() % 2 != 0 {
+                fail(format!("{} intersections from outside", xs.len()));
+            }
+            for (i, x) in xs.iter().enumerate() {
+                if !x.t.is_finite() {
+                    fail(format!("t = {}", x.t));
+                    continue;
+                }
+                let p = ray.position(x.t);
+                let normal = case.shape.local_normal(&p);
+                if (normal.magnitude() - 1.).abs() > NORMAL_TOLERANCE {
+                    fail(format!("normal {normal:?} at {p:?}"));
+                }
+                // Entries and exits alternate, so outward normals face the ray on every other hit.
+                let facing = normal.dot(&ray.direction);
+                if from_outside && facing.abs() > NORMAL_TOLERANCE && (facing < 0.) != (i % 2 == 0)
+                {
+                    fail(format!("normal {normal:?} at {p:?} points inward"));
+                }
+                if let Some(distance) = case.surface.map(|f| f(&p)) {
+                    if distance.abs() > SURFACE_TOLERANCE {
+                        fail(format!("hit {p:?} is {distance} off the surface"));
+                    }
+                }
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,343 @@
+use std::any::Any;
+
+use crate::bounds::Bounds;
+use crate::error::Result;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::{Intersection, Shape, ShapeCommon, ShapeType};
+use crate::tuple::{Point, Vector, EPSILON};
+use smallvec::SmallVec;
+
+// Hits this close to a vertex, as a fraction of the segment, may be reported by both segments.
+const VERTEX_ZONE: f32 = 1e-3;
+// How far along the ray either side of such a hit to look for a change from outside to inside.
+const VERTEX_STEP: f32 = 1e-3;
+
+// A surface of revolution around the y axis. The profile is a polyline of (radius, y) points and
+// every segment sweeps out a cone frustum, a cylinder or, when both ends share a height, a flat
+// ring. Close the profile on the axis (radius 0 at both ends) to get a solid that refracts
+// correctly.
+pub struct Lathe {
+    common: ShapeCommon,
+    profile: Vec<(f32, f32)>,
+    bounds: Bounds,
+    // 1 when the profile runs counter-clockwise in the (radius, y) plane, -1 when it runs
+    // clockwise, so normals point outward whichever way round the profile was given.
+    winding: f32,
+}
+
+impl Lathe {
+    pub fn new(profile: Vec<(f32, f32)>) -> &'static mut Self {
+        let radius = profile.iter().map(|&(r, _)| r.abs()).fold(0., f32::max);
+        let (bottom, top) = profile
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &(_, y)| {
+                (lo.min(y), hi.max(y))
+            });
+        let bounds = if profile.is_empty() {
+            Bounds::empty()
+        } else {
+            Bounds::new(
+                Point::new(-radius, bottom, -radius),
+                Point::new(radius, top, radius),
+            )
+        };
+
+        // Twice the signed area of the profile closed along the axis.
+        let area = profile
+            .iter()
+            .zip(profile.iter().skip(1))
+            .map(|(&(r0, y0), &(r1, y1))| r0.mul_add(y1, -r1 * y0))
+            .sum::<f32>();
+        let winding = if area < 0. { -1. } else { 1. };
+
+        Box::leak(Box::new(Self {
+            common: ShapeCommon::default(),
+            profile,
+            bounds,
+            winding,
+        }))
+    }
+
+    pub fn set_transform(&mut self, t: Matrix4) {
+        self.try_set_transform(t).unwrap();
+    }
+
+    pub fn try_set_transform(&mut self, t: Matrix4) -> Result<()> {
+        self.replace_transform(t)
+    }
+
+    pub fn profile(&self) -> &[(f32, f32)] {
+        &self.profile
+    }
+
+    fn segments(&self) -> impl Iterator<Item = ((f32, f32), (f32, f32))> + '_ {
+        self.profile.windows(2).map(|w| (w[0], w[1]))
+    }
+
+    // Whether the ray goes from one side of the profile to the other around `t`.
+    fn crosses_at(&self, ray: &Ray, t: f32) -> bool {
+        self.contains(&ray.position(t - VERTEX_STEP))
+            != self.contains(&ray.position(t + VERTEX_STEP))
+    }
+
+    // Even-odd test of the point's (radius, y) against the profile, closed along the axis.
+    fn contains(&self, p: &Point) -> bool {
+        let (r, y) = (p.x.hypot(p.z), p.y);
+        let closing = match (self.profile.first(), self.profile.last()) {
+            (Some(&(_, first)), Some(&(_, last))) => [(0., last), (0., first)],
+            _ => return false,
+        };
+        let closed = self
+            .profile
+            .iter()
+            .chain(&closing)
+            .chain(self.profile.first());
+        closed
+            .clone()
+            .zip(closed.skip(1))
+            .filter(|&(&(r0, y0), &(r1, y1))| {
+                (y0 > y) != (y1 > y) && r < r0 + (y - y0) / (y1 - y0) * (r1 - r0)
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+unsafe impl Send for Lathe {}
+unsafe impl Sync for Lathe {}
+
+impl Shape for Lathe {
+    fn local_intersect(&'static self, ray: &Ray) -> Option<SmallVec<[Intersection; 8]>> {
+        self.bounds.intersect(ray)?;
+
+        let last = self.profile.len().saturating_sub(2);
+        // Candidate hits as (t, whether the hit is next to a vertex two segments share).
+        let mut candidates: SmallVec<[(f32, bool); 8]> = self
+            .segments()
+            .enumerate()
+            .flat_map(|(i, (start, end))| {
+                intersect_segment(ray, start, end)
+                    .into_iter()
+                    .map(move |(t, s)| {
+                        (
+                            t,
+                            i > 0 && s < VERTEX_ZONE || i < last && s > 1. - VERTEX_ZONE,
+                        )
+                    })
+            })
+            .collect();
+        candidates.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Around a vertex both segments report the hit, and a ray that only touches the vertex
+        // doesn't cross the surface at all. Hits that bunch up like that are replaced by a single
+        // one, kept only if the ray goes from one side of the profile to the other.
+        let mut xs: SmallVec<[Intersection; 8]> = SmallVec::new();
+        let mut rest = candidates.as_slice();
+        while let Some(&(t, near_vertex)) = rest.first() {
+            let len = rest
+                .iter()
+                .take_while(|&&(other, _)| other - t < VERTEX_STEP)
+                .count();
+            if len == 1 && !near_vertex || self.crosses_at(ray, t) {
+                xs.push(Intersection::new(t, self));
+            }
+            rest = &rest[len..];
+        }
+        if xs.is_empty() {
+            return None;
+        }
+        xs.sort_unstable();
+        Some(xs)
+    }
+
+    // The normal of whichever segment the point lies closest to, rotated around the axis.
+    fn local_normal(&self, p: &Point) -> Vector {
+        let r = p.x.hypot(p.z);
+        let Some(((r0, y0), (r1, y1))) = self.segments().min_by(|&(a, b), &(c, d)| {
+            distance_to_segment(r, p.y, a, b).total_cmp(&distance_to_segment(r, p.y, c, d))
+        }) else {
+            return Vector::new(0., 1., 0.);
+        };
+
+        let (normal_r, normal_y) = (self.winding * (y1 - y0), self.winding * (r0 - r1));
+        if r < EPSILON {
+            return Vector::new(0., normal_y.signum(), 0.);
+        }
+        Vector::new(normal_r * p.x / r, normal_y, normal_r * p.z / r).normalize()
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn common(&self) -> &ShapeCommon {
+        &self.common
+    }
+
+    fn common_mut(&mut self) -> &mut ShapeCommon {
+        &mut self.common
+    }
+
+    // Closed when the profile starts and ends on the axis or loops back on itself.
+    fn is_closed(&self) -> bool {
+        match (self.profile.first(), self.profile.last()) {
+            (Some(&(r0, y0)), Some(&(r1, y1))) => {
+                r0.abs() < EPSILON && r1.abs() < EPSILON || (r0, y0) == (r1, y1)
+            }
+            _ => false,
+        }
+    }
+
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::Lathe
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Hits as (t, position along the segment from 0 at the start to 1 at the end).
+fn intersect_segment(
+    ray: &Ray,
+    (r0, y0): (f32, f32),
+    (r1, y1): (f32, f32),
+) -> SmallVec<[(f32, f32); 2]> {
+    let (o, d) = (ray.origin, ray.direction);
+    let on_segment = |s: f32| (-EPSILON..=1. + EPSILON).contains(&s);
+    let mut ts = SmallVec::new();
+
+    if (y1 - y0).abs() < EPSILON {
+        if d.y.abs() < EPSILON {
+            return ts;
+        }
+        let t = (y0 - o.y) / d.y;
+        let r = (o.x + t * d.x).hypot(o.z + t * d.z);
+        let s = (r - r0) / (r1 - r0);
+        if on_segment(s) {
+            ts.push((t, s));
+        }
+        return ts;
+    }
+
+    // Points on the frustum satisfy x² + z² = (a + k·y)², with the radius growing by k per unit y.
+    let k = (r1 - r0) / (y1 - y0);
+    let a = k.mul_add(-y0, r0);
+    let radius_at_origin = k.mul_add(o.y, a);
+    let qa = d.x.mul_add(d.x, d.z * d.z) - k * k * d.y * d.y;
+    let qb = 2. * (o.x.mul_add(d.x, o.z * d.z) - k * radius_at_origin * d.y);
+    let qc = o.x.mul_add(o.x, o.z * o.z) - radius_at_origin * radius_at_origin;
+
+    let candidates: SmallVec<[f32; 2]> = if qa.abs() < EPSILON {
+        if qb.abs() < EPSILON {
+            return ts;
+        }
+        SmallVec::from_slice(&[-qc / qb])
+    } else {
+        let discriminant = qb.mul_add(qb, -4. * qa * qc);
+        if discriminant < 0. {
+            return ts;
+        }
+        let root = discriminant.sqrt();
+        SmallVec::from_slice(&[(-qb - root) / (2. * qa), (-qb + root) / (2. * qa)])
+    };
+
+    for t in candidates {
+        let y = t.mul_add(d.y, o.y);
+        // Skip the mirrored half of the double cone, where the radius would be negative.
+        let s = (y - y0) / (y1 - y0);
+        if on_segment(s) && k.mul_add(y, a) >= -EPSILON {
+            ts.push((t, s));
+        }
+    }
+    ts
+}
+
+fn distance_to_segment(r: f32, y: f32, (r0, y0): (f32, f32), (r1, y1): (f32, f32)) -> f32 {
+    let (dr, dy) = (r1 - r0, y1 - y0);
+    let length = dr.mul_add(dr, dy * dy);
+    let s = if length < EPSILON {
+        0.
+    } else {
+        ((r - r0).mul_add(dr, (y - y0) * dy) / length).clamp(0., 1.)
+    };
+    (r - s.mul_add(dr, r0)).hypot(y - s.mul_add(dy, y0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::Bounds;
+    use crate::ray::Ray;
+    use crate::shape::{Lathe, Shape};
+    use crate::tuple::{Point, Vector};
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    // A closed cylinder of radius 1 from y = 0 to y = 2.
+    fn cylinder() -> &'static Lathe {
+        Lathe::new(vec![(0., 0.), (1., 0.), (1., 2.), (0., 2.)])
+    }
+
+    #[test_case(Point::new(0., 1., -5.), Vector::new(0., 0., 1.), &[4., 6.] ; "through the side")]
+    #[test_case(Point::new(0.5, 5., 0.), Vector::new(0., -1., 0.), &[3., 5.] ; "through the caps")]
+    #[test_case(Point::new(0., 3., -5.), Vector::new(0., 0., 1.), &[] ; "above")]
+    #[test_case(Point::new(2., 1., -5.), Vector::new(0., 0., 1.), &[] ; "beside")]
+    pub fn ray_intersects_closed_cylinder(origin: Point, direction: Vector, expected: &[f32]) {
+        let xs = cylinder()
+            .local_intersect(&Ray::new(origin, direction))
+            .unwrap_or_default();
+        assert_eq!(xs.iter().map(|i| i.t).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    pub fn ray_intersects_cone_once_per_side() {
+        // A cone narrowing from radius 1 at y = 0 to a point at y = 1.
+        let cone = Lathe::new(vec![(1., 0.), (0., 1.)]);
+        let xs = cone
+            .local_intersect(&Ray::new(Point::new(0., 0.5, -5.), Vector::new(0., 0., 1.)))
+            .unwrap();
+        assert_eq!(xs.len(), 2);
+        assert!((xs[0].t - 4.5).abs() < 1e-4);
+        assert!((xs[1].t - 5.5).abs() < 1e-4);
+        // The mirrored cone above the tip is not part of the shape.
+        let above = Ray::new(Point::new(0., 1.5, -5.), Vector::new(0., 0., 1.));
+        assert!(cone.local_intersect(&above).is_none());
+    }
+
+    #[test_case(Point::new(1., 1., 0.), Vector::new(1., 0., 0.) ; "side")]
+    #[test_case(Point::new(0., 1., -1.), Vector::new(0., 0., -1.) ; "side along z")]
+    #[test_case(Point::new(0.5, 2., 0.), Vector::new(0., 1., 0.) ; "top cap")]
+    #[test_case(Point::new(0.5, 0., 0.), Vector::new(0., -1., 0.) ; "bottom cap")]
+    pub fn normal_follows_the_nearest_segment(point: Point, expected: Vector) {
+        assert_eq!(cylinder().local_normal(&point), expected);
+    }
+
+    #[test]
+    pub fn normals_point_outward_whichever_way_the_profile_runs() {
+        let reversed = Lathe::new(vec![(0., 2.), (1., 2.), (1., 0.), (0., 0.)]);
+        for point in [Point::new(1., 1., 0.), Point::new(0.5, 2., 0.)] {
+            assert_eq!(
+                reversed.local_normal(&point),
+                cylinder().local_normal(&point)
+            );
+        }
+    }
+
+    #[test_case(vec![(0., 0.), (1., 0.), (1., 2.), (0., 2.)], true ; "ends on the axis")]
+    #[test_case(vec![(1., 0.), (2., 0.), (1.5, 1.), (1., 0.)], true ; "loops back")]
+    #[test_case(vec![(1., 0.), (1., 2.)], false ; "open tube")]
+    pub fn closed_profiles_enclose_a_volume(profile: Vec<(f32, f32)>, closed: bool) {
+        assert_eq!(Lathe::new(profile).is_closed(), closed);
+    }
+
+    #[test]
+    pub fn bounds_cover_the_revolved_profile() {
+        let vase = Lathe::new(vec![(0., -1.), (0.5, -1.), (1.5, 0.5), (0.75, 3.)]);
+        assert_eq!(
+            vase.local_bounds(),
+            Bounds::new(Point::new(-1.5, -1., -1.5), Point::new(1.5, 3., 1.5))
+        );
+    }
+}
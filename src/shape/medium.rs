@@ -0,0 +1,185 @@
+use std::fmt;
+
+use crate::shape::{Intersection, Shape};
+
+// How many nested media a ray keeps track of. Entering a medium past this is ignored.
+pub const MAX_MEDIA: usize = 8;
+
+// The objects a ray is currently inside, innermost last. Refracted rays carry the stack with the
+// medium they crossed into or out of toggled, so the (n1, n2) pair of every hit comes straight
+// from the top of the stack instead of a walk over every intersection behind it. Leaving an
+// object removes it wherever it sits, which keeps overlapping objects correct.
+#[derive(Copy, Clone)]
+pub struct MediumStack {
+    media: [Option<&'static dyn Shape>; MAX_MEDIA],
+    len: usize,
+}
+
+impl MediumStack {
+    pub const fn new() -> Self {
+        Self {
+            media: [None; MAX_MEDIA],
+            len: 0,
+        }
+    }
+
+    // The media a ray starts inside, worked out from its intersections behind the origin. Used
+    // for camera rays, which carry no stack of their own.
+    pub fn behind(xs: &[Intersection]) -> Self {
+        xs.iter()
+            .take_while(|x| x.t < 0.)
+            .fold(Self::new(), |media, x| media.crossing(x.object))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn innermost(&self) -> Option<&'static dyn Shape> {
+        self.len.checked_sub(1).and_then(|top| self.media[top])
+    }
+
+    pub fn contains(&self, shape: &dyn Shape) -> bool {
+        self.position(shape).is_some()
+    }
+
+    pub fn refractive_index(&self) -> f32 {
+        self.innermost()
+            .map_or(1.0, |x| x.get_material().refractive_index)
+    }
+
+    // The stack on the other side of `shape`'s surface: without it when the ray is inside it,
+    // with it on top otherwise. Open surfaces have no inside and leave the stack as it is.
+    pub fn crossing(mut self, shape: &'static dyn Shape) -> Self {
+        if !shape.is_closed() {
+            return self;
+        }
+        if let Some(index) = self.position(shape) {
+            self.media.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+            self.media[self.len] = None;
+        } else if self.len < MAX_MEDIA {
+            self.media[self.len] = Some(shape);
+            self.len += 1;
+        }
+        self
+    }
+
+    // The refractive indices on either side of a hit on `shape`. For an open surface the second
+    // is the surface's own index, which only sets how much of the light it reflects.
+    pub fn indices(&self, shape: &'static dyn Shape) -> (f32, f32) {
+        if !shape.is_closed() {
+            return (
+                self.refractive_index(),
+                shape.get_material().refractive_index,
+            );
+        }
+        (
+            self.refractive_index(),
+            self.crossing(shape).refractive_index(),
+        )
+    }
+
+    fn position(&self, shape: &dyn Shape) -> Option<usize> {
+        self.media[..self.len]
+            .iter()
+            .position(|x| x.is_some_and(|x| x.get_id() == shape.get_id()))
+    }
+}
+
+impl Default for MediumStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MediumStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.media[..self.len].iter().flatten().map(|x| x.get_id()))
+            .finish()
+    }
+}
+
+impl PartialEq for MediumStack {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self.media[..self.len]
+                .iter()
+                .zip(&other.media[..other.len])
+                .all(|(a, b)| a.map(Shape::get_id) == b.map(Shape::get_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::Material;
+    use crate::shape::{Intersection, MediumStack, Plane, Shape, Sphere, MAX_MEDIA};
+    use pretty_assertions::assert_eq;
+
+    fn glass(refractive_index: f32) -> &'static dyn Shape {
+        Sphere::default_with_material(Material {
+            transparency: 1.,
+            refractive_index,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    pub fn crossing_enters_and_leaves_media() {
+        let (water, ice) = (glass(1.33), glass(1.31));
+        let media = MediumStack::new();
+        assert_eq!(media.indices(water), (1., 1.33));
+        let media = media.crossing(water);
+        assert_eq!(media.indices(ice), (1.33, 1.31));
+        let media = media.crossing(ice);
+        assert_eq!(media.indices(ice), (1.31, 1.33));
+        assert_eq!(media.crossing(ice).crossing(water), MediumStack::new());
+    }
+
+    #[test]
+    pub fn leaving_an_overlapping_medium_keeps_the_one_inside_it() {
+        let (a, b) = (glass(1.5), glass(2.));
+        let media = MediumStack::new().crossing(a).crossing(b);
+        // The ray leaves `a` while still inside `b`.
+        assert_eq!(media.indices(a), (2., 2.));
+        assert_eq!(media.crossing(a), MediumStack::new().crossing(b));
+    }
+
+    #[test]
+    pub fn open_surfaces_are_thin_dielectrics() {
+        let water = glass(1.33);
+        let pane = Plane::default_with_material(Material {
+            transparency: 1.,
+            refractive_index: 1.5,
+            ..Default::default()
+        });
+        let media = MediumStack::new().crossing(water);
+        assert_eq!(media.indices(pane), (1.33, 1.5));
+        assert_eq!(media.crossing(pane), media);
+    }
+
+    #[test]
+    pub fn stack_behind_the_origin_comes_from_negative_hits() {
+        let (outer, inner) = (glass(1.5), glass(2.));
+        let xs = [
+            Intersection::new(-3., outer),
+            Intersection::new(-1., inner),
+            Intersection::new(1., inner),
+            Intersection::new(3., outer),
+        ];
+        let media = MediumStack::behind(&xs);
+        assert_eq!(media.len(), 2);
+        assert_eq!(media.refractive_index(), 2.);
+    }
+
+    #[test]
+    pub fn media_past_the_limit_are_ignored() {
+        let media = (0..=MAX_MEDIA).fold(MediumStack::new(), |media, _| media.crossing(glass(1.5)));
+        assert_eq!(media.len(), MAX_MEDIA);
+    }
+}
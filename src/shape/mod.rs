@@ -1,18 +1,36 @@
+mod common;
 mod cube;
+#[cfg(test)]
+mod fuzz;
+mod lathe;
+mod medium;
+mod overlap;
 mod plane;
+mod rounded_cube;
 mod sphere;
+mod triangle;
+mod voxel;
 
+pub use common::ShapeCommon;
 pub use cube::Cube;
-pub use plane::Plane;
+pub use lathe::Lathe;
+pub use medium::{MediumStack, MAX_MEDIA};
+pub use plane::{HorizonFade, Plane};
+pub use rounded_cube::RoundedCube;
 pub use sphere::Sphere;
+pub use triangle::{Triangle, VertexColorBlend};
+pub use voxel::{VoxelColors, VoxelGrid};
 
-use crate::ray::Ray;
+use crate::ray::{Footprint, Ray, RayDifferential};
 use derive_more::Constructor;
-use itertools::Itertools;
 use smallvec::SmallVec;
+use std::any::Any;
 use std::cmp::Ordering;
+use std::fmt;
 use uuid::Uuid;
 
+use crate::bounds::Bounds;
+use crate::error::Result;
 use crate::material::Material;
 use crate::matrix::Matrix4;
 use crate::tuple::{Point, Vector, EPSILON};
@@ -29,12 +47,96 @@ pub trait Shape: Send + Sync {
         let local_normal = self.local_normal(&local_point);
         let world_normal = self.get_inverse_transform().transpose() * local_normal;
 
-        world_normal.normalize()
+        if self.common().invert_normals {
+            -world_normal.normalize()
+        } else {
+            world_normal.normalize()
+        }
+    }
+    fn local_tangent(&self, p: &Point) -> Vector {
+        let normal = self.local_normal(p);
+        let reference = if normal.y.abs() < 0.999 {
+            Vector::new(0., 1., 0.)
+        } else {
+            Vector::new(1., 0., 0.)
+        };
+        reference.cross(&normal).normalize()
+    }
+    fn get_tangent(&self, point: &Point) -> Vector {
+        let local_point = self.get_inverse_transform() * point;
+        let local_tangent = self.local_tangent(&local_point);
+        let world_tangent = self.get_transform() * local_tangent;
+
+        world_tangent.normalize()
+    }
+    fn local_bounds(&self) -> Bounds;
+    fn get_bounds(&self) -> Bounds {
+        self.local_bounds().transform(self.get_transform())
+    }
+    fn common(&self) -> &ShapeCommon;
+    fn common_mut(&mut self) -> &mut ShapeCommon;
+    fn get_material(&self) -> &Material {
+        &self.common().material
+    }
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.common_mut().material
+    }
+    fn get_transform(&self) -> &Matrix4 {
+        self.common().transform()
+    }
+    fn replace_transform(&mut self, transform: Matrix4) -> Result<()> {
+        self.common_mut().set_transform(transform)
+    }
+    fn get_inverse_transform(&self) -> &Matrix4 {
+        self.common().inverse_transform()
+    }
+    fn get_id(&self) -> &Uuid {
+        &self.common().id
+    }
+    // Whether the surface encloses a volume. Open surfaces such as planes and triangles have no
+    // inside, so refraction treats them as thin dielectrics: rays pass straight through them
+    // without entering a new medium.
+    fn is_closed(&self) -> bool {
+        true
+    }
+    fn shape_type(&self) -> ShapeType;
+    fn as_any(&self) -> &dyn Any;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ShapeType {
+    Sphere,
+    Plane,
+    Cube,
+    RoundedCube,
+    Lathe,
+    Triangle,
+    VoxelGrid,
+}
+
+impl fmt::Display for ShapeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Sphere => "sphere",
+            Self::Plane => "plane",
+            Self::Cube => "cube",
+            Self::RoundedCube => "rounded cube",
+            Self::Lathe => "lathe",
+            Self::Triangle => "triangle",
+            Self::VoxelGrid => "voxel grid",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl dyn Shape + '_ {
+    pub fn downcast_ref<T: Shape + 'static>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    pub fn overlaps(&self, other: &dyn Shape) -> bool {
+        overlap::overlaps(self, other)
     }
-    fn get_material(&self) -> &Material;
-    fn get_transform(&self) -> &Matrix4;
-    fn get_inverse_transform(&self) -> &Matrix4;
-    fn get_id(&self) -> &Uuid;
 }
 
 impl Eq for dyn Shape {}
@@ -60,59 +162,56 @@ impl PartialEq<Self> for Intersection {
 
 impl PartialOrd<Self> for Intersection {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.t.partial_cmp(&other.t)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Intersection {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        self.t.partial_cmp(&other.t).unwrap()
     }
 }
 
 impl Intersection {
     pub fn get_hit(hits: &[Self]) -> Option<Self> {
-        hits.iter()
-            .filter(|&&x| x.t >= 0.)
-            .min_by(|x, y| x.t.partial_cmp(&y.t).unwrap())
-            .copied()
+        Self::hit_index(hits).map(|index| hits[index])
     }
 
-    fn calculate_refractive_indices(&self, xs: &[Self]) -> (f32, f32) {
-        let mut n1 = 0.0;
-        let mut n2 = 0.0;
-
-        let mut containers: Vec<&'_ dyn Shape> = vec![];
-        for i in xs {
-            if i == self {
-                if containers.is_empty() {
-                    n1 = 1.0;
-                } else {
-                    n1 = containers.last().unwrap().get_material().refractive_index;
-                }
-            }
-
-            if let Some((index, _)) = containers.iter().find_position(|&x| x == &i.object) {
-                containers.remove(index);
-            } else {
-                containers.push(i.object);
-            }
-
-            if i == self {
-                if containers.is_empty() {
-                    n2 = 1.0;
-                } else {
-                    n2 = containers.last().unwrap().get_material().refractive_index;
-                }
+    pub fn hit_index(hits: &[Self]) -> Option<usize> {
+        hits.iter()
+            .enumerate()
+            .filter(|(_, x)| x.t >= 0.)
+            .min_by(|(_, x), (_, y)| x.t.partial_cmp(&y.t).unwrap())
+            .map(|(index, _)| index)
+    }
 
-                break;
-            }
-        }
+    // Walks the sorted intersections once, tracking which objects the ray is currently inside,
+    // and records the (n1, n2) pair for every intersection on the way.
+    pub fn refractive_indices(xs: &[Self]) -> SmallVec<[(f32, f32); 8]> {
+        let mut media = MediumStack::new();
+        xs.iter()
+            .map(|i| {
+                let indices = media.indices(i.object);
+                media = media.crossing(i.object);
+                indices
+            })
+            .collect()
+    }
 
-        (n1, n2)
+    fn calculate_refractive_indices(&self, xs: &[Self]) -> (f32, f32) {
+        xs.iter()
+            .position(|i| i == self)
+            .map_or((0.0, 0.0), |index| {
+                Self::refractive_indices(&xs[..=index])[index]
+            })
     }
 
     pub fn precompute_hit(self, ray: &Ray, xs: &[Self]) -> PrecomputedHit {
+        let indices = self.calculate_refractive_indices(xs);
+        self.precompute_hit_with_indices(ray, indices)
+    }
+
+    pub fn precompute_hit_with_indices(self, ray: &Ray, (n1, n2): (f32, f32)) -> PrecomputedHit {
         let point = ray.position(self.t);
         let eye = -ray.direction;
         let mut normal = self.object.get_normal(&point);
@@ -127,7 +226,7 @@ impl Intersection {
         let over_point = point + normal * EPSILON;
         let under_point = point - normal * EPSILON;
         let reflected = ray.direction.reflect(&normal);
-        let (n1, n2) = self.calculate_refractive_indices(xs);
+        let footprint = ray.differential.and_then(|d| d.footprint(&point, &normal));
 
         PrecomputedHit {
             intersection: self,
@@ -140,11 +239,13 @@ impl Intersection {
             reflected_vector: reflected,
             n1,
             n2,
+            differential: ray.differential,
+            footprint,
         }
     }
 }
 
-#[derive(Constructor, Copy, Clone)]
+#[derive(Copy, Clone)]
 pub struct PrecomputedHit {
     pub intersection: Intersection,
     pub point: Point,
@@ -156,10 +257,22 @@ pub struct PrecomputedHit {
     pub reflected_vector: Vector,
     pub n1: f32,
     pub n2: f32,
+    pub differential: Option<RayDifferential>,
+    pub footprint: Option<Footprint>,
 }
 
 impl PrecomputedHit {
     pub fn schlick_reflectance(&self) -> f32 {
+        let reflectance = self.interface_reflectance();
+        if self.intersection.object.is_closed() {
+            reflectance
+        } else {
+            // A thin pane reflects off both of its faces, light bouncing between them included.
+            2. * reflectance / (1. + reflectance)
+        }
+    }
+
+    fn interface_reflectance(&self) -> f32 {
         let mut cos = self.eye.dot(&self.normal);
 
         if self.n1 > self.n2 {
@@ -179,9 +292,10 @@ impl PrecomputedHit {
 
 #[cfg(test)]
 mod tests {
+    use crate::material::Material;
     use crate::matrix::Matrix4;
     use crate::ray::Ray;
-    use crate::shape::{Intersection, Plane, Sphere};
+    use crate::shape::{Cube, Intersection, Plane, Shape, ShapeType, Sphere};
     use crate::tuple::{Point, Vector, EPSILON};
 
     use pretty_assertions::assert_eq;
@@ -224,6 +338,47 @@ mod tests {
         assert_eq!(h.unwrap().t, 2.);
     }
 
+    #[test]
+    pub fn refractive_indices_for_all_intersections_in_one_pass() {
+        let a = Sphere::static_glass_sphere();
+        a.common.material.refractive_index = 1.5;
+        let b = Sphere::static_glass_sphere();
+        b.common.material.refractive_index = 2.0;
+        let c = Sphere::static_glass_sphere();
+        c.common.material.refractive_index = 2.5;
+        let xs = [
+            Intersection::new(2.0, a),
+            Intersection::new(2.75, b),
+            Intersection::new(3.25, c),
+            Intersection::new(4.75, b),
+            Intersection::new(5.25, c),
+            Intersection::new(6.0, a),
+        ];
+        let indices = Intersection::refractive_indices(&xs);
+        assert_eq!(
+            indices.to_vec(),
+            vec![
+                (1.0, 1.5),
+                (1.5, 2.0),
+                (2.0, 2.5),
+                (2.5, 2.5),
+                (2.5, 1.5),
+                (1.5, 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    pub fn hit_index_points_at_lowest_nonnegative() {
+        let s = Sphere::static_default();
+        let xs = [
+            Intersection::new(5., s),
+            Intersection::new(-7., s),
+            Intersection::new(2., s),
+        ];
+        assert_eq!(Intersection::hit_index(&xs), Some(2));
+    }
+
     #[test]
     pub fn precompute_the_state_of_intersection() {
         let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
@@ -248,6 +403,21 @@ mod tests {
         assert!(ph.inside);
     }
 
+    #[test]
+    pub fn inverted_normals_face_into_the_shape() {
+        let r = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let shape = Sphere::static_default();
+        shape.common_mut().invert_normals = true;
+        assert_eq!(
+            shape.get_normal(&Point::new(1., 0., 0.)),
+            Vector::new(-1., 0., 0.)
+        );
+        let i = Intersection::new(1., &*shape);
+        let ph = i.precompute_hit(&r, &[i]);
+        assert_eq!(ph.normal, Vector::new(0., 0., -1.));
+        assert!(!ph.inside);
+    }
+
     #[test]
     pub fn hit_should_offset_point() {
         let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
@@ -326,4 +496,33 @@ mod tests {
         let reflectance = comps.schlick_reflectance();
         assert_eq!(reflectance, 0.488_730_67);
     }
+
+    #[test]
+    pub fn thin_pane_reflects_off_both_faces() {
+        let pane = Plane::default_with_material(Material {
+            transparency: 1.,
+            refractive_index: 1.5,
+            ..Default::default()
+        });
+        let r = Ray::new(Point::new(0., 1., 0.), Vector::new(0., -1., 0.));
+        let i = vec![Intersection::new(1., pane)];
+        let comps = i[0].precompute_hit(&r, &i);
+        assert_eq!((comps.n1, comps.n2), (1., 1.5));
+        // Twice the 4% of a single glass face, less what bounces back out through the first.
+        assert!((comps.schlick_reflectance() - 0.08 / 1.04).abs() < EPSILON);
+    }
+
+    #[test]
+    pub fn concrete_shapes_are_recovered_from_trait_objects() {
+        let objects: Vec<&'static dyn Shape> =
+            vec![Sphere::static_default(), Cube::static_default()];
+        assert_eq!(objects[0].shape_type(), ShapeType::Sphere);
+        assert_eq!(objects[1].shape_type().to_string(), "cube");
+        assert!(objects[0].downcast_ref::<Sphere>().is_some());
+        assert!(objects[0].downcast_ref::<Plane>().is_none());
+        assert_eq!(
+            objects[1].downcast_ref::<Cube>().map(|c| c.get_id()),
+            Some(objects[1].get_id())
+        );
+    }
 }
@@ -0,0 +1,104 @@
+use crate::bounds::{axis_of, Bounds};
+use crate::shape::{Cube, Shape, Sphere};
+use crate::tuple::{Point, Vector, EPSILON};
+
+// Shapes only overlap when they interpenetrate; touching surfaces don't count. Spheres that are
+// uniformly scaled and cubes that aren't rotated or sheared are tested exactly; everything else
+// falls back to comparing bounding boxes, which can report overlaps that aren't there.
+pub(crate) fn overlaps(a: &dyn Shape, b: &dyn Shape) -> bool {
+    match (as_ball(a), as_ball(b), as_box(a), as_box(b)) {
+        (Some((c1, r1)), Some((c2, r2)), _, _) => (c1 - c2).magnitude() < r1 + r2 - EPSILON,
+        (Some(ball), _, _, Some(bounds)) | (_, Some(ball), Some(bounds), _) => {
+            ball_overlaps_box(ball, &bounds)
+        }
+        _ => bounds_overlap(&a.get_bounds(), &b.get_bounds()),
+    }
+}
+
+fn as_ball(shape: &dyn Shape) -> Option<(Point, f32)> {
+    shape.downcast_ref::<Sphere>()?;
+    let t = *shape.get_transform();
+    let [x, y, z] = axes(shape);
+    let radius = x.magnitude();
+    let uniform = [y.magnitude(), z.magnitude()]
+        .iter()
+        .all(|m| (m - radius).abs() < EPSILON);
+    let orthogonal = [x.dot(&y), y.dot(&z), z.dot(&x)]
+        .iter()
+        .all(|d| d.abs() < EPSILON);
+    (uniform && orthogonal).then(|| (t * Point::zero(), radius))
+}
+
+fn as_box(shape: &dyn Shape) -> Option<Bounds> {
+    shape.downcast_ref::<Cube>()?;
+    let [x, y, z] = axes(shape);
+    let axis_aligned = [x.y, x.z, y.x, y.z, z.x, z.y]
+        .iter()
+        .all(|v| v.abs() < EPSILON);
+    axis_aligned.then(|| shape.get_bounds())
+}
+
+fn axes(shape: &dyn Shape) -> [Vector; 3] {
+    let t = *shape.get_transform();
+    [
+        t * Vector::new(1., 0., 0.),
+        t * Vector::new(0., 1., 0.),
+        t * Vector::new(0., 0., 1.),
+    ]
+}
+
+fn ball_overlaps_box((centre, radius): (Point, f32), bounds: &Bounds) -> bool {
+    let distance_squared: f32 = (0..3)
+        .map(|axis| {
+            let c = axis_of(&centre, axis);
+            let closest = c.clamp(bounds.min_at(axis), bounds.max_at(axis));
+            (c - closest).powi(2)
+        })
+        .sum();
+    distance_squared.sqrt() < radius - EPSILON
+}
+
+fn bounds_overlap(a: &Bounds, b: &Bounds) -> bool {
+    (0..3).all(|axis| {
+        a.min_at(axis) < b.max_at(axis) - EPSILON && b.min_at(axis) < a.max_at(axis) - EPSILON
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Matrix4;
+    use crate::shape::{Cube, Lathe, Shape, Sphere};
+    use crate::tuple::{Point, Vector};
+    use test_case::test_case;
+
+    fn cube_at(x: f32) -> &'static dyn Shape {
+        let c = Cube::static_default();
+        c.set_transform(Matrix4::identity().translate(&Vector::new(x, 0., 0.)));
+        c
+    }
+
+    #[test_case(1.9, true ; "interpenetrating")]
+    #[test_case(2., false ; "touching")]
+    #[test_case(3., false ; "apart")]
+    pub fn spheres_overlap_by_distance(x: f32, expected: bool) {
+        let a: &dyn Shape = Sphere::new(Point::zero(), 1.);
+        let b: &dyn Shape = Sphere::new(Point::new(x, 0., 0.), 1.);
+        assert_eq!(a.overlaps(b), expected);
+    }
+
+    #[test]
+    pub fn sphere_near_a_box_corner_is_tested_exactly() {
+        let ball: &dyn Shape = Sphere::new(Point::new(1.6, 1.6, 0.), 0.8);
+        // The bounding boxes overlap, but the sphere stays clear of the corner.
+        assert!(!ball.overlaps(cube_at(0.)));
+        assert!(!cube_at(0.).overlaps(ball));
+        assert!(ball.overlaps(cube_at(0.5)));
+    }
+
+    #[test]
+    pub fn other_shapes_fall_back_to_bounds() {
+        let vase: &dyn Shape = Lathe::new(vec![(1., 0.), (0., 1.)]);
+        assert!(vase.overlaps(cube_at(1.5)));
+        assert!(!vase.overlaps(cube_at(2.5)));
+    }
+}
@@ -1,18 +1,35 @@
+use std::any::Any;
+
+use crate::bounds::Bounds;
+use crate::error::Result;
 use crate::material::Material;
 use crate::matrix::Matrix4;
+use crate::postprocess::smoothstep;
 use crate::ray::Ray;
-use crate::shape::{Intersection, Shape};
+use crate::shape::{Intersection, Shape, ShapeCommon, ShapeType};
 use crate::tuple::{Point, Vector, EPSILON};
-use derive_more::Constructor;
 use smallvec::{smallvec, SmallVec};
-use uuid::Uuid;
 
-#[derive(Debug, Constructor)]
+#[derive(Debug, Default)]
 pub struct Plane {
-    id: Uuid,
-    transform: Matrix4,
-    inverse_transform: Matrix4,
-    material: Material,
+    common: ShapeCommon,
+    horizon_fade: Option<HorizonFade>,
+    half_space: bool,
+}
+
+// Blends a plane into the background between two hit distances, hiding the aliased line where an
+// infinite plane meets the horizon.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HorizonFade {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl HorizonFade {
+    // How much of the background shows through at the given distance from the ray origin.
+    pub fn amount(&self, distance: f32) -> f32 {
+        smoothstep(self.start, self.end, distance)
+    }
 }
 
 impl Plane {
@@ -22,32 +39,41 @@ impl Plane {
 
     pub fn default_with_material(m: Material) -> &'static mut Self {
         Box::leak(Box::new(Self {
-            material: m,
-            ..Default::default()
+            common: ShapeCommon::with_material(m),
+            horizon_fade: None,
+            half_space: false,
         }))
     }
 
-    pub fn set_transform(&'static mut self, transform: Matrix4) -> &'static mut Self {
-        self.transform = transform;
-        self.inverse_transform = self.transform.inverse();
+    pub fn with_horizon_fade(&'static mut self, start: f32, end: f32) -> &'static mut Self {
+        self.horizon_fade = Some(HorizonFade { start, end });
         self
     }
-}
 
-unsafe impl Send for Plane {}
-unsafe impl Sync for Plane {}
+    // Makes the plane the surface of everything below it, like a body of water, instead of a thin
+    // pane that refracted rays pass straight through.
+    pub fn as_half_space(&'static mut self) -> &'static mut Self {
+        self.half_space = true;
+        self
+    }
 
-impl Default for Plane {
-    fn default() -> Self {
-        Self {
-            id: Uuid::new_v4(),
-            transform: Matrix4::identity(),
-            inverse_transform: Matrix4::identity().inverse(),
-            material: Material::default(),
-        }
+    pub const fn horizon_fade(&self) -> Option<&HorizonFade> {
+        self.horizon_fade.as_ref()
+    }
+
+    pub fn set_transform(&'static mut self, transform: Matrix4) -> &'static mut Self {
+        self.try_set_transform(transform).unwrap()
+    }
+
+    pub fn try_set_transform(&'static mut self, transform: Matrix4) -> Result<&'static mut Self> {
+        self.replace_transform(transform)?;
+        Ok(self)
     }
 }
 
+unsafe impl Send for Plane {}
+unsafe impl Sync for Plane {}
+
 impl Shape for Plane {
     fn local_intersect(&'static self, ray: &Ray) -> Option<SmallVec<[Intersection; 8]>> {
         if ray.direction.y.abs() < EPSILON {
@@ -61,27 +87,42 @@ impl Shape for Plane {
         Vector::new(0.0, 1.0, 0.0)
     }
 
-    fn get_material(&self) -> &Material {
-        &self.material
+    fn local_tangent(&self, _p: &Point) -> Vector {
+        Vector::new(1.0, 0.0, 0.0)
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::new(f32::NEG_INFINITY, 0., f32::NEG_INFINITY),
+            Point::new(f32::INFINITY, 0., f32::INFINITY),
+        )
     }
 
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
+    fn common(&self) -> &ShapeCommon {
+        &self.common
     }
 
-    fn get_inverse_transform(&self) -> &Matrix4 {
-        &self.inverse_transform
+    fn common_mut(&mut self) -> &mut ShapeCommon {
+        &mut self.common
     }
 
-    fn get_id(&self) -> &Uuid {
-        &self.id
+    fn is_closed(&self) -> bool {
+        self.half_space
+    }
+
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::Plane
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::ray::Ray;
-    use crate::shape::plane::Plane;
+    use crate::shape::plane::{HorizonFade, Plane};
     use crate::shape::Shape;
     use crate::tuple::{Point, Vector};
     use pretty_assertions::assert_eq;
@@ -104,4 +145,15 @@ mod tests {
         let xs = plane.local_intersect(&r);
         assert_eq!(xs.map(|xs| xs[0].t), expected);
     }
+
+    #[test]
+    pub fn horizon_fade_ramps_smoothly_between_distances() {
+        let fade = HorizonFade {
+            start: 10.,
+            end: 20.,
+        };
+        assert_eq!(fade.amount(5.), 0.);
+        assert_eq!(fade.amount(15.), 0.5);
+        assert_eq!(fade.amount(25.), 1.);
+    }
 }
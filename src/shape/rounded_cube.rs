@@ -0,0 +1,188 @@
+use std::any::Any;
+
+use crate::bounds::Bounds;
+use crate::error::Result;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::{Intersection, Shape, ShapeCommon, ShapeType};
+use crate::tuple::{Point, Vector, EPSILON};
+use smallvec::{smallvec, SmallVec};
+
+const MAX_MARCH_STEPS: usize = 256;
+
+// The unit cube from -1 to 1 with its edges and corners rounded off to `radius`. The surface has
+// no closed-form intersection worth the trouble, so rays sphere-trace its signed distance field
+// inside the bounding cube: forwards to find where they enter and backwards to find where they
+// leave.
+#[derive(Debug, Default)]
+pub struct RoundedCube {
+    common: ShapeCommon,
+    radius: f32,
+}
+
+impl RoundedCube {
+    pub fn new(radius: f32) -> &'static mut Self {
+        Self::with_material(radius, Material::default())
+    }
+
+    pub fn with_material(radius: f32, m: Material) -> &'static mut Self {
+        Box::leak(Box::new(Self {
+            common: ShapeCommon::with_material(m),
+            radius: radius.clamp(0., 1.),
+        }))
+    }
+
+    pub fn set_transform(&mut self, t: Matrix4) {
+        self.try_set_transform(t).unwrap();
+    }
+
+    pub fn try_set_transform(&mut self, t: Matrix4) -> Result<()> {
+        self.replace_transform(t)
+    }
+
+    pub const fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    // How far each coordinate sticks out past the flat part of the faces.
+    fn excess(&self, p: &Point) -> [f32; 3] {
+        let inner = 1. - self.radius;
+        [p.x.abs() - inner, p.y.abs() - inner, p.z.abs() - inner]
+    }
+
+    fn distance(&self, p: &Point) -> f32 {
+        let [x, y, z] = self.excess(p);
+        let outside = Vector::new(x.max(0.), y.max(0.), z.max(0.)).magnitude();
+        let inside = x.max(y).max(z).min(0.);
+        outside + inside - self.radius
+    }
+
+    fn march(&self, ray: &Ray, from: f32, to: f32) -> Option<f32> {
+        let step = (to - from).signum() / ray.direction.magnitude();
+        let mut t = from;
+        for _ in 0..MAX_MARCH_STEPS {
+            let d = self.distance(&ray.position(t));
+            if d < EPSILON {
+                return Some(t);
+            }
+            t += d * step;
+            if (t - to) * step > 0. {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+unsafe impl Send for RoundedCube {}
+unsafe impl Sync for RoundedCube {}
+
+impl Shape for RoundedCube {
+    fn local_intersect(&'static self, ray: &Ray) -> Option<SmallVec<[Intersection; 8]>> {
+        let (tmin, tmax) = Bounds::unit_cube().intersect(ray)?;
+        let entry = self.march(ray, tmin, tmax)?;
+        let exit = self.march(ray, tmax, entry)?;
+        Some(smallvec![
+            Intersection::new(entry, self),
+            Intersection::new(exit, self)
+        ])
+    }
+
+    fn local_normal(&self, p: &Point) -> Vector {
+        let [x, y, z] = self.excess(p);
+        let outward = Vector::new(
+            x.max(0.).copysign(p.x),
+            y.max(0.).copysign(p.y),
+            z.max(0.).copysign(p.z),
+        );
+        if outward.magnitude() > 0. {
+            return outward.normalize();
+        }
+        // Only reachable with a zero radius, where this is a plain cube.
+        if x >= y && x >= z {
+            Vector::new(p.x.signum(), 0., 0.)
+        } else if y >= z {
+            Vector::new(0., p.y.signum(), 0.)
+        } else {
+            Vector::new(0., 0., p.z.signum())
+        }
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::unit_cube()
+    }
+
+    fn common(&self) -> &ShapeCommon {
+        &self.common
+    }
+
+    fn common_mut(&mut self) -> &mut ShapeCommon {
+        &mut self.common
+    }
+
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::RoundedCube
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use crate::ray::Ray;
+    use crate::shape::{RoundedCube, Shape};
+    use crate::tuple::{Point, Vector};
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(Point::new(5., 0., 0.), Vector::new(-1., 0., 0.), 4., 6. ; "through the faces")]
+    #[test_case(Point::new(0., 0., 0.), Vector::new(0., 0., 1.), -1., 1. ; "from inside")]
+    #[test_case(Point::new(0., 0., -5.), Vector::new(0., 0., 2.), 2., 3. ; "with a long direction")]
+    pub fn ray_through_flat_faces_matches_cube(origin: Point, direction: Vector, t1: f32, t2: f32) {
+        let c = RoundedCube::new(0.25);
+        let xs = c.local_intersect(&Ray::new(origin, direction)).unwrap();
+        assert_eq!(xs.len(), 2);
+        assert!((xs[0].t - t1).abs() < 1e-4, "{} != {t1}", xs[0].t);
+        assert!((xs[1].t - t2).abs() < 1e-4, "{} != {t2}", xs[1].t);
+    }
+
+    #[test]
+    pub fn rounded_corner_is_cut_off() {
+        // Heads straight for the corner at (1, 1, 1), which is rounded away to a sphere of radius
+        // 0.5 centred at (0.5, 0.5, 0.5).
+        let c = RoundedCube::new(0.5);
+        let direction = Vector::new(-1., -1., -1.).normalize();
+        let xs = c
+            .local_intersect(&Ray::new(Point::new(3., 3., 3.), direction))
+            .unwrap();
+        let expected = 3f32.sqrt() * 2.5 - 0.5;
+        assert!((xs[0].t - expected).abs() < 1e-4, "{}", xs[0].t);
+
+        let sharp = RoundedCube::new(0.);
+        let xs = sharp
+            .local_intersect(&Ray::new(Point::new(3., 3., 3.), direction))
+            .unwrap();
+        assert!((xs[0].t - 3f32.sqrt() * 2.).abs() < 1e-4, "{}", xs[0].t);
+    }
+
+    #[test]
+    pub fn ray_past_rounded_edge_misses() {
+        // Cuts across the edge along z at x = y = 1, which a plain cube would still hit.
+        let c = RoundedCube::new(0.5);
+        let r = Ray::new(Point::new(-2.1, 4., 0.), Vector::new(1., -1., 0.));
+        assert!(c.local_intersect(&r).is_none());
+    }
+
+    #[test_case(Point::new(1., 0.2, -0.3), Vector::new(1., 0., 0.) ; "face")]
+    #[test_case(Point::new(0.9, 0.9, 0.), Vector::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.) ; "edge")]
+    #[test_case(Point::new(-0.9, -0.9, -0.9), Vector::new(-0.57735, -0.57735, -0.57735) ; "corner")]
+    pub fn normal_bends_around_rounded_edges(point: Point, expected: Vector) {
+        let c = RoundedCube::new(0.2);
+        assert_eq!(c.local_normal(&point), expected);
+    }
+}
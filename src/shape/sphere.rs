@@ -1,24 +1,24 @@
+use std::any::Any;
+
+use crate::bounds::Bounds;
+use crate::error::Result;
 use crate::material::Material;
 use smallvec::{smallvec, SmallVec};
-use uuid::Uuid;
 
 use crate::matrix::Matrix4;
 use crate::ray::Ray;
-use crate::shape::{Intersection, Shape};
+use crate::shape::{Intersection, Shape, ShapeCommon, ShapeType};
 use crate::tuple::{Point, Vector};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Sphere {
-    pub id: Uuid,
-    pub transform: Matrix4,
-    inverse_transform: Matrix4,
-    pub material: Material,
+    pub common: ShapeCommon,
 }
 
 impl Eq for Sphere {}
 impl PartialEq for Sphere {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        self.common.id == other.common.id
     }
 }
 
@@ -31,35 +31,46 @@ impl Sphere {
 
     pub fn default_with_material(material: Material) -> &'static mut Self {
         let mut s = Box::<Self>::default();
-        s.material = material;
+        s.common.material = material;
 
         let leaked = Box::leak(s);
         leaked
     }
 
+    // A sphere centred on `center`. `radius` must be positive: a zero radius would collapse the
+    // sphere to a point, whose transform can't be inverted, so this panics instead.
+    pub fn new(center: Point, radius: f32) -> &'static mut Self {
+        Self::static_default().set_transform(&Self::placement(center, radius))
+    }
+
+    // A glass sphere with the same requirements on `radius` as `new`.
+    pub fn glass(center: Point, radius: f32) -> &'static mut Self {
+        Self::static_glass_sphere().set_transform(&Self::placement(center, radius))
+    }
+
+    fn placement(center: Point, radius: f32) -> Matrix4 {
+        assert!(radius > 0., "sphere radius must be positive, got {radius}");
+        Matrix4::identity()
+            .scale(&Vector::new(radius, radius, radius))
+            .translate(&(center - Point::zero()))
+    }
+
     pub fn static_glass_sphere() -> &'static mut Self {
         let mut s = Box::<Self>::default();
-        s.material.transparency = 1.0;
-        s.material.refractive_index = 1.5;
+        s.common.material.transparency = 1.0;
+        s.common.material.refractive_index = 1.5;
 
         Box::leak(s)
     }
 
     pub fn set_transform(&'static mut self, transform: &Matrix4) -> &'static mut Self {
-        self.transform = *transform * self.transform;
-        self.inverse_transform = self.transform.inverse();
-        self
+        self.try_set_transform(transform).unwrap()
     }
-}
 
-impl Default for Sphere {
-    fn default() -> Self {
-        Self {
-            id: Uuid::new_v4(),
-            transform: Matrix4::identity(),
-            inverse_transform: Matrix4::identity().inverse(),
-            material: Material::default(),
-        }
+    pub fn try_set_transform(&'static mut self, transform: &Matrix4) -> Result<&'static mut Self> {
+        let combined = *transform * *self.common.transform();
+        self.replace_transform(combined)?;
+        Ok(self)
     }
 }
 
@@ -90,20 +101,24 @@ impl Shape for Sphere {
         (p - Point::zero()).normalize()
     }
 
-    fn get_material(&self) -> &Material {
-        &self.material
+    fn local_bounds(&self) -> Bounds {
+        Bounds::unit_cube()
+    }
+
+    fn common(&self) -> &ShapeCommon {
+        &self.common
     }
 
-    fn get_transform(&self) -> &Matrix4 {
-        &self.transform
+    fn common_mut(&mut self) -> &mut ShapeCommon {
+        &mut self.common
     }
 
-    fn get_inverse_transform(&self) -> &Matrix4 {
-        &self.inverse_transform
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::Sphere
     }
 
-    fn get_id(&self) -> &Uuid {
-        &self.id
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 }
 
@@ -114,11 +129,13 @@ unsafe impl Sync for Sphere {}
 mod tests {
     use test_case::test_case;
 
+    use crate::error::Error;
     use crate::matrix::Matrix4;
     use crate::ray::Ray;
     use crate::shape::{Shape, Sphere};
     use crate::tuple::{Point, Vector};
     use pretty_assertions::assert_eq;
+    use std::f32::consts::FRAC_1_SQRT_2;
 
     #[test]
     pub fn ray_intersects_sphere_at_two_points() {
@@ -169,7 +186,15 @@ mod tests {
         let s = Sphere::static_default();
         let t = Matrix4::identity().translate(&Vector::new(2., 3., 4.));
         let s2 = s.set_transform(&t);
-        assert_eq!(s2.transform, t);
+        assert_eq!(s2.get_transform(), &t);
+    }
+
+    #[test]
+    pub fn setting_singular_transform_names_the_sphere() {
+        let s = Sphere::static_default();
+        let id = s.common.id;
+        let result = s.try_set_transform(&Matrix4::identity().scale(&Vector::new(0., 1., 1.)));
+        assert!(matches!(result, Err(Error::SingularTransform(x)) if x == id));
     }
 
     #[test]
@@ -223,8 +248,8 @@ mod tests {
     pub fn normal_of_translated_sphere() {
         let s = Sphere::static_default()
             .set_transform(&Matrix4::identity().translate(&Vector::new(0., 1., 0.)));
-        let n = s.get_normal(&Point::new(0., 1.70711, -0.70711));
-        assert_eq!(n, Vector::new(0., 0.70711, -0.70711));
+        let n = s.get_normal(&Point::new(0., 1. + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_eq!(n, Vector::new(0., FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
     }
 
     #[test]
@@ -237,4 +262,41 @@ mod tests {
         let n = s.get_normal(&Point::new(0., 2_f32.sqrt() / 2., -(2_f32.sqrt()) / 2.));
         assert_eq!(n, Vector::new(0., 0.97014, -0.24254));
     }
+
+    #[test]
+    pub fn tangent_of_transformed_sphere_is_perpendicular_to_normal() {
+        let s = Sphere::static_default().set_transform(
+            &Matrix4::identity()
+                .rotate_z(std::f32::consts::PI / 5.)
+                .scale(&Vector::new(1., 0.5, 1.)),
+        );
+        let p = Point::new(0., 2_f32.sqrt() / 2., -(2_f32.sqrt()) / 2.);
+        let n = s.get_normal(&p);
+        let t = s.get_tangent(&p);
+        assert!(n.dot(&t).abs() < 0.0001);
+        assert_eq!(t, t.normalize());
+    }
+
+    #[test]
+    pub fn sphere_from_center_and_radius() {
+        let s = Sphere::new(Point::new(1., 2., 3.), 2.);
+        let r = Ray::new(Point::new(1., 2., -5.), Vector::new(0., 0., 1.));
+        let xs = s.intersect(&r).unwrap();
+        assert_eq!(xs[0].t, 6.);
+        assert_eq!(xs[1].t, 10.);
+        assert_eq!(s.get_material().transparency, 0.);
+
+        let glass = Sphere::glass(Point::zero(), 0.5);
+        assert_eq!(glass.get_material().refractive_index, 1.5);
+        assert_eq!(
+            glass.get_normal(&Point::new(0., 0.5, 0.)),
+            Vector::new(0., 1., 0.)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "sphere radius must be positive")]
+    pub fn sphere_with_zero_radius_panics() {
+        Sphere::new(Point::zero(), 0.);
+    }
 }
@@ -0,0 +1,204 @@
+use std::any::Any;
+
+use crate::bounds::Bounds;
+use crate::material::Material;
+use crate::pattern::VertexColors;
+use crate::ray::Ray;
+use crate::shape::{Intersection, Shape, ShapeCommon, ShapeType};
+use crate::tuple::{Color, Point, Vector, EPSILON};
+use smallvec::{smallvec, SmallVec};
+
+// How per-vertex colours combine with the material colour of a triangle.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum VertexColorBlend {
+    #[default]
+    Replace,
+    Multiply,
+}
+
+pub struct Triangle {
+    common: ShapeCommon,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    normal: Vector,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> &'static mut Self {
+        Self::with_material(p1, p2, p3, Material::default())
+    }
+
+    pub fn with_material(p1: Point, p2: Point, p3: Point, m: Material) -> &'static mut Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Box::leak(Box::new(Self {
+            common: ShapeCommon::with_material(m),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalize(),
+        }))
+    }
+
+    pub const fn vertices(&self) -> [Point; 3] {
+        [self.p1, self.p2, self.p3]
+    }
+
+    // Colours the triangle by interpolating `colors`, given in vertex order, across its face. This
+    // replaces any pattern on the material.
+    pub fn set_vertex_colors(&mut self, colors: [Color; 3], blend: VertexColorBlend) {
+        let vertices = self.vertices();
+        let material = &mut self.common.material;
+        let tint = match blend {
+            VertexColorBlend::Replace => Color::white(),
+            VertexColorBlend::Multiply => material.color,
+        };
+        material.pattern = Some(VertexColors::tinted(vertices, colors, tint));
+    }
+}
+
+unsafe impl Send for Triangle {}
+unsafe impl Sync for Triangle {}
+
+impl Shape for Triangle {
+    // Möller–Trumbore.
+    fn local_intersect(&'static self, ray: &Ray) -> Option<SmallVec<[Intersection; 8]>> {
+        let dir_cross_e2 = ray.direction.cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let f = 1. / det;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * ray.direction.dot(&origin_cross_e1);
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+
+        let t = f * self.e2.dot(&origin_cross_e1);
+        Some(smallvec![Intersection::new(t, self)])
+    }
+
+    fn local_normal(&self, _p: &Point) -> Vector {
+        self.normal
+    }
+
+    fn local_tangent(&self, _p: &Point) -> Vector {
+        self.e1.normalize()
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let mut bounds = Bounds::empty();
+        for p in self.vertices() {
+            bounds.add_point(&p);
+        }
+        bounds
+    }
+
+    fn common(&self) -> &ShapeCommon {
+        &self.common
+    }
+
+    fn common_mut(&mut self) -> &mut ShapeCommon {
+        &mut self.common
+    }
+
+    // Each triangle is a surface of its own, even as part of a closed mesh, and media are
+    // tracked per object. A glass mesh therefore refracts like a hollow shell of thin panes
+    // rather than a solid.
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::Triangle
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::Material;
+    use crate::ray::Ray;
+    use crate::shape::{Shape, Triangle, VertexColorBlend};
+    use crate::tuple::{Color, Point, Vector};
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    fn triangle() -> &'static Triangle {
+        Triangle::new(
+            Point::new(0., 1., 0.),
+            Point::new(-1., 0., 0.),
+            Point::new(1., 0., 0.),
+        )
+    }
+
+    #[test]
+    pub fn normal_is_constant_across_the_face() {
+        let t = triangle();
+        assert_eq!(
+            t.local_normal(&Point::new(0., 0.5, 0.)),
+            Vector::new(0., 0., -1.)
+        );
+        assert_eq!(
+            t.local_normal(&Point::new(-0.5, 0.75, 0.)),
+            Vector::new(0., 0., -1.)
+        );
+    }
+
+    #[test_case(Point::new(0., -1., -2.), Vector::new(0., 1., 0.) ; "parallel")]
+    #[test_case(Point::new(1., 1., -2.), Vector::new(0., 0., 1.) ; "beyond p1-p3 edge")]
+    #[test_case(Point::new(-1., 1., -2.), Vector::new(0., 0., 1.) ; "beyond p1-p2 edge")]
+    #[test_case(Point::new(0., -1., -2.), Vector::new(0., 0., 1.) ; "beyond p2-p3 edge")]
+    pub fn ray_misses_triangle(origin: Point, direction: Vector) {
+        assert!(triangle()
+            .local_intersect(&Ray::new(origin, direction))
+            .is_none());
+    }
+
+    #[test]
+    pub fn ray_strikes_triangle() {
+        let r = Ray::new(Point::new(0., 0.5, -2.), Vector::new(0., 0., 1.));
+        let xs = triangle().local_intersect(&r).unwrap();
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.);
+    }
+
+    #[test]
+    pub fn vertex_colors_replace_or_tint_the_material_color() {
+        let colors = [Color::white(), Color::new(1., 0., 0.), Color::black()];
+        let material = || Material {
+            color: Color::new(0.5, 0.5, 1.),
+            ..Default::default()
+        };
+        let (p1, p2, p3) = (
+            Point::new(0., 1., 0.),
+            Point::new(-1., 0., 0.),
+            Point::new(1., 0., 0.),
+        );
+        let replaced = Triangle::with_material(p1, p2, p3, material());
+        replaced.set_vertex_colors(colors, VertexColorBlend::Replace);
+        let multiplied = Triangle::with_material(p1, p2, p3, material());
+        multiplied.set_vertex_colors(colors, VertexColorBlend::Multiply);
+
+        let between = Point::new(-0.5, 0.5, 0.);
+        let color = |t: &'static Triangle| t.get_material().surface_color(t, &between, None);
+        assert_eq!(color(replaced), Color::new(1., 0.5, 0.5));
+        assert_eq!(color(multiplied), Color::new(0.5, 0.25, 0.5));
+    }
+}
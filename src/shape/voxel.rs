@@ -0,0 +1,445 @@
+use std::any::Any;
+use std::io::Read;
+
+use crate::bounds::{axis_of, Bounds};
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::pattern::Pattern;
+use crate::ray::{Footprint, Ray};
+use crate::shape::{Intersection, Shape, ShapeCommon, ShapeType};
+use crate::tuple::{Color, Point, Vector};
+use smallvec::SmallVec;
+
+// The most voxels a MagicaVoxel model has along each axis.
+const MAX_VOX_SIZE: usize = 256;
+
+// A block of unit voxels filling 0..size on each axis of object space. Each cell holds an index
+// into the palette, with 0 meaning empty. The palette colours come through the `VoxelColors`
+// pattern the grid is created with; the rest of the material is shared by every voxel.
+pub struct VoxelGrid {
+    common: ShapeCommon,
+    size: [usize; 3],
+    cells: Vec<u8>,
+    palette: Vec<Color>,
+}
+
+impl VoxelGrid {
+    // `palette[i]` is the colour of voxels with index `i + 1`.
+    pub fn new(size: [usize; 3], palette: Vec<Color>) -> &'static mut Self {
+        Box::leak(Box::new(Self {
+            common: ShapeCommon::with_material(Material {
+                pattern: Some(Box::<VoxelColors>::default()),
+                ..Default::default()
+            }),
+            size,
+            cells: vec![0; size[0] * size[1] * size[2]],
+            palette,
+        }))
+    }
+
+    pub fn set_transform(&mut self, t: Matrix4) {
+        self.try_set_transform(t).unwrap();
+    }
+
+    pub fn try_set_transform(&mut self, t: Matrix4) -> Result<()> {
+        self.replace_transform(t)
+    }
+
+    pub const fn size(&self) -> [usize; 3] {
+        self.size
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        self.index(x, y, z).map_or(0, |i| self.cells[i])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, value: u8) {
+        if let Some(i) = self.index(x, y, z) {
+            self.cells[i] = value;
+        }
+    }
+
+    // Loads a MagicaVoxel `.vox` model. Its z-up coordinates are turned so that z becomes y.
+    pub fn load_vox(mut reader: impl Read) -> Result<&'static mut Self> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        if data.get(..4) != Some(b"VOX ") {
+            return Err(vox_error("missing VOX magic number"));
+        }
+
+        let mut size = None;
+        let mut voxels: &[u8] = &[];
+        let mut palette = None;
+        // The chunks of the first model sit inside MAIN; reading them flat covers both.
+        let mut offset = 8;
+        while offset + 12 <= data.len() {
+            let id = &data[offset..offset + 4];
+            let content = read_u32(&data, offset + 4)? as usize;
+            let body = data
+                .get(offset + 12..offset + 12 + content)
+                .ok_or_else(|| vox_error("chunk runs past the end of the file"))?;
+            match id {
+                b"SIZE" if size.is_none() => {
+                    size = Some([
+                        read_u32(body, 0)? as usize,
+                        read_u32(body, 8)? as usize,
+                        read_u32(body, 4)? as usize,
+                    ]);
+                }
+                b"XYZI" if voxels.is_empty() => {
+                    let count = read_u32(body, 0)? as usize;
+                    voxels = body
+                        .get(4..4 + count * 4)
+                        .ok_or_else(|| vox_error("voxel list is truncated"))?;
+                }
+                b"RGBA" => {
+                    palette = Some(
+                        body.chunks_exact(4)
+                            .map(|c| {
+                                Color::new(
+                                    f32::from(c[0]) / 255.,
+                                    f32::from(c[1]) / 255.,
+                                    f32::from(c[2]) / 255.,
+                                )
+                            })
+                            .collect(),
+                    );
+                }
+                _ => {}
+            }
+            offset += if id == b"MAIN" { 12 } else { 12 + content };
+        }
+
+        let size = size.ok_or_else(|| vox_error("missing SIZE chunk"))?;
+        if size.contains(&0) {
+            return Err(vox_error("model has no size along some axis"));
+        }
+        // Voxel coordinates are single bytes, so anything larger is a corrupt header asking for
+        // a huge grid.
+        if size.iter().any(|&n| n > MAX_VOX_SIZE) {
+            return Err(vox_error("model is larger than 256 voxels along some axis"));
+        }
+        size.iter()
+            .try_fold(1usize, |cells, &n| cells.checked_mul(n))
+            .ok_or_else(|| vox_error("model is too large"))?;
+        // Files without a palette use MagicaVoxel's default one, which isn't bundled here.
+        let palette = palette.unwrap_or_else(|| vec![Color::new(0.75, 0.75, 0.75); 255]);
+        let grid = Self::new(size, palette);
+        for voxel in voxels.chunks_exact(4) {
+            let (x, y, z) = (voxel[0].into(), voxel[2].into(), voxel[1].into());
+            grid.set(x, y, z, voxel[3]);
+        }
+        Ok(grid)
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> Option<usize> {
+        let [w, h, d] = self.size;
+        (x < w && y < h && z < d).then(|| (z * h + y) * w + x)
+    }
+
+    fn cell_at(&self, cell: [i64; 3]) -> u8 {
+        if cell.iter().any(|&c| c < 0) {
+            return 0;
+        }
+        self.get(cell[0] as usize, cell[1] as usize, cell[2] as usize)
+    }
+
+    // The occupied voxel a surface point belongs to. Points on a face touch two cells, of which
+    // only one can be filled if the face is visible.
+    fn voxel_at_surface(&self, p: &Point) -> Option<(u8, [i64; 3])> {
+        let candidates = |v: f32| {
+            let below = (v - 1e-3).floor() as i64;
+            let above = (v + 1e-3).floor() as i64;
+            [below, above]
+        };
+        let (xs, ys, zs) = (candidates(p.x), candidates(p.y), candidates(p.z));
+        for x in xs {
+            for y in ys {
+                for z in zs {
+                    let value = self.cell_at([x, y, z]);
+                    if value != 0 {
+                        return Some((value, [x, y, z]));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn color_at_surface(&self, p: &Point) -> Color {
+        self.voxel_at_surface(p)
+            .and_then(|(value, _)| self.palette.get(usize::from(value) - 1).copied())
+            .unwrap_or_else(Color::white)
+    }
+
+    fn grid_bounds(&self) -> Bounds {
+        let [w, h, d] = self.size;
+        Bounds::new(Point::zero(), Point::new(w as f32, h as f32, d as f32))
+    }
+}
+
+unsafe impl Send for VoxelGrid {}
+unsafe impl Sync for VoxelGrid {}
+
+impl Shape for VoxelGrid {
+    // Walks the cells along the ray (Amanatides & Woo) and records every change between empty
+    // and filled space, so the hits pair up as entries and exits like any other closed shape.
+    fn local_intersect(&'static self, ray: &Ray) -> Option<SmallVec<[Intersection; 8]>> {
+        if self.size.contains(&0) {
+            return None;
+        }
+        let bounds = self.grid_bounds();
+        let (t_enter, t_exit) = bounds.intersect(ray)?;
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+        let start = ray.position(t_enter);
+
+        let mut cell = [0i64; 3];
+        let mut step = [0i64; 3];
+        let mut t_max = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            let last = self.size[axis] as i64 - 1;
+            cell[axis] = (axis_of(&start, axis).floor() as i64).clamp(0, last);
+            if direction[axis] > 0. {
+                step[axis] = 1;
+                t_max[axis] =
+                    t_enter + ((cell[axis] + 1) as f32 - axis_of(&start, axis)) / direction[axis];
+                t_delta[axis] = 1. / direction[axis];
+            } else if direction[axis] < 0. {
+                step[axis] = -1;
+                t_max[axis] =
+                    t_enter + (cell[axis] as f32 - axis_of(&start, axis)) / direction[axis];
+                t_delta[axis] = -1. / direction[axis];
+            }
+        }
+
+        let mut xs = SmallVec::new();
+        let mut inside = false;
+        let mut t = t_enter;
+        loop {
+            let filled = self.cell_at(cell) != 0;
+            if filled != inside {
+                xs.push(Intersection::new(t, self));
+                inside = filled;
+            }
+
+            let axis = (0..3)
+                .min_by(|&a, &b| t_max[a].total_cmp(&t_max[b]))
+                .unwrap();
+            t = t_max[axis];
+            cell[axis] += step[axis];
+            if t > t_exit || cell[axis] < 0 || cell[axis] >= self.size[axis] as i64 {
+                if inside {
+                    xs.push(Intersection::new(t.min(t_exit), self));
+                }
+                break;
+            }
+            t_max[axis] += t_delta[axis];
+        }
+
+        (!xs.is_empty()).then_some(xs)
+    }
+
+    // Points at the face of the filled voxel the point lies on, outwards from that voxel.
+    fn local_normal(&self, p: &Point) -> Vector {
+        let Some((_, cell)) = self.voxel_at_surface(p) else {
+            return Vector::new(0., 1., 0.);
+        };
+        let centre = [
+            cell[0] as f32 + 0.5,
+            cell[1] as f32 + 0.5,
+            cell[2] as f32 + 0.5,
+        ];
+        let offset = [p.x - centre[0], p.y - centre[1], p.z - centre[2]];
+        let axis = (0..3)
+            .max_by(|&a, &b| offset[a].abs().total_cmp(&offset[b].abs()))
+            .unwrap();
+        let mut normal = [0.; 3];
+        normal[axis] = offset[axis].signum();
+        Vector::new(normal[0], normal[1], normal[2])
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.grid_bounds()
+    }
+
+    fn common(&self) -> &ShapeCommon {
+        &self.common
+    }
+
+    fn common_mut(&mut self) -> &mut ShapeCommon {
+        &mut self.common
+    }
+
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::VoxelGrid
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Colours a `VoxelGrid` from its palette. On any other shape it is plain white.
+#[derive(Debug, Default)]
+pub struct VoxelColors;
+
+impl Pattern for VoxelColors {
+    fn color_object(&self, object: &dyn Shape, point: &Point) -> Color {
+        object
+            .downcast_ref::<VoxelGrid>()
+            .map_or_else(Color::white, |grid| {
+                grid.color_at_surface(&(object.get_inverse_transform() * point))
+            })
+    }
+
+    // Voxels are flat-coloured, so there is nothing to filter.
+    fn color_object_filtered(
+        &self,
+        object: &dyn Shape,
+        point: &Point,
+        _footprint: &Footprint,
+    ) -> Color {
+        self.color_object(object, point)
+    }
+
+    fn color_at(&self, _point: &Point) -> Color {
+        Color::white()
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| vox_error("unexpected end of file"))
+}
+
+fn vox_error(msg: &str) -> Error {
+    Error::Import(format!("VOX: {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::ray::Ray;
+    use crate::shape::{Shape, VoxelGrid};
+    use crate::tuple::{Color, Point, Vector};
+    use pretty_assertions::assert_eq;
+
+    fn grid() -> &'static VoxelGrid {
+        let grid = VoxelGrid::new(
+            [3, 1, 1],
+            vec![Color::new(1., 0., 0.), Color::new(0., 0., 1.)],
+        );
+        grid.set(0, 0, 0, 1);
+        grid.set(2, 0, 0, 2);
+        grid
+    }
+
+    #[test]
+    pub fn ray_enters_and_leaves_every_filled_run() {
+        let r = Ray::new(Point::new(-1., 0.5, 0.5), Vector::new(1., 0., 0.));
+        let ts: Vec<f32> = grid()
+            .local_intersect(&r)
+            .unwrap()
+            .iter()
+            .map(|i| i.t)
+            .collect();
+        assert_eq!(ts, vec![1., 2., 3., 4.]);
+
+        let through_gap = Ray::new(Point::new(1.5, 2., 0.5), Vector::new(0., -1., 0.));
+        assert!(grid().local_intersect(&through_gap).is_none());
+    }
+
+    #[test]
+    pub fn diagonal_ray_steps_through_cells() {
+        let grid = VoxelGrid::new([2, 2, 1], vec![Color::white()]);
+        grid.set(1, 1, 0, 1);
+        let r = Ray::new(Point::new(0.1, 0.2, 0.5), Vector::new(1., 1., 0.));
+        let xs = grid.local_intersect(&r).unwrap();
+        assert_eq!(xs.len(), 2);
+        assert!((xs[0].t - 0.9).abs() < 1e-5);
+        assert!((xs[1].t - 1.8).abs() < 1e-5);
+    }
+
+    #[test]
+    pub fn surface_takes_normal_and_color_from_its_voxel() {
+        let grid = grid();
+        assert_eq!(
+            grid.local_normal(&Point::new(2., 0.5, 0.5)),
+            Vector::new(-1., 0., 0.)
+        );
+        assert_eq!(
+            grid.local_normal(&Point::new(0.5, 1., 0.5)),
+            Vector::new(0., 1., 0.)
+        );
+        let material = grid.get_material();
+        assert_eq!(
+            material.surface_color(grid, &Point::new(2., 0.5, 0.5), None),
+            Color::new(0., 0., 1.)
+        );
+        assert_eq!(
+            material.surface_color(grid, &Point::new(1., 0.5, 0.5), None),
+            Color::new(1., 0., 0.)
+        );
+    }
+
+    // A `.vox` file with one green voxel at (1, 2, 3) in a model of the given size.
+    fn vox_file(size: [u32; 3]) -> Vec<u8> {
+        let chunk = |id: &[u8], body: &[u8]| {
+            let mut c = id.to_vec();
+            c.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            c.extend_from_slice(&0u32.to_le_bytes());
+            c.extend_from_slice(body);
+            c
+        };
+        let size = size.map(u32::to_le_bytes).concat();
+        let mut xyzi = 1u32.to_le_bytes().to_vec();
+        xyzi.extend_from_slice(&[1, 2, 3, 1]);
+        let mut rgba = vec![0; 1024];
+        rgba[..4].copy_from_slice(&[0, 255, 0, 255]);
+        let children = [
+            chunk(b"SIZE", &size),
+            chunk(b"XYZI", &xyzi),
+            chunk(b"RGBA", &rgba),
+        ]
+        .concat();
+
+        let mut file = b"VOX ".to_vec();
+        file.extend_from_slice(&150u32.to_le_bytes());
+        file.extend_from_slice(b"MAIN");
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&(children.len() as u32).to_le_bytes());
+        file.extend_from_slice(&children);
+        file
+    }
+
+    #[test]
+    pub fn vox_files_are_loaded_y_up() {
+        let grid = VoxelGrid::load_vox(vox_file([2, 3, 4]).as_slice()).unwrap();
+        assert_eq!(grid.size(), [2, 4, 3]);
+        assert_eq!(grid.get(1, 3, 2), 1);
+        assert_eq!(
+            grid.color_at_surface(&Point::new(1., 3.5, 2.5)),
+            Color::new(0., 1., 0.)
+        );
+    }
+
+    #[test]
+    pub fn vox_files_with_an_empty_size_are_rejected() {
+        assert!(matches!(
+            VoxelGrid::load_vox(vox_file([2, 0, 4]).as_slice()),
+            Err(Error::Import(_))
+        ));
+    }
+
+    #[test]
+    pub fn vox_files_larger_than_magicavoxel_allows_are_rejected() {
+        assert!(VoxelGrid::load_vox(vox_file([256, 256, 256]).as_slice()).is_ok());
+        assert!(matches!(
+            VoxelGrid::load_vox(vox_file([2, 257, 4]).as_slice()),
+            Err(Error::Import(_))
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::light::PointLight;
+use crate::tuple::{Color, Point, Vector};
+
+// Preetham, Shirley & Smits, "A Practical Analytic Model for Daylight" (1999).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sky {
+    pub sun_direction: Vector,
+    pub turbidity: f32,
+    pub exposure: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Perez {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+}
+
+impl Perez {
+    fn eval(&self, cos_theta: f32, gamma: f32) -> f32 {
+        let cos_gamma = gamma.cos();
+        (1. + self.a * (self.b / cos_theta).exp())
+            * (1. + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+impl Sky {
+    pub fn new(sun_elevation: f32, sun_azimuth: f32, turbidity: f32) -> Self {
+        let (sin_el, cos_el) = sun_elevation.sin_cos();
+        let (sin_az, cos_az) = sun_azimuth.sin_cos();
+        Self {
+            sun_direction: Vector::new(cos_el * sin_az, sin_el, cos_el * cos_az).normalize(),
+            turbidity: turbidity.max(1.),
+            exposure: 0.1,
+        }
+    }
+
+    pub fn radiance(&self, direction: &Vector) -> Color {
+        let direction = direction.normalize();
+        // The model is only defined above the horizon; the ground reuses the horizon color.
+        let cos_theta = direction.y.max(0.001);
+        let theta_s = self.sun_direction.y.clamp(0.001, 1.).acos();
+        let gamma = direction.dot(&self.sun_direction).clamp(-1., 1.).acos();
+
+        let t = self.turbidity;
+        let chi = (4. / 9. - t / 120.) * (std::f32::consts::PI - 2. * theta_s);
+        let zenith_y = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_x = zenith_chromaticity(
+            t,
+            theta_s,
+            [
+                [0.00166, -0.00375, 0.00209, 0.],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ],
+        );
+        let zenith_yy = zenith_chromaticity(
+            t,
+            theta_s,
+            [
+                [0.00275, -0.00610, 0.00317, 0.],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ],
+        );
+
+        let luminance = Perez {
+            a: 0.1787 * t - 1.4630,
+            b: -0.3554 * t + 0.4275,
+            c: -0.0227 * t + 5.3251,
+            d: 0.1206 * t - 2.5771,
+            e: -0.0670 * t + 0.3703,
+        };
+        let x = Perez {
+            a: -0.0193 * t - 0.2592,
+            b: -0.0665 * t + 0.0008,
+            c: -0.0004 * t + 0.2125,
+            d: -0.0641 * t - 0.8989,
+            e: -0.0033 * t + 0.0452,
+        };
+        let y = Perez {
+            a: -0.0167 * t - 0.2608,
+            b: -0.0950 * t + 0.0092,
+            c: -0.0079 * t + 0.2102,
+            d: -0.0441 * t - 1.6537,
+            e: -0.0109 * t + 0.0529,
+        };
+
+        let relative = |p: &Perez| p.eval(cos_theta, gamma) / p.eval(1., theta_s);
+        let big_y = zenith_y * relative(&luminance) * self.exposure;
+        let x = zenith_x * relative(&x);
+        let y = zenith_yy * relative(&y);
+
+        xyy_to_rgb(x, y, big_y)
+    }
+
+    pub fn sun_light(&self, distance: f32) -> PointLight {
+        let color = self.radiance(&self.sun_direction);
+        let brightest = color.r.max(color.g).max(color.b).max(f32::EPSILON);
+        PointLight::new(
+            Point::zero() + self.sun_direction * distance,
+            color * (1. / brightest),
+        )
+    }
+}
+
+fn zenith_chromaticity(turbidity: f32, theta_s: f32, m: [[f32; 4]; 3]) -> f32 {
+    let t = [turbidity * turbidity, turbidity, 1.];
+    let th = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.];
+    (0..3)
+        .map(|i| t[i] * (0..4).map(|j| m[i][j] * th[j]).sum::<f32>())
+        .sum()
+}
+
+fn xyy_to_rgb(x: f32, y: f32, big_y: f32) -> Color {
+    let big_x = x / y * big_y;
+    let big_z = (1. - x - y) / y * big_y;
+    Color::new(
+        (3.2406 * big_x - 1.5372 * big_y - 0.4986 * big_z).max(0.),
+        (-0.9689 * big_x + 1.8758 * big_y + 0.0415 * big_z).max(0.),
+        (0.0557 * big_x - 0.2040 * big_y + 1.0570 * big_z).max(0.),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sky::Sky;
+    use crate::tuple::Vector;
+    use std::f32::consts::PI;
+
+    #[test]
+    pub fn clear_sky_is_blue_at_zenith() {
+        let sky = Sky::new(PI / 4., 0., 2.5);
+        let c = sky.radiance(&Vector::new(0., 1., 0.));
+        assert!(c.b > c.r);
+        assert!(c.b > 0.);
+    }
+
+    #[test]
+    pub fn sky_is_brightest_towards_the_sun() {
+        let sky = Sky::new(PI / 6., 0., 3.);
+        let towards = sky.radiance(&sky.sun_direction);
+        let away = sky.radiance(&Vector::new(
+            -sky.sun_direction.x,
+            sky.sun_direction.y,
+            -sky.sun_direction.z,
+        ));
+        assert!(towards.g > away.g);
+    }
+
+    #[test]
+    pub fn sun_light_sits_along_sun_direction() {
+        let sky = Sky::new(PI / 3., PI / 2., 2.);
+        let light = sky.sun_light(1000.);
+        let to_light = (light.position - crate::tuple::Point::zero()).normalize();
+        assert_eq!(to_light, sky.sun_direction);
+        let c = light.intensity;
+        assert_eq!(c.r.max(c.g).max(c.b), 1.);
+    }
+}
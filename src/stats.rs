@@ -0,0 +1,122 @@
+use std::cell::Cell;
+
+use crate::tuple::Color;
+
+// Per-thread counters bumped by `World::intersect_world` and `World::shade_hit`. A pixel is rendered start to finish on
+// one thread, so resetting before and reading after it gives that pixel's cost.
+thread_local! {
+    static RAYS: Cell<u64> = const { Cell::new(0) };
+    static INTERSECTION_TESTS: Cell<u64> = const { Cell::new(0) };
+    static INTERNAL_REFLECTIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct RayStats {
+    pub rays: u64,
+    pub intersection_tests: u64,
+    // Hits on transparent surfaces that reflect nearly all light, including total internal
+    // reflection. Many of them inside glass usually explain unexpectedly dark patches.
+    pub internal_reflections: u64,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum HeatmapMetric {
+    #[default]
+    IntersectionTests,
+    Rays,
+    InternalReflections,
+}
+
+impl RayStats {
+    pub const fn get(&self, metric: HeatmapMetric) -> u64 {
+        match metric {
+            HeatmapMetric::IntersectionTests => self.intersection_tests,
+            HeatmapMetric::Rays => self.rays,
+            HeatmapMetric::InternalReflections => self.internal_reflections,
+        }
+    }
+}
+
+pub(crate) fn record_ray(intersection_tests: usize) {
+    RAYS.with(|r| r.set(r.get() + 1));
+    INTERSECTION_TESTS.with(|t| t.set(t.get() + intersection_tests as u64));
+}
+
+pub(crate) fn record_internal_reflection() {
+    INTERNAL_REFLECTIONS.with(|r| r.set(r.get() + 1));
+}
+
+pub fn take() -> RayStats {
+    RayStats {
+        rays: RAYS.with(|r| r.replace(0)),
+        intersection_tests: INTERSECTION_TESTS.with(|t| t.replace(0)),
+        internal_reflections: INTERNAL_REFLECTIONS.with(|r| r.replace(0)),
+    }
+}
+
+// Black through blue, red and yellow to white as `value` goes from 0 to 1.
+pub fn heat_color(value: f32) -> Color {
+    const STOPS: [Color; 5] = [
+        Color {
+            r: 0.,
+            g: 0.,
+            b: 0.,
+        },
+        Color {
+            r: 0.,
+            g: 0.,
+            b: 1.,
+        },
+        Color {
+            r: 1.,
+            g: 0.,
+            b: 0.,
+        },
+        Color {
+            r: 1.,
+            g: 1.,
+            b: 0.,
+        },
+        Color {
+            r: 1.,
+            g: 1.,
+            b: 1.,
+        },
+    ];
+    let scaled = value.clamp(0., 1.) * (STOPS.len() - 1) as f32;
+    let index = (scaled as usize).min(STOPS.len() - 2);
+    let t = scaled - index as f32;
+    STOPS[index] * (1. - t) + STOPS[index + 1] * t
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::{heat_color, record_internal_reflection, record_ray, take, RayStats};
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn counters_reset_when_taken() {
+        take();
+        record_ray(3);
+        record_ray(4);
+        record_internal_reflection();
+        assert_eq!(
+            take(),
+            RayStats {
+                rays: 2,
+                intersection_tests: 7,
+                internal_reflections: 1,
+            }
+        );
+        assert_eq!(take(), RayStats::default());
+    }
+
+    #[test]
+    pub fn heat_ramp_endpoints() {
+        assert_eq!(heat_color(0.), Color::black());
+        assert_eq!(heat_color(0.5), Color::new(1., 0., 0.));
+        assert_eq!(heat_color(1.), Color::white());
+        assert_eq!(heat_color(0.125), Color::new(0., 0., 0.5));
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::canvas::Canvas;
+use crate::tuple::Color;
+
+// Redraws are throttled so large terminals don't spend more time printing than rendering.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+// A downsampled, ANSI-coloured view of a frame that fills in as tiles complete. Unrendered pixels
+// are black. Pixels show their latest sample rather than the filtered result, which is close
+// enough at terminal resolution.
+pub(crate) struct TermPreview {
+    columns: usize,
+    state: Mutex<(Canvas, Option<Instant>)>,
+}
+
+impl TermPreview {
+    pub(crate) fn new(width: usize, height: usize, columns: usize) -> Self {
+        Self {
+            columns,
+            state: Mutex::new((Canvas::new(width, height), None)),
+        }
+    }
+
+    pub(crate) fn add_samples(&self, samples: &[(f32, f32, Color)]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let (canvas, last_draw) = &mut *state;
+        for &(x, y, color) in samples {
+            canvas.set(x as usize, y as usize, color);
+        }
+        match last_draw {
+            // Clear whatever was on screen before the first frame.
+            None => eprint!("\x1b[2J"),
+            Some(t) if t.elapsed() < REDRAW_INTERVAL => return,
+            Some(_) => {}
+        }
+        *last_draw = Some(Instant::now());
+        draw(&canvas.to_ansi(self.columns));
+    }
+
+    pub(crate) fn finish(&self) {
+        if let Ok(state) = self.state.lock() {
+            draw(&state.0.to_ansi(self.columns));
+        }
+    }
+
+    #[cfg(test)]
+    fn frame(&self) -> String {
+        self.state.lock().unwrap().0.to_ansi(self.columns)
+    }
+}
+
+// Moves the cursor home and overwrites the previous frame in place.
+fn draw(frame: &str) {
+    eprint!("\x1b[H{frame}");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::term_preview::TermPreview;
+    use crate::tuple::Color;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn completed_tiles_show_up_in_the_frame() {
+        let preview = TermPreview::new(4, 2, 4);
+        preview.add_samples(&[(2.5, 0.5, Color::white()), (3.5, 1.2, Color::white())]);
+
+        let mut expected = Canvas::new(4, 2);
+        expected.write_pixel(2, 0, Color::white()).unwrap();
+        expected.write_pixel(3, 1, Color::white()).unwrap();
+        assert_eq!(preview.frame(), expected.to_ansi(4));
+    }
+}
@@ -0,0 +1,91 @@
+use crate::canvas::font::{glyph, GLYPH_ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::shape::{Cube, Shape};
+use crate::tuple::Vector;
+
+// Builds text out of the canvas bitmap font, one unit per font pixel. The text starts at the
+// origin and runs along +x with its baseline on y = 0, extruded from z = 0 to z = `depth`.
+// Each lit run in a glyph row becomes a single box, and boxes are grouped per character so
+// labels can be placed or coloured glyph by glyph. Spaces produce empty groups. Materials can't
+// be shared between shapes, so `material` is called once per box.
+pub fn extrude_text(
+    text: &str,
+    depth: f32,
+    material: impl Fn() -> Material,
+) -> Vec<Vec<&'static dyn Shape>> {
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let origin_x = (i * GLYPH_ADVANCE) as f32;
+            let mut boxes = vec![];
+            for (row, bits) in glyph(c).iter().enumerate() {
+                let top = (GLYPH_HEIGHT - row) as f32;
+                let mut column = 0;
+                while column < GLYPH_WIDTH {
+                    if !lit(*bits, column) {
+                        column += 1;
+                        continue;
+                    }
+                    let start = column;
+                    while column < GLYPH_WIDTH && lit(*bits, column) {
+                        column += 1;
+                    }
+                    boxes.push(prism(
+                        origin_x + start as f32,
+                        origin_x + column as f32,
+                        top,
+                        depth,
+                        material(),
+                    ));
+                }
+            }
+            boxes
+        })
+        .collect()
+}
+
+const fn lit(bits: u8, column: usize) -> bool {
+    bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0
+}
+
+// A box spanning `left..right` in x, `top - 1..top` in y and `0..depth` in z.
+fn prism(left: f32, right: f32, top: f32, depth: f32, material: Material) -> &'static dyn Shape {
+    let cube = Cube::default_with_material(material);
+    cube.set_transform(
+        Matrix4::identity()
+            .scale(&Vector::new((right - left) / 2., 0.5, depth / 2.))
+            .translate(&Vector::new((left + right) / 2., top - 0.5, depth / 2.)),
+    );
+    cube
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text::extrude_text;
+    use crate::tuple::Point;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    pub fn glyph_rows_are_merged_into_runs() {
+        let glyphs = extrude_text("T ", 2., Default::default);
+        assert_eq!(glyphs.len(), 2);
+        // The bar across the top and one box for each of the six rows of the stem.
+        assert_eq!(glyphs[0].len(), 7);
+        assert!(glyphs[1].is_empty());
+
+        let bar = glyphs[0][0].get_bounds();
+        assert_eq!(bar.min, Point::new(0., 6., 0.));
+        assert_eq!(bar.max, Point::new(5., 7., 2.));
+    }
+
+    #[test]
+    pub fn characters_advance_along_x() {
+        let glyphs = extrude_text("-.", 1., Default::default);
+        let dash = glyphs[0][0].get_bounds();
+        assert_eq!(dash.min, Point::new(0., 3., 0.));
+        let dot = glyphs[1][0].get_bounds();
+        assert_eq!(dot.min, Point::new(7., 1., 0.));
+        assert_eq!(dot.max, Point::new(9., 2., 1.));
+    }
+}
@@ -0,0 +1,164 @@
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    // Tiles in rings around the one holding the centre pixel, nearest ring first, so the
+    // middle of the image, where the subject usually is, finishes first. Tiles within a ring
+    // follow Morton order, which keeps consecutive tiles close together.
+    pub fn centre_out(width: usize, height: usize, tile_size: usize) -> Vec<Self> {
+        let columns = width.div_ceil(tile_size);
+        let rows = height.div_ceil(tile_size);
+        let (centre_column, centre_row) = (width / 2 / tile_size, height / 2 / tile_size);
+
+        (0..rows)
+            .cartesian_product(0..columns)
+            .sorted_by_key(|&(row, column)| {
+                let ring = row.abs_diff(centre_row).max(column.abs_diff(centre_column));
+                (ring, morton_code(column as u32, row as u32))
+            })
+            .map(|(row, column)| {
+                let x = column * tile_size;
+                let y = row * tile_size;
+                Self {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                }
+            })
+            .collect()
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+
+    pub const fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Runs `work` on every item across the current rayon pool, handing items out strictly in slice
+// order: each worker takes the next untaken item from a shared cursor. Splitting the slice
+// instead would let idle workers steal from the far end, which for `centre_out` is the outer
+// ring.
+pub fn for_each_in_order<T: Sync>(items: &[T], work: impl Fn(usize, &T) + Sync) {
+    let next = AtomicUsize::new(0);
+    (0..rayon::current_num_threads())
+        .into_par_iter()
+        .with_max_len(1)
+        .for_each(|_| loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(item) = items.get(index) else {
+                break;
+            };
+            work(index, item);
+        });
+}
+
+pub fn morton_code(x: u32, y: u32) -> u64 {
+    spread_bits(x) | (spread_bits(y) << 1)
+}
+
+fn spread_bits(v: u32) -> u64 {
+    let mut v = u64::from(v);
+    v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+    v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tile::{for_each_in_order, morton_code, Tile};
+    use itertools::Itertools;
+    use pretty_assertions::assert_eq;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    pub fn morton_code_interleaves_bits() {
+        assert_eq!(morton_code(0, 0), 0);
+        assert_eq!(morton_code(1, 0), 1);
+        assert_eq!(morton_code(0, 1), 2);
+        assert_eq!(morton_code(1, 1), 3);
+        assert_eq!(morton_code(2, 0), 4);
+        assert_eq!(morton_code(3, 5), 0b100111);
+    }
+
+    #[test]
+    pub fn tiles_start_at_the_centre_and_follow_z_order_within_rings() {
+        let tiles = Tile::centre_out(64, 64, 16);
+        let origins = tiles.iter().take(5).map(|t| (t.x, t.y)).collect_vec();
+        assert_eq!(
+            origins,
+            vec![(32, 32), (16, 16), (32, 16), (48, 16), (16, 32)]
+        );
+        assert_eq!(tiles.last().map(|t| (t.x, t.y)), Some((0, 48)));
+    }
+
+    #[test]
+    pub fn first_tile_contains_the_centre_pixel() {
+        for (width, height) in [(37, 21), (64, 64), (20, 10), (5, 3)] {
+            let first = Tile::centre_out(width, height, 8)[0];
+            assert!(first.pixels().contains(&(width / 2, height / 2)));
+        }
+    }
+
+    #[test]
+    pub fn tiles_cover_every_pixel_exactly_once() {
+        let tiles = Tile::centre_out(37, 21, 8);
+        let pixels = tiles.iter().flat_map(Tile::pixels).sorted().collect_vec();
+        let expected = (0..37).cartesian_product(0..21).sorted().collect_vec();
+        assert_eq!(pixels, expected);
+    }
+
+    #[test]
+    pub fn edge_tiles_are_clipped() {
+        let tiles = Tile::centre_out(20, 10, 16);
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(tiles[1].width, 4);
+        assert_eq!(tiles[1].height, 10);
+    }
+
+    #[test]
+    pub fn workers_take_tiles_in_order() {
+        let threads = 4;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let tiles = Tile::centre_out(64, 64, 8);
+        let started = Mutex::new(Vec::new());
+        pool.install(|| {
+            for_each_in_order(&tiles, |index, _| {
+                started.lock().unwrap().push(index);
+                thread::sleep(Duration::from_millis(20));
+            });
+        });
+        let started = started.into_inner().unwrap();
+        let first = started.iter().take(threads).copied().sorted().collect_vec();
+        assert_eq!(first, (0..threads).collect_vec());
+        assert_eq!(
+            started.into_iter().sorted().collect_vec(),
+            (0..tiles.len()).collect_vec()
+        );
+    }
+}
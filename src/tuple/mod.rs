@@ -250,6 +250,94 @@ impl Color {
             b: 0.,
         }
     }
+
+    // Relative luminance with Rec. 709 weights.
+    pub fn luminance(&self) -> f32 {
+        0.2126f32.mul_add(self.r, 0.7152f32.mul_add(self.g, 0.0722 * self.b))
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.is_ascii() {
+            return None;
+        }
+
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok().map(|v| f32::from(v) / 255.);
+        match digits.len() {
+            6 => Some(Self::new(
+                channel(&digits[0..2])?,
+                channel(&digits[2..4])?,
+                channel(&digits[4..6])?,
+            )),
+            3 => {
+                let short = |s: &str| channel(&s.repeat(2));
+                Some(Self::new(
+                    short(&digits[0..1])?,
+                    short(&digits[1..2])?,
+                    short(&digits[2..3])?,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let hue = hue.rem_euclid(360.);
+        let chroma = value * saturation;
+        let x = chroma * (1. - ((hue / 60.) % 2. - 1.).abs());
+        let m = value - chroma;
+
+        let (r, g, b) = match (hue / 60.) as u32 {
+            0 => (chroma, x, 0.),
+            1 => (x, chroma, 0.),
+            2 => (0., chroma, x),
+            3 => (0., x, chroma),
+            4 => (x, 0., chroma),
+            _ => (chroma, 0., x),
+        };
+
+        Self::new(r + m, g + m, b + m)
+    }
+
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+
+        let hue = if delta == 0. {
+            0.
+        } else if max == self.r {
+            60. * ((self.g - self.b) / delta).rem_euclid(6.)
+        } else if max == self.g {
+            60. * ((self.b - self.r) / delta + 2.)
+        } else {
+            60. * ((self.r - self.g) / delta + 4.)
+        };
+        let saturation = if max == 0. { 0. } else { delta / max };
+
+        (hue, saturation, max)
+    }
+
+    pub fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+
+    pub fn lerp_hsv(a: Self, b: Self, t: f32) -> Self {
+        let (h1, s1, v1) = a.to_hsv();
+        let (h2, s2, v2) = b.to_hsv();
+        let mut dh = h2 - h1;
+        if dh > 180. {
+            dh -= 360.;
+        } else if dh < -180. {
+            dh += 360.;
+        }
+
+        Self::from_hsv(
+            dh.mul_add(t, h1),
+            (s2 - s1).mul_add(t, s1),
+            (v2 - v1).mul_add(t, v1),
+        )
+    }
 }
 
 impl Eq for Color {}
@@ -426,6 +514,48 @@ mod tests {
         assert_eq!(a * b, Color::new(0.9, 0.2, 0.04));
     }
 
+    #[test_case("#1e90ff", Some(Color::new(30. / 255., 144. / 255., 1.)) ; "with hash")]
+    #[test_case("ff0000", Some(Color::new(1., 0., 0.)) ; "without hash")]
+    #[test_case("#0f0", Some(Color::new(0., 1., 0.)) ; "shorthand")]
+    #[test_case("#12345", None ; "wrong length")]
+    #[test_case("#zzzzzz", None ; "not hex")]
+    pub fn color_from_hex(input: &str, expected: Option<Color>) {
+        assert_eq!(Color::from_hex(input), expected);
+    }
+
+    #[test_case(0., 1., 1., Color::new(1., 0., 0.) ; "red")]
+    #[test_case(120., 1., 1., Color::new(0., 1., 0.) ; "green")]
+    #[test_case(240., 1., 0.5, Color::new(0., 0., 0.5) ; "dark blue")]
+    #[test_case(60., 0.5, 1., Color::new(1., 1., 0.5) ; "pale yellow")]
+    #[test_case(0., 0., 0.25, Color::new(0.25, 0.25, 0.25) ; "gray")]
+    pub fn color_from_hsv(h: f32, s: f32, v: f32, expected: Color) {
+        assert_eq!(Color::from_hsv(h, s, v), expected);
+    }
+
+    #[test]
+    pub fn hsv_round_trip() {
+        let c = Color::from_hex("#1e90ff").unwrap();
+        let (h, s, v) = c.to_hsv();
+        assert_eq!(Color::from_hsv(h, s, v), c);
+    }
+
+    #[test]
+    pub fn lerp_between_colors() {
+        let a = Color::black();
+        let b = Color::new(1., 0.5, 0.);
+        assert_eq!(Color::lerp(a, b, 0.), a);
+        assert_eq!(Color::lerp(a, b, 0.5), Color::new(0.5, 0.25, 0.));
+        assert_eq!(Color::lerp(a, b, 1.), b);
+    }
+
+    #[test]
+    pub fn hsv_lerp_takes_shortest_hue_path() {
+        let red = Color::from_hsv(350., 1., 1.);
+        let orange = Color::from_hsv(30., 1., 1.);
+        let (h, _, _) = Color::lerp_hsv(red, orange, 0.5).to_hsv();
+        assert!(approx_eq(h, 10.));
+    }
+
     #[test]
     pub fn reflect_at_45_degree() {
         let v = Vector::new(1., -1., 0.);
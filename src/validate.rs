@@ -0,0 +1,247 @@
+use std::fmt;
+
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::camera::Camera;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::Intersection;
+use crate::tuple::{Vector, EPSILON};
+use crate::world::World;
+
+const MAX_AMBIENT_PLUS_DIFFUSE: f32 = 1.5;
+const CAMERA_PROBES: usize = 9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    EmptyWorld,
+    NoLights,
+    LightInsideObject {
+        group: String,
+        object: Uuid,
+    },
+    DegenerateTransform(Uuid),
+    ExcessiveAlbedo {
+        object: Uuid,
+        ambient_plus_diffuse: f32,
+    },
+    CameraSeesNothing,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyWorld => write!(f, "world has no objects; the render will be black"),
+            Self::NoLights => write!(
+                f,
+                "world has no enabled lights; only ambient light will be visible"
+            ),
+            Self::LightInsideObject { group, object } => write!(
+                f,
+                "a light in group '{group}' is inside object {object}; move it out or it will only light the interior"
+            ),
+            Self::DegenerateTransform(id) => write!(
+                f,
+                "object {id} has a transform with zero scale along some axis; it will not be visible"
+            ),
+            Self::ExcessiveAlbedo {
+                object,
+                ambient_plus_diffuse,
+            } => write!(
+                f,
+                "object {object} has ambient + diffuse = {ambient_plus_diffuse}; values above {MAX_AMBIENT_PLUS_DIFFUSE} reflect more light than they receive"
+            ),
+            Self::CameraSeesNothing => write!(
+                f,
+                "camera does not see any object; check its position and look-at point"
+            ),
+        }
+    }
+}
+
+impl World {
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        if self.objects.is_empty() {
+            diagnostics.push(Diagnostic::EmptyWorld);
+        }
+        if self.lights().next().is_none() {
+            diagnostics.push(Diagnostic::NoLights);
+        }
+
+        for object in &self.objects {
+            if is_degenerate(object.get_transform()) {
+                diagnostics.push(Diagnostic::DegenerateTransform(*object.get_id()));
+            }
+
+            let material = object.get_material();
+            let ambient_plus_diffuse = material.ambient + material.diffuse;
+            if ambient_plus_diffuse > MAX_AMBIENT_PLUS_DIFFUSE {
+                diagnostics.push(Diagnostic::ExcessiveAlbedo {
+                    object: *object.get_id(),
+                    ambient_plus_diffuse,
+                });
+            }
+        }
+
+        for group in &self.light_groups {
+            for light in &group.lights {
+                // A ray leaving a point inside a closed object crosses its surface an odd number
+                // of times. Open surfaces such as planes and triangles have no inside to be in.
+                let ray = Ray::new(light.position, Vector::new(0.267, 0.534, 0.802));
+                for object in self.objects.iter().filter(|o| o.is_closed()) {
+                    let crossings = object
+                        .intersect(&ray)
+                        .map_or(0, |xs| xs.iter().filter(|i| i.t > 0.).count());
+                    if crossings % 2 == 1 {
+                        diagnostics.push(Diagnostic::LightInsideObject {
+                            group: group.name.clone(),
+                            object: *object.get_id(),
+                        });
+                    }
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    pub fn validate_view(&self, camera: &Camera) -> Vec<Diagnostic> {
+        let mut diagnostics = self.validate();
+        let columns = (0..CAMERA_PROBES).map(|i| i * (camera.hsize - 1) / (CAMERA_PROBES - 1));
+        let rows = (0..CAMERA_PROBES).map(|i| i * (camera.vsize - 1) / (CAMERA_PROBES - 1));
+        let sees_something = columns.cartesian_product(rows).any(|(x, y)| {
+            Intersection::get_hit(&self.intersect_world(&camera.pixel_center_ray(x, y))).is_some()
+        });
+        if !self.objects.is_empty() && !sees_something {
+            diagnostics.push(Diagnostic::CameraSeesNothing);
+        }
+        diagnostics
+    }
+}
+
+// A transform squashing some axis to nothing next to the others. Scales are compared with each
+// other rather than with a fixed threshold, so small objects with sensible proportions pass.
+fn is_degenerate(transform: &Matrix4) -> bool {
+    let scales = [
+        Vector::new(1., 0., 0.),
+        Vector::new(0., 1., 0.),
+        Vector::new(0., 0., 1.),
+    ]
+    .map(|axis| (*transform * axis).magnitude());
+    let largest = scales.into_iter().fold(0., f32::max);
+    transform.try_inverse().is_err() || scales.iter().any(|&s| s <= largest * EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::Camera;
+    use crate::light::PointLight;
+    use crate::material::Material;
+    use crate::matrix::Matrix4;
+    use crate::shape::{Shape, Sphere, Triangle};
+    use crate::tuple::{Color, Point, Vector};
+    use crate::validate::Diagnostic;
+    use crate::world::World;
+    use pretty_assertions::assert_eq;
+    use std::f32::consts::PI;
+
+    #[test]
+    pub fn default_world_is_valid() {
+        assert_eq!(World::default().validate(), vec![]);
+    }
+
+    #[test]
+    pub fn empty_world_without_lights() {
+        let mut w = World::new(PointLight::default(), vec![]);
+        w.light_groups.clear();
+        assert_eq!(
+            w.validate(),
+            vec![Diagnostic::EmptyWorld, Diagnostic::NoLights]
+        );
+    }
+
+    #[test]
+    pub fn light_inside_sphere() {
+        let s = Sphere::static_default();
+        let w = World::new(PointLight::new(Point::zero(), Color::white()), vec![s]);
+        assert_eq!(
+            w.validate(),
+            vec![Diagnostic::LightInsideObject {
+                group: "default".to_string(),
+                object: *s.get_id(),
+            }]
+        );
+    }
+
+    #[test]
+    pub fn small_objects_are_not_degenerate() {
+        let light = PointLight::new(Point::new(-10., 10., -10.), Color::white());
+        let w = World::new(light, vec![Sphere::new(Point::zero(), 0.02)]);
+        assert_eq!(w.validate(), vec![]);
+    }
+
+    #[test]
+    pub fn flattened_object_is_degenerate() {
+        let s = Sphere::static_default();
+        s.replace_transform(Matrix4::identity().scale(&Vector::new(1., 1e-7, 1.)))
+            .unwrap();
+        let light = PointLight::new(Point::new(-10., 10., -10.), Color::white());
+        let w = World::new(light, vec![s]);
+        assert_eq!(
+            w.validate(),
+            vec![Diagnostic::DegenerateTransform(*s.get_id())]
+        );
+    }
+
+    #[test]
+    pub fn light_under_an_open_surface_is_not_inside_it() {
+        let roof = Triangle::new(
+            Point::new(-10., 2., -10.),
+            Point::new(10., 2., -10.),
+            Point::new(0., 2., 10.),
+        );
+        let w = World::new(PointLight::new(Point::zero(), Color::white()), vec![roof]);
+        assert_eq!(w.validate(), vec![]);
+    }
+
+    #[test]
+    pub fn overly_bright_material() {
+        let s = Sphere::default_with_material(Material {
+            ambient: 1.,
+            diffuse: 1.,
+            ..Default::default()
+        });
+        let w = World::new(
+            PointLight::new(Point::new(-10., 10., -10.), Color::white()),
+            vec![s],
+        );
+        assert_eq!(
+            w.validate(),
+            vec![Diagnostic::ExcessiveAlbedo {
+                object: *s.get_id(),
+                ambient_plus_diffuse: 2.,
+            }]
+        );
+    }
+
+    #[test]
+    pub fn camera_looking_away_from_objects() {
+        let w = World::default();
+        let mut c = Camera::new(50, 50, PI / 3.);
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::new(0., 0., -10.),
+            Vector::new(0., 1., 0.),
+        );
+        assert_eq!(w.validate_view(&c), vec![Diagnostic::CameraSeesNothing]);
+
+        c.set_transform(
+            Point::new(0., 0., -5.),
+            Point::zero(),
+            Vector::new(0., 1., 0.),
+        );
+        assert_eq!(w.validate_view(&c), vec![]);
+    }
+}
@@ -1,17 +1,44 @@
-use crate::light::PointLight;
-use crate::material::Material;
+use crate::accel::{Accel, KdTree};
+use crate::arena::IntersectionBuffer;
+use crate::bounds::Bounds;
+use crate::error::Result;
+use crate::light::{LightGroup, LightLinks, PointLight, DEFAULT_LIGHT_GROUP};
+use crate::material::{Material, MaterialChannel};
 use crate::matrix::Matrix4;
-use crate::ray::Ray;
-use crate::shape::{Intersection, PrecomputedHit, Shape, Sphere};
+use crate::probes::ProbeGrid;
+use crate::ray::{refract, Ray};
+use crate::sampler;
+use crate::settings::{RenderSettings, ThreadPoolCache, Transmittance};
+use crate::shadow_cache;
+use crate::shape::{Intersection, MediumStack, Plane, PrecomputedHit, Shape, Sphere};
+use crate::sky::Sky;
+use crate::stats;
 use crate::tuple::{Color, Point, Vector};
-use derive_more::Constructor;
-use itertools::Itertools;
 use nalgebra::matrix;
+use rand::Rng;
+use rayon::ThreadPool;
+use smallvec::SmallVec;
+use std::sync::Arc;
+use uuid::Uuid;
 
-#[derive(Constructor)]
 pub struct World {
-    pub light_source: PointLight,
+    pub light_groups: Vec<LightGroup>,
     pub objects: Vec<&'static dyn Shape>,
+    pub settings: RenderSettings,
+    pub sky: Option<Sky>,
+    acceleration: Option<KdTree>,
+    probes: Option<ProbeGrid>,
+    thread_pool: ThreadPoolCache,
+}
+
+// The nearest surface along a ray cast with `World::raycast`. The normal faces back towards the
+// ray's origin.
+#[derive(Copy, Clone)]
+pub struct RayHit {
+    pub point: Point,
+    pub normal: Vector,
+    pub distance: f32,
+    pub object: &'static dyn Shape,
 }
 
 impl Default for World {
@@ -26,109 +53,523 @@ impl Default for World {
             .set_transform(&Matrix4::identity().scale(&Vector::new(0.5, 0.5, 0.5)));
 
         Self {
-            light_source: PointLight::new(
-                crate::tuple::Point::new(-10., 10., -10.),
-                crate::tuple::Color::new(1., 1., 1.),
-            ),
+            light_groups: vec![LightGroup::new(
+                DEFAULT_LIGHT_GROUP,
+                vec![PointLight::new(
+                    crate::tuple::Point::new(-10., 10., -10.),
+                    crate::tuple::Color::new(1., 1., 1.),
+                )],
+            )],
             objects: vec![s1, s2],
+            settings: RenderSettings::default(),
+            sky: None,
+            acceleration: None,
+            probes: None,
+            thread_pool: ThreadPoolCache::default(),
         }
     }
 }
 
 impl World {
-    fn intersect_world(&self, r: &Ray) -> Vec<Intersection> {
-        self.objects
+    pub fn new(light_source: PointLight, objects: Vec<&'static dyn Shape>) -> Self {
+        Self {
+            light_groups: vec![LightGroup::new(DEFAULT_LIGHT_GROUP, vec![light_source])],
+            objects,
+            settings: RenderSettings::default(),
+            sky: None,
+            acceleration: None,
+            probes: None,
+            thread_pool: ThreadPoolCache::default(),
+        }
+    }
+
+    // A world with no objects and no lights.
+    pub fn empty() -> Self {
+        Self {
+            light_groups: vec![],
+            objects: vec![],
+            settings: RenderSettings::default(),
+            sky: None,
+            acceleration: None,
+            probes: None,
+            thread_pool: ThreadPoolCache::default(),
+        }
+    }
+
+    pub fn add_light(&mut self, group: &str, light: PointLight) {
+        match self.light_groups.iter_mut().find(|g| g.name == group) {
+            Some(existing) => existing.lights.push(light),
+            None => self.light_groups.push(LightGroup::new(group, vec![light])),
+        }
+    }
+
+    pub fn lights(&self) -> impl Iterator<Item = &PointLight> {
+        self.light_groups
             .iter()
-            .map(|&x| x.intersect(r))
-            .filter(Option::is_some)
-            .flatten()
-            .flatten()
-            .sorted()
-            .collect_vec()
+            .filter(|g| g.enabled)
+            .flat_map(|g| g.lights.iter())
+    }
+
+    pub fn build_acceleration(&mut self) {
+        let _span =
+            tracing::info_span!("build_acceleration", objects = self.objects.len()).entered();
+        self.acceleration = match self.settings.accel {
+            Accel::None => None,
+            Accel::KdTree => Some(KdTree::build(&self.objects)),
+        };
+    }
+
+    // The pool renders of this world run on, built from the settings on first use and reused
+    // for as long as the thread settings stay the same.
+    pub fn thread_pool(&self) -> Result<Arc<ThreadPool>> {
+        Ok(self.thread_pool.get(&self.settings)?)
+    }
+
+    pub const fn acceleration(&self) -> Option<&KdTree> {
+        self.acceleration.as_ref()
+    }
+
+    // Whether the acceleration structure matches the settings and the objects. The tree is a
+    // snapshot taken by `build_acceleration`, so objects added or replaced since are missing
+    // from it until it is rebuilt.
+    pub fn acceleration_is_current(&self) -> bool {
+        match (self.settings.accel, &self.acceleration) {
+            (Accel::None, None) => true,
+            (Accel::KdTree, Some(tree)) => tree.is_built_from(&self.objects),
+            _ => false,
+        }
+    }
+
+    // Bakes the irradiance probes configured in the settings over the bounds of every finite
+    // object, or drops them when probes are turned off. Probes see the world as it is shaded
+    // without them. The probes are traced on the world's render threads.
+    pub fn bake_probes(&mut self) -> Result<()> {
+        self.probes = None;
+        let Some(probes) = self.settings.irradiance_probes else {
+            return Ok(());
+        };
+        let bounds = self
+            .objects
+            .iter()
+            .map(|o| o.get_bounds())
+            .filter(|b| b.is_finite() && !b.is_empty())
+            .fold(Bounds::empty(), |acc, b| acc.merge(&b));
+        if bounds.is_empty() {
+            return Ok(());
+        }
+        let pool = self.thread_pool()?;
+        let grid =
+            pool.install(|| ProbeGrid::bake(self, bounds, probes.resolution, probes.samples));
+        self.probes = Some(grid);
+        Ok(())
+    }
+
+    // Moves `shape` straight down until its bounding box rests on the first object below it and
+    // returns that object. Rays are cast from the centre and the corners of the bottom of the
+    // box, so shapes hanging over an edge still land on it. Shapes with nothing below them, or
+    // with unbounded extents, are left where they are.
+    pub fn drop_onto(&self, shape: &mut dyn Shape) -> Result<Option<&'static dyn Shape>> {
+        let bounds = shape.get_bounds();
+        if !bounds.is_finite() || bounds.is_empty() {
+            return Ok(None);
+        }
+
+        let (min, max) = (bounds.min, bounds.max);
+        let centre = Point::new((min.x + max.x) / 2., min.y, (min.z + max.z) / 2.);
+        let origins = [
+            centre,
+            Point::new(min.x, min.y, min.z),
+            Point::new(min.x, min.y, max.z),
+            Point::new(max.x, min.y, min.z),
+            Point::new(max.x, min.y, max.z),
+        ];
+        let id = *shape.get_id();
+        let landing = origins
+            .into_iter()
+            .filter_map(|origin| {
+                let xs = self.intersect_world(&Ray::new(origin, Vector::new(0., -1., 0.)));
+                xs.iter()
+                    .filter(|x| x.t >= 0. && x.object.get_id() != &id)
+                    .min()
+                    .copied()
+            })
+            .min();
+
+        let Some(landing) = landing else {
+            return Ok(None);
+        };
+        shape.replace_transform(
+            shape
+                .get_transform()
+                .translate(&Vector::new(0., -landing.t, 0.)),
+        )?;
+        Ok(Some(landing.object))
+    }
+
+    // Finds the nearest surface in front of `origin`. `direction` doesn't have to be normalized;
+    // `distance` is always in world units.
+    pub fn raycast(&self, origin: Point, direction: Vector) -> Option<RayHit> {
+        let ray = Ray::new(origin, direction.normalize());
+        let xs = self.intersect_world(&ray);
+        let hit = Intersection::get_hit(&xs)?;
+        let comps = hit.precompute_hit_with_indices(&ray, (1., 1.));
+        Some(RayHit {
+            point: comps.point,
+            normal: comps.normal,
+            distance: hit.t,
+            object: hit.object,
+        })
+    }
+
+    // Index pairs of every two objects that interpenetrate, as decided by `Shape::overlaps`.
+    pub fn find_overlaps(&self) -> Vec<(usize, usize)> {
+        let mut overlaps = vec![];
+        for (i, a) in self.objects.iter().enumerate() {
+            for (j, b) in self.objects.iter().enumerate().skip(i + 1) {
+                if a.overlaps(*b) {
+                    overlaps.push((i, j));
+                }
+            }
+        }
+        overlaps
+    }
+
+    pub(crate) fn intersect_world(&self, r: &Ray) -> IntersectionBuffer {
+        let mut xs = IntersectionBuffer::take();
+        let candidates = self.candidates(r);
+        stats::record_ray(candidates.len());
+        xs.extend(
+            candidates
+                .into_iter()
+                .filter_map(|x| x.intersect(r))
+                .flatten(),
+        );
+        xs.sort();
+        xs
     }
 
-    fn shade_hit(&self, comps: &PrecomputedHit, remaining_reflections: i32) -> Color {
-        let shadowed = self.is_shadowed(&comps.over_point);
+    // Objects `r` might hit.
+    fn candidates(&self, r: &Ray) -> SmallVec<[&'static dyn Shape; 16]> {
+        // A tree missing some of the objects would hide them; fall back to testing them all.
+        let tree = self
+            .acceleration
+            .as_ref()
+            .filter(|tree| tree.len() == self.objects.len());
+        match tree {
+            Some(tree) => tree.candidates(r),
+            None => self.objects.iter().copied().collect(),
+        }
+    }
 
-        let surface = self.light_source.calculate_lighting(
-            comps.intersection.object.get_material(),
+    fn shade_hit(&self, comps: &PrecomputedHit, path: Path) -> Color {
+        let material = comps.intersection.object.get_material();
+        // The colour comes from the material itself, since the sampled copy has no pattern.
+        let surface_color = material.surface_color(
             comps.intersection.object,
             &comps.over_point,
-            &comps.eye,
-            &comps.normal,
-            shadowed,
+            comps.footprint.as_ref(),
         );
-        let reflected = self.reflected_color(comps, remaining_reflections);
-        let refracted = self.refracted_color(comps, remaining_reflections);
-        let material = comps.intersection.object.get_material();
-        if material.reflective > 0.0 && material.transparency > 0.0 {
+        let sampled = material.sampled_at(comps.intersection.object, &comps.over_point);
+        let material = sampled.as_ref().unwrap_or(material);
+        // Baked probes provide the indirect light in place of each light's ambient term.
+        let indirect = self.probes.as_ref().map(|probes| {
+            surface_color.hadamard_product(&probes.irradiance_at(&comps.over_point))
+                * material.diffuse
+        });
+        let surface = self
+            .lights()
+            .enumerate()
+            .map(|(index, light)| {
+                let (ambient, direct) = light.lighting_components(
+                    material,
+                    surface_color,
+                    comps.intersection.object,
+                    &comps.over_point,
+                    &comps.eye,
+                    &comps.normal,
+                );
+                let ambient = if indirect.is_some() {
+                    Color::black()
+                } else {
+                    ambient
+                };
+                let shadow = || self.light_filter(light, &comps.over_point);
+                let filter = match self.settings.shadow_cache {
+                    Some(cell) => shadow_cache::get_or_insert_with(
+                        *comps.intersection.object.get_id(),
+                        &comps.over_point,
+                        cell,
+                        index,
+                        shadow,
+                    ),
+                    None => shadow(),
+                };
+                if filter == Color::black() {
+                    ambient
+                } else {
+                    ambient + direct * filter
+                }
+            })
+            .fold(indirect.unwrap_or_else(Color::black), |acc, c| acc + c);
+        let reflected = self.reflected_color(comps, path);
+        let refracted = self.refracted_color(comps, path);
+        if material.transparency > 0.0 {
             let reflectance = comps.schlick_reflectance();
-            return surface + reflected * reflectance + refracted * (1.0 - reflectance);
+            if reflectance >= NEAR_TOTAL_REFLECTANCE {
+                stats::record_internal_reflection();
+            }
+            if material.reflective > 0.0 {
+                return surface + reflected * reflectance + refracted * (1.0 - reflectance);
+            }
         }
 
         surface + reflected + refracted
     }
 
     pub fn color_at(&self, r: &Ray, remaining_reflections: i32) -> Color {
+        self.trace(r, Path::new(remaining_reflections))
+    }
+
+    fn trace(&self, r: &Ray, path: Path) -> Color {
         let xs = self.intersect_world(r);
 
-        if let Some(hit) = Intersection::get_hit(&xs) {
-            let comps = hit.precompute_hit(r, &xs);
-            self.shade_hit(&comps, remaining_reflections)
+        if let Some(index) = Intersection::hit_index(&xs) {
+            let media = path.media.unwrap_or_else(|| MediumStack::behind(&xs));
+            let indices = media.indices(xs[index].object);
+            let comps = xs[index].precompute_hit_with_indices(r, indices);
+            let path = Path {
+                media: Some(media),
+                ..path
+            };
+            let color = self.shade_hit(&comps, path);
+            match comps
+                .intersection
+                .object
+                .downcast_ref::<Plane>()
+                .and_then(Plane::horizon_fade)
+            {
+                Some(fade) => {
+                    let distance = (comps.point - r.origin).magnitude();
+                    Color::lerp(color, self.background(r), fade.amount(distance))
+                }
+                None => color,
+            }
         } else {
-            Color::new(0., 0., 0.)
+            self.background(r)
         }
     }
 
-    pub fn is_shadowed(&self, p: &Point) -> bool {
-        let v = self.light_source.position - p;
+    fn background(&self, r: &Ray) -> Color {
+        self.sky
+            .as_ref()
+            .map_or_else(Color::black, |sky| sky.radiance(&r.direction))
+    }
+
+    pub fn is_shadowed(&self, light_position: &Point, p: &Point) -> bool {
+        self.shadow_filter(light_position, p) == Color::black()
+    }
+
+    // Light reaching `p` after passing through every occluder on the way. With translucent
+    // shadows enabled, transparent objects tint it by their color; otherwise any hit blocks it.
+    pub fn shadow_filter(&self, light_position: &Point, p: &Point) -> Color {
+        self.occlusion(light_position, p, &LightLinks::All)
+    }
+
+    // The shadow filter for a specific light, ignoring occluders the light isn't linked to.
+    pub fn light_filter(&self, light: &PointLight, p: &Point) -> Color {
+        self.occlusion(&light.position, p, &light.links)
+    }
+
+    fn occlusion(&self, light_position: &Point, p: &Point, links: &LightLinks) -> Color {
+        let v = *light_position - p;
         let distance = v.magnitude();
-        let direction = v.normalize();
+        let r = Ray::new(*p, v.normalize());
 
-        let r = Ray::new(*p, direction);
-        let intersections = self.intersect_world(&r);
-        let h = Intersection::get_hit(&intersections);
+        let mut paths: SmallVec<[Color; 16]> = match self.settings.shadow_transmittance {
+            Transmittance::Exact => SmallVec::from_elem(Color::white(), 1),
+            Transmittance::Stochastic { samples } => {
+                SmallVec::from_elem(Color::white(), samples.max(1))
+            }
+        };
+        let path_count = paths.len();
+        let mut seen: SmallVec<[Uuid; 8]> = SmallVec::new();
+        // Occluders are tested one object at a time rather than through `intersect_world`, so
+        // the walk can stop at the first opaque one or once every path has been absorbed.
+        let candidates = self.candidates(&r);
+        let mut tests = 0;
+        for candidate in candidates {
+            tests += 1;
+            let Some(mut xs) = candidate.intersect(&r) else {
+                continue;
+            };
+            xs.sort();
+            for i in &xs {
+                if i.t < 0.
+                    || i.t >= distance
+                    || seen.contains(i.object.get_id())
+                    || !links.affects(i.object.get_id())
+                {
+                    continue;
+                }
+                let material = i.object.get_material();
+                let transparency =
+                    material.value_at(MaterialChannel::Transparency, i.object, &r.position(i.t));
+                if transparency <= 0. || !self.settings.translucent_shadows {
+                    stats::record_ray(tests);
+                    return Color::black();
+                }
+                let transmittance = material.color * transparency;
+                seen.push(*i.object.get_id());
 
-        h.is_some() && h.unwrap().t < distance
+                match self.settings.shadow_transmittance {
+                    Transmittance::Exact => paths[0] = paths[0] * transmittance,
+                    Transmittance::Stochastic { .. } => {
+                        // Each path survives with the transmittance's largest channel and is
+                        // reweighted so the average stays unbiased.
+                        let survival = transmittance.r.max(transmittance.g).max(transmittance.b);
+                        let survival = survival.min(1.);
+                        sampler::with_rng(|rng| paths.retain(|_| rng.gen::<f32>() < survival));
+                        for path in &mut paths {
+                            *path = *path * transmittance * (1. / survival);
+                        }
+                        if paths.is_empty() {
+                            stats::record_ray(tests);
+                            return Color::black();
+                        }
+                    }
+                }
+            }
+        }
+        stats::record_ray(tests);
+        paths.iter().fold(Color::black(), |acc, &c| acc + c) * (1. / path_count as f32)
     }
-    fn reflected_color(&self, comps: &PrecomputedHit, remaining_reflections: i32) -> Color {
-        if remaining_reflections <= 0 {
+    // Follows a secondary ray whose contribution is scaled by `weight`. Without Russian roulette
+    // the path stops once `remaining` runs out. With it, paths past the minimum depth survive
+    // with a probability tied to their throughput and are reweighted to stay unbiased.
+    fn continue_path(&self, ray: &Ray, path: Path, weight: f32) -> Color {
+        let next = path.bounce(weight);
+        let Some(min_depth) = self.settings.russian_roulette else {
+            if path.remaining <= 0 {
+                return Color::black();
+            }
+            return self.trace(ray, next) * weight;
+        };
+
+        if path.depth >= MAX_ROULETTE_DEPTH {
+            return Color::black();
+        }
+        if path.depth < min_depth {
+            return self.trace(ray, next) * weight;
+        }
+        let survival = next.throughput.clamp(0.05, 0.95);
+        if sampler::with_rng(|rng| rng.gen::<f32>()) >= survival {
             return Color::black();
         }
+        self.trace(ray, next) * (weight / survival)
+    }
 
-        if comps.intersection.object.get_material().reflective == 0.0 {
+    fn reflected_color(&self, comps: &PrecomputedHit, path: Path) -> Color {
+        let object = comps.intersection.object;
+        let reflective =
+            object
+                .get_material()
+                .value_at(MaterialChannel::Reflective, object, &comps.over_point);
+        if reflective == 0.0 || exceeds(path.reflections, self.settings.max_reflection_depth) {
             return Color::black();
         }
 
-        let reflected_ray = Ray::new(comps.over_point, comps.reflected_vector);
-        let color = self.color_at(&reflected_ray, remaining_reflections - 1);
-        color * comps.intersection.object.get_material().reflective
+        let mut reflected_ray = Ray::new(comps.over_point, comps.reflected_vector);
+        if let (Some(differential), Some(footprint)) = (comps.differential, comps.footprint) {
+            reflected_ray = reflected_ray.with_differential(differential.reflect(
+                &comps.point,
+                &comps.normal,
+                &footprint,
+            ));
+        }
+        let path = Path {
+            reflections: path.reflections + 1,
+            ..path
+        };
+        self.continue_path(&reflected_ray, path, reflective)
     }
 
-    fn refracted_color(&self, comps: &PrecomputedHit, bounces_remaining: i32) -> Color {
-        if bounces_remaining == 0 {
+    fn refracted_color(&self, comps: &PrecomputedHit, path: Path) -> Color {
+        let object = comps.intersection.object;
+        let transparency = object.get_material().value_at(
+            MaterialChannel::Transparency,
+            object,
+            &comps.over_point,
+        );
+        if transparency == 0.0 || exceeds(path.refractions, self.settings.max_refraction_depth) {
             return Color::black();
         }
 
-        if comps.intersection.object.get_material().transparency == 0.0 {
+        // Open surfaces are thin panes that the ray leaves as soon as it enters, so it carries on
+        // undeviated.
+        let n_ratio = if object.is_closed() {
+            comps.n1 / comps.n2
+        } else {
+            1.
+        };
+        let Some(direction) = refract(&comps.eye, &comps.normal, n_ratio) else {
             return Color::black();
+        };
+
+        let mut refracted_ray = Ray::new(comps.under_point, direction);
+        if let (Some(differential), Some(footprint)) = (comps.differential, comps.footprint) {
+            if let Some(differential) =
+                differential.refract(&comps.point, &comps.normal, &footprint, n_ratio)
+            {
+                refracted_ray = refracted_ray.with_differential(differential);
+            }
         }
+        let path = Path {
+            refractions: path.refractions + 1,
+            media: path.media.map(|media| media.crossing(object)),
+            ..path
+        };
+        self.continue_path(&refracted_ray, path, transparency)
+    }
+}
 
-        let n_ratio = comps.n1 / comps.n2;
-        let cos_i = comps.eye.dot(&comps.normal);
-        let sin2_t = n_ratio.powi(2) * cos_i.mul_add(-cos_i, 1.0);
+const MAX_ROULETTE_DEPTH: i32 = 64;
+const NEAR_TOTAL_REFLECTANCE: f32 = 0.99;
 
-        if sin2_t > 1.0 {
-            return Color::black();
-        }
+fn exceeds(count: i32, limit: Option<i32>) -> bool {
+    limit.is_some_and(|limit| count >= limit)
+}
 
-        let cos_t = (1.0 - sin2_t).sqrt();
-        let direction = comps.normal * n_ratio.mul_add(cos_i, -cos_t) - comps.eye * n_ratio;
-        let refracted_ray = Ray::new(comps.under_point, direction);
-        let color = self.color_at(&refracted_ray, bounces_remaining - 1)
-            * comps.intersection.object.get_material().transparency;
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Path {
+    remaining: i32,
+    depth: i32,
+    reflections: i32,
+    refractions: i32,
+    throughput: f32,
+    // The media the ray travels through. `None` until the first hit, which works it out from the
+    // intersections behind the ray's origin.
+    media: Option<MediumStack>,
+}
 
-        color
+impl Path {
+    const fn new(remaining: i32) -> Self {
+        Self {
+            remaining,
+            depth: 0,
+            reflections: 0,
+            refractions: 0,
+            throughput: 1.,
+            media: None,
+        }
+    }
+
+    fn bounce(self, weight: f32) -> Self {
+        Self {
+            remaining: self.remaining - 1,
+            depth: self.depth + 1,
+            throughput: self.throughput * weight,
+            ..self
+        }
     }
 }
 
@@ -157,16 +598,24 @@ impl Matrix4 {
 
 #[cfg(test)]
 mod tests {
-    use crate::light::PointLight;
-    use crate::material::Material;
+    use crate::accel::Accel;
+    use crate::camera::Camera;
+    use crate::error::Error;
+    use crate::light::{LightGroup, LightLinks, PointLight, DEFAULT_LIGHT_GROUP};
+    use crate::material::{Material, MaterialChannel};
     use crate::matrix::Matrix4;
-    use crate::pattern::TestPattern;
+    use crate::pattern::{Checkers, TestPattern};
     use crate::ray::Ray;
+    use crate::settings::{ProbeSettings, Transmittance};
     use crate::shape::{Intersection, Plane, Shape, Sphere};
+    use crate::sky::Sky;
+    use crate::stats;
     use crate::tuple::{Color, Point, Vector};
+    use crate::world::Path;
     use crate::world::World;
     use nalgebra::matrix;
-    use pretty_assertions::assert_eq;
+    use pretty_assertions::{assert_eq, assert_ne};
+    use std::f32::consts::PI;
     use test_case::test_case;
 
     #[test]
@@ -184,6 +633,40 @@ mod tests {
         assert_eq!(xs[3].t, 6.);
     }
 
+    #[test]
+    pub fn intersect_world_with_kd_tree() {
+        let mut w = World::default();
+        w.settings.accel = Accel::KdTree;
+        w.build_acceleration();
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = w.intersect_world(&r);
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[0].t, 4.);
+        assert_eq!(xs[1].t, 4.5);
+        assert_eq!(xs[2].t, 5.5);
+        assert_eq!(xs[3].t, 6.);
+    }
+
+    #[test]
+    pub fn objects_added_after_building_the_tree_are_still_hit() {
+        let mut w = World::default();
+        w.settings.accel = Accel::KdTree;
+        assert!(!w.acceleration_is_current());
+        w.build_acceleration();
+        assert!(w.acceleration_is_current());
+
+        w.objects.push(Sphere::new(Point::new(0., 0., -3.), 0.5));
+        assert!(!w.acceleration_is_current());
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(w.intersect_world(&r).len(), 6);
+        let camera = Camera::new(4, 4, PI / 2.);
+        assert!(matches!(camera.render(&w), Err(Error::StaleAcceleration)));
+
+        w.build_acceleration();
+        assert!(w.acceleration_is_current());
+        assert!(camera.render(&w).is_ok());
+    }
+
     #[test]
     pub fn shading_intersection() {
         let w = World::default();
@@ -194,17 +677,20 @@ mod tests {
         let shape = w.objects[0];
         let i = crate::shape::Intersection::new(4., shape);
         let comps = i.precompute_hit(&r, &[i]);
-        let c = w.shade_hit(&comps, 1);
+        let c = w.shade_hit(&comps, Path::new(1));
         assert_eq!(c, crate::tuple::Color::new(0.38066, 0.47582, 0.28549));
     }
 
     #[test]
     pub fn shading_intersection_from_inside() {
         let w = World {
-            light_source: PointLight::new(
-                crate::tuple::Point::new(0., 0.25, 0.),
-                crate::tuple::Color::new(1., 1., 1.),
-            ),
+            light_groups: vec![LightGroup::new(
+                DEFAULT_LIGHT_GROUP,
+                vec![PointLight::new(
+                    crate::tuple::Point::new(0., 0.25, 0.),
+                    crate::tuple::Color::new(1., 1., 1.),
+                )],
+            )],
             ..Default::default()
         };
         let r = crate::ray::Ray::new(
@@ -214,7 +700,7 @@ mod tests {
         let shape = w.objects[1];
         let i = crate::shape::Intersection::new(0.5, shape);
         let comps = i.precompute_hit(&r, &[i]);
-        let c = w.shade_hit(&comps, 1);
+        let c = w.shade_hit(&comps, Path::new(1));
         assert_eq!(c, crate::tuple::Color::new(0.90498, 0.90498, 0.90498));
     }
 
@@ -313,7 +799,8 @@ mod tests {
     #[test_case(Point::new(- 2., 2., - 2.), false; "no shadow when object is behind point")]
     pub fn no_shadow_when_nothing_is_collinear_with_point_and_light(p: Point, expected: bool) {
         let w = World::default();
-        assert_eq!(w.is_shadowed(&p), expected);
+        let light = w.lights().next().unwrap();
+        assert_eq!(w.is_shadowed(&light.position, &p), expected);
     }
 
     #[test]
@@ -324,12 +811,13 @@ mod tests {
         let light = PointLight::new(Point::new(0., 0., -10.), Color::new(1., 1., 1.));
         let w = World {
             objects: vec![s1, s2],
-            light_source: light,
+            light_groups: vec![LightGroup::new(DEFAULT_LIGHT_GROUP, vec![light])],
+            ..Default::default()
         };
         let r = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
         let i = Intersection::new(4., s2);
         let comps = i.precompute_hit(&r, &[i]);
-        let c = w.shade_hit(&comps, 1);
+        let c = w.shade_hit(&comps, Path::new(1));
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -353,7 +841,7 @@ mod tests {
         let r = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
         let i = Intersection::new(1.0, s2);
         let comps = i.precompute_hit(&r, &[i]);
-        let color = w.reflected_color(&comps, 1);
+        let color = w.reflected_color(&comps, Path::new(1));
         assert_eq!(color, Color::black());
     }
 
@@ -373,7 +861,7 @@ mod tests {
         );
         let i = Intersection::new(2.0_f32.sqrt(), plane);
         let comps = i.precompute_hit(&r, &[i]);
-        let color = w.reflected_color(&comps, 1);
+        let color = w.reflected_color(&comps, Path::new(1));
         assert_eq!(color, Color::new(0.19033, 0.23791, 0.142_749));
     }
 
@@ -393,7 +881,7 @@ mod tests {
         );
         let i = Intersection::new(2.0_f32.sqrt(), plane);
         let comps = i.precompute_hit(&r, &[i]);
-        let color = w.shade_hit(&comps, 1);
+        let color = w.shade_hit(&comps, Path::new(1));
         assert_eq!(color, Color::new(0.87675, 0.92434, 0.82917));
     }
 
@@ -411,8 +899,61 @@ mod tests {
         .set_transform(Matrix4::identity().translate(&Vector::new(0., 1., 0.)));
         let w = World {
             objects: vec![lower, upper],
-            light_source: PointLight::new(Point::new(0., 0., 0.), Color::new(1., 1., 1.)),
+            light_groups: vec![LightGroup::new(
+                DEFAULT_LIGHT_GROUP,
+                vec![PointLight::new(
+                    Point::new(0., 0., 0.),
+                    Color::new(1., 1., 1.),
+                )],
+            )],
+            ..Default::default()
+        };
+        let r = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 1., 0.));
+        let _ = w.color_at(&r, 1);
+    }
+
+    #[test]
+    pub fn russian_roulette_is_unbiased() {
+        let plane = Plane::default_with_material(Material {
+            reflective: 0.5,
+            ..Default::default()
+        })
+        .set_transform(Matrix4::identity().translate(&Vector::new(0., -1., 0.)));
+        let mut w = World::default();
+        w.objects.push(plane);
+        w.settings.russian_roulette = Some(0);
+
+        let r = Ray::new(
+            Point::new(0., 0., -3.),
+            Vector::new(0., -(2.0_f32.sqrt()) / 2., (2.0_f32.sqrt()) / 2.),
+        );
+        let i = Intersection::new(2.0_f32.sqrt(), plane);
+        let comps = i.precompute_hit(&r, &[i]);
+        let runs = 4000;
+        let mean = (0..runs)
+            .map(|_| w.shade_hit(&comps, Path::new(1)))
+            .fold(Color::black(), |acc, c| acc + c)
+            * (1. / runs as f32);
+        assert!((mean.g - 0.92434).abs() < 0.01, "{mean:?}");
+    }
+
+    #[test]
+    pub fn russian_roulette_terminates_between_perfect_mirrors() {
+        let lower = Plane::default_with_material(Material {
+            reflective: 1.0,
+            ..Default::default()
+        })
+        .set_transform(Matrix4::identity().translate(&Vector::new(0., -1., 0.)));
+        let upper = Plane::default_with_material(Material {
+            reflective: 1.0,
+            ..Default::default()
+        })
+        .set_transform(Matrix4::identity().translate(&Vector::new(0., 1., 0.)));
+        let mut w = World {
+            objects: vec![lower, upper],
+            ..Default::default()
         };
+        w.settings.russian_roulette = Some(2);
         let r = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 1., 0.));
         let _ = w.color_at(&r, 1);
     }
@@ -432,7 +973,7 @@ mod tests {
         );
         let i = Intersection::new(2.0_f32.sqrt(), plane);
         let comps = i.precompute_hit(&r, &[i]);
-        let color = w.reflected_color(&comps, 0);
+        let color = w.reflected_color(&comps, Path::new(0));
         assert_eq!(color, Color::black());
     }
 
@@ -443,26 +984,29 @@ mod tests {
     #[test_case(4, 2.5, 1.5)]
     #[test_case(5, 1.5, 1.0)]
     pub fn finding_n1_and_n2_at_various_intersections(index: usize, n1: f32, n2: f32) {
-        let A = Sphere::static_glass_sphere();
-        A.transform = Matrix4::identity().scale(&Vector::new(2., 2., 2.));
-        A.material.refractive_index = 1.5;
+        let a = Sphere::static_glass_sphere();
+        a.replace_transform(Matrix4::identity().scale(&Vector::new(2., 2., 2.)))
+            .unwrap();
+        a.common.material.refractive_index = 1.5;
 
-        let B = Sphere::static_glass_sphere();
-        B.transform = Matrix4::identity().translate(&Vector::new(0., 0., -0.25));
-        B.material.refractive_index = 2.0;
+        let b = Sphere::static_glass_sphere();
+        b.replace_transform(Matrix4::identity().translate(&Vector::new(0., 0., -0.25)))
+            .unwrap();
+        b.common.material.refractive_index = 2.0;
 
-        let C = Sphere::static_glass_sphere();
-        C.transform = Matrix4::identity().translate(&Vector::new(0., 0., 0.25));
-        C.material.refractive_index = 2.5;
+        let c = Sphere::static_glass_sphere();
+        c.replace_transform(Matrix4::identity().translate(&Vector::new(0., 0., 0.25)))
+            .unwrap();
+        c.common.material.refractive_index = 2.5;
 
         let ray = Ray::new(Point::new(0., 0., -4.), Vector::new(0., 0., 1.));
         let xs = vec![
-            Intersection::new(2.0, A),
-            Intersection::new(2.75, B),
-            Intersection::new(3.25, C),
-            Intersection::new(4.75, B),
-            Intersection::new(5.25, C),
-            Intersection::new(6.0, A),
+            Intersection::new(2.0, a),
+            Intersection::new(2.75, b),
+            Intersection::new(3.25, c),
+            Intersection::new(4.75, b),
+            Intersection::new(5.25, c),
+            Intersection::new(6.0, a),
         ];
         let comps = xs[index].precompute_hit(&ray, &xs);
         assert_eq!(comps.n1, n1);
@@ -478,7 +1022,7 @@ mod tests {
             Intersection::new(6., w.objects[0]),
         ];
         let comps = xs[0].precompute_hit(&r, &xs);
-        let color = w.refracted_color(&comps, 5);
+        let color = w.refracted_color(&comps, Path::new(5));
         assert_eq!(color, Color::black());
     }
 
@@ -499,7 +1043,7 @@ mod tests {
             Intersection::new(6., w.objects[0]),
         ];
         let comps = xs[0].precompute_hit(&r, &xs);
-        let color = w.refracted_color(&comps, 0);
+        let color = w.refracted_color(&comps, Path::new(0));
         assert_eq!(color, Color::black());
     }
 
@@ -524,36 +1068,58 @@ mod tests {
             Intersection::new(sqrt2over2, w.objects[0]),
         ];
         let comps = xs[1].precompute_hit(&r, &xs);
-        let color = w.refracted_color(&comps, 5);
+        let color = w.refracted_color(&comps, Path::new(5));
         assert_eq!(color, Color::black());
     }
 
+    #[test]
+    pub fn channel_patterns_keep_the_colour_pattern() {
+        // Red and blue tiles where only the blue ones are glossy.
+        let floor = Plane::default_with_material(Material {
+            pattern: Some(Checkers::new(
+                Color::new(1., 0., 0.),
+                Color::new(0., 0., 1.),
+            )),
+            channels: vec![(
+                MaterialChannel::Specular,
+                Checkers::new(Color::black(), Color::white()),
+            )],
+            ..Default::default()
+        });
+        let w = World::new(
+            PointLight::new(Point::new(0.5, 10., 0.5), Color::white()),
+            vec![floor],
+        );
+        let r = Ray::new(Point::new(0.5, 1., 0.5), Vector::new(0., -1., 0.));
+        assert_eq!(w.color_at(&r, 1), Color::new(1., 0., 0.));
+    }
+
     #[test]
     pub fn refracted_color_with_refracted_ray() {
         let r = Ray::new(Point::new(0., 0., 0.1), Vector::new(0., 1., 0.));
-        let A = Sphere::default_with_material(Material {
+        let a = Sphere::default_with_material(Material {
             pattern: Some(TestPattern::new()),
             ambient: 1.0,
             ..Default::default()
         });
-        let B = Sphere::default_with_material(Material {
+        let b = Sphere::default_with_material(Material {
             transparency: 1.0,
             refractive_index: 1.5,
             ..Default::default()
         });
         let w = World {
-            objects: vec![A, B],
+            objects: vec![a, b],
             ..Default::default()
         };
 
         let xs = vec![
-            Intersection::new(-0.9899, A),
-            Intersection::new(-0.4899, B),
-            Intersection::new(0.4899, B),
-            Intersection::new(0.9899, A),
+            Intersection::new(-0.9899, a),
+            Intersection::new(-0.4899, b),
+            Intersection::new(0.4899, b),
+            Intersection::new(0.9899, a),
         ];
         let comps = xs[2].precompute_hit(&r, &xs);
-        let color = w.refracted_color(&comps, 5);
+        let color = w.refracted_color(&comps, Path::new(5));
         assert_eq!(color, Color::new(0., 0.99887, 0.04721));
     }
 
@@ -581,7 +1147,7 @@ mod tests {
         );
         let i = Intersection::new(2.0_f32.sqrt(), floor);
         let comps = i.precompute_hit(&ray, &[i]);
-        let color = w.shade_hit(&comps, 5);
+        let color = w.shade_hit(&comps, Path::new(5));
         assert_eq!(color, Color::new(0.93642, 0.68642, 0.68642));
     }
 
@@ -593,6 +1159,7 @@ mod tests {
             reflective: 0.5,
             ..Default::default()
         })
+        .as_half_space()
         .set_transform(Matrix4::identity().translate(&Vector::new(0., -1., 0.)));
         let sphere = Sphere::default_with_material(Material {
             color: Color::new(1., 0., 0.),
@@ -610,7 +1177,326 @@ mod tests {
         );
         let i = vec![Intersection::new(2.0_f32.sqrt(), floor)];
         let comps = i[0].precompute_hit(&ray, &i);
-        let color = world.shade_hit(&comps, 5);
+        let color = world.shade_hit(&comps, Path::new(5));
         assert_eq!(color, Color::new(0.92590, 0.686_425, 0.686_425));
     }
+
+    #[test]
+    pub fn lights_are_grouped_by_name() {
+        let mut w = World::default();
+        w.add_light(
+            "key",
+            PointLight::new(Point::new(0., 5., 0.), Color::white()),
+        );
+        w.add_light(
+            "key",
+            PointLight::new(Point::new(5., 5., 0.), Color::white()),
+        );
+        assert_eq!(w.light_groups.len(), 2);
+        assert_eq!(w.light_groups[1].lights.len(), 2);
+        assert_eq!(w.lights().count(), 3);
+
+        w.light_groups[0].enabled = false;
+        assert_eq!(w.lights().count(), 2);
+    }
+
+    #[test]
+    pub fn shading_sums_contributions_of_all_lights() {
+        let mut w = World::default();
+        w.add_light(
+            "second",
+            PointLight::new(Point::new(-10., 10., -10.), Color::white()),
+        );
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let c = w.color_at(&r, 1);
+        assert_eq!(c, Color::new(0.76132, 0.95165, 0.57099));
+    }
+
+    #[test]
+    pub fn transparent_occluder_tints_shadow() {
+        let glass = Sphere::default_with_material(Material {
+            color: Color::new(1., 0., 0.),
+            transparency: 0.5,
+            ..Default::default()
+        });
+        let mut w = World::new(
+            PointLight::new(Point::new(0., 10., 0.), Color::white()),
+            vec![glass],
+        );
+        let (light, p) = (Point::new(0., 10., 0.), Point::new(0., -5., 0.));
+        assert!(w.is_shadowed(&light, &p));
+
+        w.settings.translucent_shadows = true;
+        assert_eq!(w.shadow_filter(&light, &p), Color::new(0.5, 0., 0.));
+        assert!(!w.is_shadowed(&light, &p));
+    }
+
+    #[test]
+    pub fn stochastic_transmittance_matches_exact_on_average() {
+        let panes = (0..50)
+            .map(|i| {
+                let pane: &'static dyn Shape = Plane::default_with_material(Material {
+                    transparency: 0.95,
+                    ..Default::default()
+                })
+                .set_transform(Matrix4::identity().translate(&Vector::new(0., i as f32, 0.)));
+                pane
+            })
+            .collect();
+        let mut w = World::new(
+            PointLight::new(Point::new(0., 100., 0.), Color::white()),
+            panes,
+        );
+        w.settings.translucent_shadows = true;
+        let (light, p) = (Point::new(0., 100., 0.), Point::new(0., -0.5, 0.));
+        let exact = w.shadow_filter(&light, &p);
+        assert!((exact.r - 0.95_f32.powi(50)).abs() < 1e-5);
+
+        w.settings.shadow_transmittance = Transmittance::Stochastic { samples: 16 };
+        let runs = 2000;
+        let mean = (0..runs)
+            .map(|_| w.shadow_filter(&light, &p).r)
+            .sum::<f32>()
+            / runs as f32;
+        assert!((mean - exact.r).abs() < 0.02, "{mean} vs {}", exact.r);
+    }
+
+    #[test]
+    pub fn stochastic_transmittance_stops_once_every_path_is_absorbed() {
+        let panes = (0..50)
+            .map(|i| {
+                let pane: &'static dyn Shape = Plane::default_with_material(Material {
+                    color: Color::black(),
+                    transparency: 1.,
+                    ..Default::default()
+                })
+                .set_transform(Matrix4::identity().translate(&Vector::new(0., i as f32, 0.)));
+                pane
+            })
+            .collect();
+        let mut w = World::new(
+            PointLight::new(Point::new(0., 100., 0.), Color::white()),
+            panes,
+        );
+        w.settings.translucent_shadows = true;
+        w.settings.shadow_transmittance = Transmittance::Stochastic { samples: 16 };
+        let (light, p) = (Point::new(0., 100., 0.), Point::new(0., -0.5, 0.));
+
+        stats::take();
+        assert_eq!(w.shadow_filter(&light, &p), Color::black());
+        let stats = stats::take();
+        assert_eq!(stats.rays, 1);
+        assert_eq!(stats.intersection_tests, 1);
+    }
+
+    #[test]
+    pub fn opaque_occluder_blocks_translucent_shadow() {
+        let mut w = World::default();
+        w.settings.translucent_shadows = true;
+        let filter = w.shadow_filter(&Point::new(-10., 10., -10.), &Point::new(10., -10., 10.));
+        assert_eq!(filter, Color::black());
+    }
+
+    #[test]
+    pub fn missed_rays_see_the_sky() {
+        let mut w = World::default();
+        let sky = Sky::new(std::f32::consts::PI / 4., 0., 2.5);
+        w.sky = Some(sky);
+        let up = Vector::new(0., 1., 0.);
+        let r = Ray::new(Point::new(0., 0., -5.), up);
+        assert_eq!(w.color_at(&r, 1), sky.radiance(&up));
+    }
+
+    #[test]
+    pub fn faded_plane_gives_way_to_the_sky_in_the_distance() {
+        let sky = Sky::new(std::f32::consts::PI / 4., 0., 2.5);
+        let floor = Plane::default_with_material(Material {
+            ambient: 1.,
+            diffuse: 0.,
+            specular: 0.,
+            ..Default::default()
+        })
+        .with_horizon_fade(10., 20.);
+        let mut w = World::new(
+            PointLight::new(Point::new(0., 10., 0.), Color::white()),
+            vec![floor],
+        );
+        w.sky = Some(sky);
+
+        let near = Ray::new(Point::new(0., 1., 0.), Vector::new(0., -1., 0.));
+        assert_eq!(w.color_at(&near, 1), Color::white());
+        let direction = Vector::new(0., -1., 30.).normalize();
+        let far = Ray::new(Point::new(0., 1., 0.), direction);
+        assert_eq!(w.color_at(&far, 1), sky.radiance(&direction));
+    }
+
+    #[test]
+    pub fn dropped_shape_rests_on_the_surface_below() {
+        let floor = Plane::static_default()
+            .set_transform(Matrix4::identity().translate(&Vector::new(0., -1., 0.)));
+        let w = World::new(
+            PointLight::new(Point::new(0., 10., 0.), Color::white()),
+            vec![floor],
+        );
+
+        let ball = Sphere::new(Point::new(3., 5., 0.), 0.5);
+        let landed = w.drop_onto(ball).unwrap();
+        assert_eq!(landed.map(|s| *s.get_id()), Some(*floor.get_id()));
+        assert_eq!(ball.get_bounds().min.y, -1.);
+        assert_eq!(
+            *ball.get_transform() * Point::zero(),
+            Point::new(3., -0.5, 0.)
+        );
+
+        let below = Sphere::new(Point::new(0., -5., 0.), 1.);
+        assert!(w.drop_onto(below).unwrap().is_none());
+    }
+
+    #[test]
+    pub fn overlapping_objects_are_reported_by_index() {
+        let w = World::new(
+            PointLight::new(Point::new(0., 10., 0.), Color::white()),
+            vec![
+                Sphere::new(Point::zero(), 1.),
+                Sphere::new(Point::new(5., 0., 0.), 1.),
+                Sphere::new(Point::new(1.5, 0., 0.), 1.),
+            ],
+        );
+        assert_eq!(w.find_overlaps(), vec![(0, 2)]);
+    }
+
+    #[test]
+    pub fn raycast_reports_the_nearest_surface() {
+        let w = World::default();
+        let hit = w
+            .raycast(Point::new(0., 0., -5.), Vector::new(0., 0., 2.))
+            .unwrap();
+        assert_eq!(hit.distance, 4.);
+        assert_eq!(hit.point, Point::new(0., 0., -1.));
+        assert_eq!(hit.normal, Vector::new(0., 0., -1.));
+        assert_eq!(hit.object.get_id(), w.objects[0].get_id());
+
+        // From inside the outer sphere the inner one is hit first.
+        let inner = w.raycast(Point::zero(), Vector::new(0., 1., 0.)).unwrap();
+        assert_eq!(inner.distance, 0.5);
+        assert_eq!(inner.normal, Vector::new(0., -1., 0.));
+
+        assert!(w
+            .raycast(Point::new(0., 0., -5.), Vector::new(0., 1., 0.))
+            .is_none());
+    }
+
+    #[test]
+    pub fn baked_probes_replace_the_ambient_term() {
+        let mut w = World::default();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let unlit = w.color_at(&ray, 1);
+
+        w.settings.irradiance_probes = Some(ProbeSettings {
+            resolution: 2,
+            samples: 4,
+        });
+        w.bake_probes().unwrap();
+        assert!(w.probes.is_some());
+        // The probes sit inside or at the edge of the spheres and see little light.
+        assert_ne!(w.color_at(&ray, 1), unlit);
+
+        w.settings.irradiance_probes = None;
+        w.bake_probes().unwrap();
+        assert_eq!(w.color_at(&ray, 1), unlit);
+    }
+
+    #[test]
+    pub fn reflection_and_refraction_depths_are_limited_separately() {
+        // A glass sphere between two facing mirrors.
+        let mirror = |z: f32| -> &'static dyn Shape {
+            Plane::default_with_material(Material {
+                reflective: 1.,
+                ..Default::default()
+            })
+            .set_transform(
+                Matrix4::identity()
+                    .rotate_x(PI / 2.)
+                    .translate(&Vector::new(0., 0., z)),
+            )
+        };
+        let glass = Sphere::glass(Point::zero(), 1.);
+        let mut w = World::new(
+            PointLight::new(Point::new(0., 10., 0.), Color::white()),
+            vec![mirror(-5.), mirror(5.), glass],
+        );
+        let r = Ray::new(Point::new(0., 0., -4.), Vector::new(0., 0., 1.));
+        let unlimited = w.color_at(&r, 10);
+
+        w.settings.max_reflection_depth = Some(1);
+        let one_bounce = w.color_at(&r, 10);
+        assert_ne!(one_bounce, unlimited);
+
+        w.settings.max_reflection_depth = Some(0);
+        w.settings.max_refraction_depth = Some(0);
+        let no_effects = w.color_at(&r, 10);
+        w.settings.max_reflection_depth = None;
+        w.settings.max_refraction_depth = None;
+        assert_eq!(no_effects, w.color_at(&r, 0));
+    }
+
+    #[test]
+    pub fn total_internal_reflection_is_counted() {
+        let glass = Sphere::glass(Point::zero(), 1.);
+        glass.common.material.reflective = 0.9;
+        let w = World::new(
+            PointLight::new(Point::new(0., 10., 0.), Color::white()),
+            vec![glass],
+        );
+        let h = 2.0_f32.sqrt() / 2.;
+
+        stats::take();
+        w.color_at(
+            &Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.)),
+            1,
+        );
+        assert_eq!(stats::take().internal_reflections, 0);
+
+        // Leaving the sphere at 45 degrees is past the critical angle of glass.
+        w.color_at(&Ray::new(Point::new(0., 0., h), Vector::new(0., 1., 0.)), 0);
+        assert_eq!(stats::take().internal_reflections, 1);
+    }
+
+    #[test]
+    pub fn shadow_cache_skips_repeated_shadow_rays() {
+        let mut w = World::default();
+        let rays = |w: &World| {
+            crate::shadow_cache::clear();
+            stats::take();
+            for x in [0., 0.001] {
+                w.color_at(
+                    &Ray::new(Point::new(x, 0., -5.), Vector::new(0., 0., 1.)),
+                    0,
+                );
+            }
+            stats::take().rays
+        };
+        assert_eq!(rays(&w), 4);
+        w.settings.shadow_cache = Some(0.01);
+        assert_eq!(rays(&w), 3);
+    }
+
+    #[test]
+    pub fn linked_lights_only_touch_their_objects() {
+        let hero = Sphere::new(Point::zero(), 1.);
+        let blocker = Sphere::new(Point::new(0., 0., -3.), 0.5);
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+        let mut w = World::new(light.clone(), vec![hero, blocker]);
+        let front = Ray::new(Point::new(0., 0., -1.2), Vector::new(0., 0., 1.));
+        let blocker_ray = Ray::new(Point::new(0., 5., -3.), Vector::new(0., -1., 0.));
+        let shadowed = w.color_at(&front, 0);
+        let blocker_lit = w.color_at(&blocker_ray, 0);
+
+        // The blocker no longer casts a shadow from the light, nor is lit by it.
+        w.light_groups[0].lights[0] = light.with_links(LightLinks::Only(vec![*hero.get_id()]));
+        assert_ne!(w.color_at(&front, 0), shadowed);
+        assert!(w.color_at(&front, 0).r > shadowed.r);
+        assert_eq!(w.color_at(&blocker_ray, 0), Color::black());
+        assert_ne!(blocker_lit, Color::black());
+    }
 }